@@ -0,0 +1,194 @@
+use crate::*;
+use std::{
+  env, fmt, fs, io,
+  path::{Component, Path, PathBuf},
+  process,
+};
+
+#[derive(Debug)]
+pub enum Error {
+  Io(PathBuf, io::Error),
+  Diagnostics(PathBuf, String, Vec<Diagnostic>),
+}
+
+pub fn compile_str(source: &str) -> Result<TokenStream, Vec<Diagnostic>> {
+  let mut program = syn::parse_str::<Program>(source).map_err(|err| {
+    err
+      .into_iter()
+      .map(|err| Diagnostic {
+        span: err.span(),
+        message: err.to_string(),
+      })
+      .collect::<Vec<_>>()
+  })?;
+  if let Some(output) = program.import_modules() {
+    return Ok(output);
+  }
+  collect_diagnostics(|| {
+    program.expand();
+    program.check();
+    program.fuse();
+    program.compile()
+  })
+}
+
+pub fn compile_file(path: impl AsRef<Path>) -> Result<String, Error> {
+  let path = path.as_ref();
+  let source = fs::read_to_string(path).map_err(|err| Error::Io(path.to_owned(), err))?;
+  match compile_str(&source) {
+    Ok(output) => Ok(output.to_string()),
+    Err(diagnostics) => Err(Error::Diagnostics(path.to_owned(), source, diagnostics)),
+  }
+}
+
+pub fn build(path: impl AsRef<Path>) {
+  let path = path.as_ref();
+  println!("cargo:rerun-if-changed={}", path.display());
+  let output = match compile_file(path) {
+    Ok(output) => output,
+    Err(err) => fail(err),
+  };
+  let out_dir = PathBuf::from(env::var_os("OUT_DIR").expect("OUT_DIR is not set"));
+  let manifest_dir =
+    PathBuf::from(env::var_os("CARGO_MANIFEST_DIR").expect("CARGO_MANIFEST_DIR is not set"));
+  let out_path = out_path(&out_dir, &manifest_dir, path).unwrap_or_else(|err| fail(err));
+  let written =
+    fs::create_dir_all(out_path.parent().unwrap()).and_then(|_| fs::write(&out_path, output));
+  if let Err(err) = written {
+    fail(Error::Io(out_path, err));
+  }
+}
+
+// Mirrors the source's path within the package, so that `src/a/x.inet` is
+// written to `$OUT_DIR/src/a/x.rs` and sources sharing a file name don't
+// overwrite each other.
+fn out_path(out_dir: &Path, manifest_dir: &Path, path: &Path) -> Result<PathBuf, Error> {
+  let relative = path.strip_prefix(manifest_dir).unwrap_or(path);
+  if !relative
+    .components()
+    .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+  {
+    let err = io::Error::new(
+      io::ErrorKind::InvalidInput,
+      "expected a path inside the package",
+    );
+    return Err(Error::Io(path.to_owned(), err));
+  }
+  Ok(out_dir.join(relative).with_extension("rs"))
+}
+
+fn fail(err: Error) -> ! {
+  eprintln!("{}", err);
+  process::exit(1)
+}
+
+impl fmt::Display for Error {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Error::Io(path, err) => write!(f, "error: {}: {}", path.display(), err),
+      Error::Diagnostics(path, source, diagnostics) => {
+        let lines = source.lines().collect::<Vec<_>>();
+        for diagnostic in diagnostics {
+          let start = diagnostic.span.start();
+          let line = lines.get(start.line.wrapping_sub(1)).copied().unwrap_or("");
+          let gutter = " ".repeat(start.line.to_string().len());
+          writeln!(f, "error: {}", diagnostic.message)?;
+          writeln!(
+            f,
+            "{}--> {}:{}:{}",
+            gutter,
+            path.display(),
+            start.line,
+            start.column + 1
+          )?;
+          writeln!(f, "{} |", gutter)?;
+          writeln!(f, "{} | {}", start.line, line)?;
+          writeln!(f, "{} | {}^", gutter, " ".repeat(start.column))?;
+        }
+        Ok(())
+      }
+    }
+  }
+}
+
+impl std::error::Error for Error {}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn temp_file(name: &str, source: &str) -> PathBuf {
+    let path = env::temp_dir().join(format!("{}-{}.inet", name, process::id()));
+    fs::write(&path, source).unwrap();
+    path
+  }
+
+  #[test]
+  fn compiles_a_module() {
+    let output = compile_str("pub struct Zero(+Zero);").unwrap().to_string();
+    assert!(output.contains("pub struct Interactions"), "{output}");
+    assert!(output.contains("struct Zero"), "{output}");
+  }
+
+  #[test]
+  fn reports_parse_errors() {
+    let diagnostics = compile_str("pub struct Zero(+Zero").unwrap_err();
+    assert!(!diagnostics.is_empty());
+  }
+
+  #[test]
+  fn compiles_files() {
+    let path = temp_file("compiles_files", "pub struct Zero(+Zero);\n");
+    let output = compile_file(&path);
+    fs::remove_file(&path).unwrap();
+    assert_eq!(
+      output.unwrap(),
+      compile_str("pub struct Zero(+Zero);").unwrap().to_string()
+    );
+  }
+
+  #[test]
+  fn reports_missing_files() {
+    let path = env::temp_dir().join("reports_missing_files.inet");
+    assert!(matches!(compile_file(&path), Err(Error::Io(p, _)) if p == path));
+  }
+
+  #[test]
+  fn mirrors_sources_in_the_output_dir() {
+    let out = Path::new("/out");
+    let package = Path::new("/package");
+    let out_path = |path: &str| out_path(out, package, Path::new(path)).ok();
+    assert_eq!(
+      out_path("src/a/x.inet"),
+      Some(PathBuf::from("/out/src/a/x.rs"))
+    );
+    assert_eq!(
+      out_path("/package/src/b/x.inet"),
+      Some(PathBuf::from("/out/src/b/x.rs"))
+    );
+    assert_eq!(out_path("../x.inet"), None);
+    assert_eq!(out_path("/elsewhere/x.inet"), None);
+  }
+
+  #[test]
+  fn points_diagnostics_at_their_line() {
+    let source = "pub struct Pair(+Pair, $u64, $u64);\n\
+                  pub struct Zero(+Zero);\n\
+                  \n\
+                  extern struct F(-Pair, +Zero);\n";
+    let path = temp_file("points_diagnostics_at_their_line", source);
+    let err = compile_file(&path).unwrap_err();
+    fs::remove_file(&path).unwrap();
+    let line = source.lines().nth(3).unwrap();
+    let column = line.find("Pair").unwrap();
+    let expected = format!(
+      "error: `Pair` cannot cross to the host: expected an agent with one port and one payload\n \
+       --> {}:4:{}\n  |\n4 | {}\n  | {}^\n",
+      path.display(),
+      column + 1,
+      line,
+      " ".repeat(column),
+    );
+    assert!(err.to_string().starts_with(&expected), "{err}");
+  }
+}
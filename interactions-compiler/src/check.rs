@@ -0,0 +1,212 @@
+use crate::*;
+
+impl Program {
+  pub fn check(&self) {
+    self.ensure_unique(
+      self
+        .items
+        .iter()
+        .filter_map(Item::as_struct)
+        .map(|x| &x.name),
+    );
+    self.check_imports();
+    self.check_field_attrs();
+
+    for item in &self.items {
+      match item {
+        Item::Impl(i) => self.ensure_used_twice(i.all_idents()),
+        Item::Fn(f) => {
+          self.ensure_used_twice(f.all_idents());
+          for future in f.net.stmts.iter().filter_map(NetStmt::awaited) {
+            emit_error!(future.span(), "`.await` is only supported in rule bodies");
+          }
+        }
+        _ => {}
+      }
+    }
+  }
+
+  fn check_imports(&self) {
+    let mut names = BTreeMap::new();
+    for s in self.items.iter().filter_map(Item::as_struct) {
+      names.insert(s.name.to_string(), "a local agent".to_string());
+    }
+    let mut modules = BTreeSet::new();
+    for u in self.uses() {
+      if let Some(module) = &u.module {
+        if !modules.insert(module.to_string()) {
+          emit_error!(
+            module.span(),
+            "module `{}` is imported more than once",
+            module
+          );
+        }
+      }
+      let m = self.module(&u.path);
+      for a in &u.agents {
+        if m.is_some_and(|m| !m.program.exports(&a.name)) {
+          emit_error!(
+            a.name.span(),
+            "`{}` is not a public agent of `{}`",
+            a.name,
+            self.path_str(&u.path)
+          );
+        }
+        let ident = a.ident();
+        if let Some(prev) = names.insert(ident.to_string(), "another import".to_string()) {
+          emit_error!(
+            ident.span(),
+            "`{}` is ambiguous: it is also {}",
+            ident,
+            prev
+          );
+        }
+      }
+    }
+    for u in self.uses() {
+      let (Some(glob), Some(m)) = (&u.glob, self.module(&u.path)) else {
+        continue;
+      };
+      let path = self.path_str(&u.path);
+      for s in m.program.exported() {
+        let glob_import = format!("imported by `{}::*`", path);
+        if let Some(prev) = names.insert(s.name.to_string(), glob_import) {
+          emit_error!(
+            glob.span(),
+            "`{}` is ambiguous: `{}::*` imports it, but it is also {}",
+            s.name,
+            path,
+            prev
+          );
+        }
+      }
+    }
+    let mut srcs = vec![];
+    for item in &self.items {
+      match item {
+        Item::Impl(i) => {
+          for a in i.agents() {
+            srcs.extend(&a.src);
+            for f in a.fields.values() {
+              if let ImplAgentField::Agent(a) = f {
+                self.net_agent_srcs(a, &mut srcs);
+              }
+            }
+          }
+          for a in &i.net.agents {
+            self.net_agent_srcs(a, &mut srcs);
+          }
+        }
+        Item::Fn(f) => {
+          for a in f.net.agents.iter() {
+            self.net_agent_srcs(a, &mut srcs);
+          }
+        }
+        _ => {}
+      }
+    }
+    for src in srcs {
+      if !modules.contains(&src.to_string()) {
+        emit_error!(src.span(), "module `{}` is not imported", src);
+      }
+    }
+  }
+
+  fn check_field_attrs(&self) {
+    let structs = self.items.iter().filter_map(Item::as_struct);
+    let fields = structs.flat_map(|s| s.fields.attrs());
+    let fns = self.items.iter().filter_map(Item::as_fn);
+    let parts = fns.flat_map(|f| f.parts.iter().map(|p| &p.attrs[..]));
+    for attr in fields.chain(parts).flatten() {
+      if attr.path().is_ident("cfg") {
+        emit_error!(attr.span(), "`#[cfg]` is not supported on agent fields");
+      }
+    }
+  }
+
+  fn net_agent_srcs<'a>(&self, agent: &'a NetAgent, srcs: &mut Vec<&'a Ident>) {
+    srcs.extend(&agent.src);
+    for f in agent.fields.values() {
+      if let NetAgentField::Agent(a) = f {
+        self.net_agent_srcs(a, srcs);
+      }
+    }
+  }
+
+  fn ensure_unique<'a, I: Iterator<Item = &'a Ident>>(&self, idents: I) {
+    let mut seen = BTreeSet::new();
+    for ident in idents {
+      if !seen.insert(ident) {
+        abort!(ident.span(), "duplicate identifier")
+      }
+    }
+  }
+
+  fn ensure_used_twice<'a, I: Iterator<Item = &'a Ident>>(&self, idents: I) {
+    let mut all = BTreeSet::new();
+    let mut once = BTreeSet::new();
+    for ident in idents {
+      if !all.insert(ident) {
+        if !once.remove(ident) {
+          emit_error!(ident.span(), "identifier used more than twice");
+        }
+      } else {
+        once.insert(ident);
+      }
+    }
+    for ident in once {
+      emit_error!(ident.span(), "identifier used only once");
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::*;
+
+  fn errors(source: &str) -> Vec<String> {
+    match compile_str(source) {
+      Ok(_) => vec![],
+      Err(diagnostics) => diagnostics.into_iter().map(|d| d.message).collect(),
+    }
+  }
+
+  const MODULES: &str = "@module a { pub struct Z(+Z); struct Hidden(+Hidden); } \
+                         @module b { pub struct Z(+Z); pub struct S(+S, -Z); }";
+
+  #[test]
+  fn accepts_unambiguous_imports() {
+    assert_eq!(
+      errors(&format!(
+        "{MODULES} use a::*; use b::S; struct P(+P, -Z, -S);"
+      )),
+      Vec::<String>::new()
+    );
+  }
+
+  #[test]
+  fn rejects_names_shadowed_by_globs() {
+    assert_eq!(
+      errors(&format!(
+        "{MODULES} use a::*; use b::Z as Z; struct P(+P, -Z);"
+      )),
+      ["`Z` is ambiguous: `a::*` imports it, but it is also another import"]
+    );
+    assert_eq!(
+      errors(&format!("{MODULES} use a::*; use b::*; struct P(+P, -Z);")),
+      ["`Z` is ambiguous: `b::*` imports it, but it is also imported by `a::*`"]
+    );
+    assert_eq!(
+      errors(&format!("{MODULES} use a::*; struct Z(+Z);")),
+      ["`Z` is ambiguous: `a::*` imports it, but it is also a local agent"]
+    );
+  }
+
+  #[test]
+  fn rejects_private_imports() {
+    assert_eq!(
+      errors(&format!("{MODULES} use a::Hidden; struct P(+P, -Hidden);")),
+      ["`Hidden` is not a public agent of `a`"]
+    );
+  }
+}
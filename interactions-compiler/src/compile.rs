@@ -0,0 +1,172 @@
+mod attrs;
+mod effects;
+mod fields;
+mod fns;
+mod impls;
+mod net;
+mod structs;
+mod uses;
+
+pub use fields::*;
+pub use fns::*;
+pub use impls::*;
+pub use net::*;
+pub use structs::*;
+pub use uses::*;
+
+use crate::*;
+use proc_macro2::{Group, Span, TokenTree};
+
+impl Program {
+  pub fn compile(&self) -> TokenStream {
+    let crate_path = &self.crate_path();
+
+    let (impls, includes, paths) = self.compile_uses();
+    let effects = self.compile_effects();
+    let struct_defs = self.compile_structs();
+    let host_fn_impls = self.compile_host_fn_impls(&includes);
+    let fn_defs = self.compile_fns(&includes);
+    let rules = self.compile_impls();
+    let source_macro = self.compile_source_macro();
+
+    let kind_count = self.kind_count(self.items.iter().filter_map(Item::as_struct));
+
+    let rule_names = (0..rules.len())
+      .map(|i| format_ident!("__rule_{}", i))
+      .collect::<Vec<_>>();
+    let rule_cfgs = rules
+      .iter()
+      .map(|rule| rule.cfg.as_ref().map(|pred| quote!(#[cfg(#pred)])))
+      .collect::<Vec<_>>();
+    let rule_not_cfgs = rules.iter().map(|rule| {
+      rule
+        .cfg
+        .as_ref()
+        .map(|pred| quote!(#[cfg(not(#pred))] let entry = None;))
+    });
+    let rule_count = rules.len();
+    let rule_kinds = rules.iter().map(|rule| &rule.kinds);
+    let rule_params = rules.iter().map(|rule| &rule.params);
+    let rule_bodies = rules.iter().map(|rule| &rule.body);
+
+    let output = quote!(
+        #[derive(Clone, Copy)]
+        #[allow(dead_code)]
+        pub struct Interactions;
+
+        #effects
+        #impls
+        #(#struct_defs)*
+        #host_fn_impls
+        #fn_defs
+        #source_macro
+
+        impl self::Use for Interactions {
+          const KIND_START: u32 = 0 #(+ <Self as #paths::Use>::KIND_COUNT)*;
+        }
+
+        impl<N: #crate_path::Net> #crate_path::Interactions<N> for Interactions
+        where
+          N::Effects: self::Effects,
+        {
+          #[inline(always)]
+          fn reduce(
+            &self,
+            net: &mut N,
+            a: (#crate_path::Kind, #crate_path::Addr),
+            b: (#crate_path::Kind, #crate_path::Addr),
+          ) -> bool {
+            const LEN: usize =
+              (<Interactions as self::Use>::KIND_START + <Interactions as self::Use>::KIND_COUNT) as usize;
+            // Borrowed so that the table is promoted to a static rather than
+            // copied onto the stack on every call.
+            let rules = &const {
+              #crate_path::RuleTable::<Self, N, LEN>::new()
+                .with_pairs(&<Interactions as self::Rules<N>>::RULES)
+                #(.with_pairs(&<Interactions as #paths::Rules<N>>::RULES))*
+                .with_wildcards(&<Interactions as self::Rules<N>>::RULES)
+                #(.with_wildcards(&<Interactions as #paths::Rules<N>>::RULES))*
+            };
+            match rules.get(a.0, b.0) {
+              Some(rule) => {
+                rule(self, net, a, b);
+                true
+              }
+              None => false,
+            }
+          }
+        }
+
+        #[allow(non_upper_case_globals, non_snake_case, dead_code)]
+        pub trait Use: Sized + Copy + 'static #(+ #paths::Use)* {
+          const KIND_START: u32;
+          const KIND_COUNT: u32 = #kind_count;
+          #(
+            #rule_cfgs
+            #[doc(hidden)]
+            #[allow(clippy::match_single_binding, clippy::nonminimal_bool, clippy::identity_op)]
+            fn #rule_names<N: #crate_path::Net>(&self, net: &mut N, #rule_params)
+            where
+              N::Effects: self::Effects,
+            {
+              #includes
+              #rule_bodies
+            }
+          )*
+        }
+
+        #[allow(dead_code)]
+        pub trait Rules<N: #crate_path::Net>: Sized {
+          const RULES: [#crate_path::RuleEntry<Self, N>; #rule_count];
+        }
+
+        impl<I: self::Use, N: #crate_path::Net> self::Rules<N> for I
+        where
+          N::Effects: self::Effects,
+        {
+          const RULES: [#crate_path::RuleEntry<Self, N>; #rule_count] = {
+            #includes
+            [#({
+              #rule_cfgs
+              let entry: #crate_path::RuleEntry<Self, N> =
+                Some((#rule_kinds, <I as self::Use>::#rule_names::<N>));
+              #rule_not_cfgs
+              entry
+            }),*]
+          };
+        }
+    );
+    self.resolve_at_call_site(output)
+  }
+
+  // A module that imports others is compiled again from inside the
+  // `__interactions_source!` of the last one, so the names generated here
+  // would be hygienic to that macro rather than to the source they are used
+  // in. The request passes along a `$` spanned at the original invocation,
+  // and every token is resolved there instead.
+  fn resolve_at_call_site(&self, tokens: TokenStream) -> TokenStream {
+    match self.call_site {
+      Some(at) => self.resolve_at(tokens, at),
+      None => tokens,
+    }
+  }
+
+  fn resolve_at(&self, tokens: TokenStream, at: Span) -> TokenStream {
+    tokens
+      .into_iter()
+      .map(|mut token| {
+        if let TokenTree::Group(group) = &token {
+          let mut new_group = Group::new(group.delimiter(), self.resolve_at(group.stream(), at));
+          new_group.set_span(group.span().resolved_at(at));
+          return TokenTree::Group(new_group);
+        }
+        token.set_span(token.span().resolved_at(at));
+        token
+      })
+      .collect()
+  }
+
+  pub fn crate_path(&self) -> TokenStream {
+    quote!(::internets_nets)
+  }
+}
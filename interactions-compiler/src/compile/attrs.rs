@@ -0,0 +1,46 @@
+use crate::*;
+use syn::{Attribute, Meta};
+
+impl Program {
+  pub fn cfg_attrs<'a>(&self, attrs: &'a [Attribute]) -> Vec<&'a Attribute> {
+    attrs
+      .iter()
+      .filter(|attr| attr.path().is_ident("cfg"))
+      .collect()
+  }
+
+  pub fn rule_attrs<'a>(&self, attrs: &'a [Attribute]) -> Vec<&'a Attribute> {
+    attrs
+      .iter()
+      .filter(|attr| !attr.path().is_ident("doc"))
+      .collect()
+  }
+
+  pub fn cfg_predicate(&self, attrs: &[&Attribute]) -> Option<TokenStream> {
+    let preds = attrs
+      .iter()
+      .filter(|attr| attr.path().is_ident("cfg"))
+      .filter_map(|attr| match &attr.meta {
+        Meta::List(list) => Some(&list.tokens),
+        _ => None,
+      })
+      .collect::<Vec<_>>();
+    match &preds[..] {
+      [] => None,
+      [pred] => Some(quote!(#pred)),
+      preds => Some(quote!(all(#(#preds),*))),
+    }
+  }
+
+  pub fn kind_count<'a>(&self, structs: impl Iterator<Item = &'a Struct>) -> TokenStream {
+    let mut count = 0u32;
+    let mut gated = vec![];
+    for s in structs {
+      match self.cfg_predicate(&self.cfg_attrs(&s.attrs)) {
+        Some(pred) => gated.push(quote!((cfg!(#pred) as u32))),
+        None => count += 1,
+      }
+    }
+    quote!(#count #(+ #gated)*)
+  }
+}
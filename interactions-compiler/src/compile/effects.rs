@@ -0,0 +1,128 @@
+use crate::*;
+use quote::ToTokens;
+
+impl Program {
+  pub fn compile_effects(&self) -> TokenStream {
+    let mut bounds = vec![];
+    let mut traits = vec![];
+    let uses_effects = self.uses_effects();
+    for e in self.items.iter().filter_map(Item::as_effect) {
+      let attrs = &e.attrs;
+      let vis = &e.vis;
+      let name = &e.name;
+      let items = &e.items;
+      traits.push(quote!(
+        #(#attrs)*
+        #vis trait #name {
+          #(#items)*
+        }
+      ));
+      if uses_effects {
+        bounds.push(quote!(#name));
+      }
+    }
+    if self
+      .items
+      .iter()
+      .filter_map(Item::as_struct)
+      .any(|s| s.external.is_some())
+    {
+      let crate_path = self.crate_path();
+      bounds.push(quote!(#crate_path::Host));
+    }
+    let mut modules = BTreeSet::new();
+    for u in self.items.iter().filter_map(Item::as_use) {
+      let path = &u.path;
+      if modules.insert(quote!(#path).to_string()) {
+        bounds.push(quote!(#path::Effects));
+      }
+    }
+    let supertraits = (!bounds.is_empty()).then(|| quote!(: #(#bounds)+*));
+    quote!(
+      #(#traits)*
+      #[allow(dead_code)]
+      pub trait Effects #supertraits {}
+      impl<T: ?Sized #(+ #bounds)*> Effects for T {}
+    )
+  }
+
+  pub fn compile_effect_access(&self, tokens: TokenStream) -> TokenStream {
+    if !self.mentions_effects(tokens.clone()) {
+      return tokens;
+    }
+    let crate_path = self.crate_path();
+    quote!({
+      let effects = #crate_path::Net::effects(net);
+      #tokens
+    })
+  }
+
+  // Effect traits only constrain the net's effects when one of the module's
+  // own rules or fns reaches for `effects`.
+  fn uses_effects(&self) -> bool {
+    let impls = self.items.iter().filter_map(Item::as_impl).map(|i| &i.net);
+    let fns = self.items.iter().filter_map(Item::as_fn).map(|f| &f.net);
+    impls.chain(fns).any(|net| self.net_uses_effects(net))
+  }
+
+  fn net_uses_effects(&self, net: &Net) -> bool {
+    net.stmts.iter().any(|stmt| {
+      let tokens = match &stmt.kind {
+        NetStmtKind::Let(local) => local.to_token_stream(),
+        NetStmtKind::Block(block) => block.to_token_stream(),
+      };
+      self.mentions_effects(tokens)
+    }) || net
+      .fused
+      .iter()
+      .flat_map(|fusion| &fusion.arms)
+      .any(|arm| self.net_uses_effects(&arm.net))
+  }
+
+  fn mentions_effects(&self, tokens: TokenStream) -> bool {
+    tokens.into_iter().any(|token| match token {
+      proc_macro2::TokenTree::Ident(ident) => ident == "effects",
+      proc_macro2::TokenTree::Group(group) => self.mentions_effects(group.stream()),
+      _ => false,
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::*;
+
+  fn effects_trait(source: &str) -> String {
+    let output = compile_str(source).unwrap().to_string();
+    let start = output.find("pub trait Effects").unwrap();
+    output[start..start + output[start..].find('}').unwrap() + 1].to_string()
+  }
+
+  #[test]
+  fn requires_effects_used_by_rules() {
+    let effects = effects_trait(
+      "
+      pub effect Output { fn print(&mut self, value: u64); }
+      pub struct U64(+U64, $u64);
+      pub struct Print(-U64);
+      impl Print(_) for U64(_, $n) {
+        { effects.print(n); }
+      }
+      ",
+    );
+    assert_eq!(effects, "pub trait Effects : Output { }");
+  }
+
+  #[test]
+  fn skips_effects_no_rule_uses() {
+    let effects = effects_trait(
+      "
+      pub effect Output { fn print(&mut self, value: u64); }
+      pub struct U64(+U64, $u64);
+      pub struct Erase(-U64);
+      impl Erase(_) for U64(_, $_) {}
+      ",
+    );
+    assert_eq!(effects, "pub trait Effects { }");
+  }
+}
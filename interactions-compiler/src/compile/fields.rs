@@ -0,0 +1,48 @@
+use crate::*;
+
+impl Program {
+  pub fn compile_fields<T>(
+    &self,
+    fields: &Fields<T>,
+    prefix: TokenStream,
+    values: impl Iterator<Item = TokenStream>,
+  ) -> TokenStream {
+    match fields {
+      Fields::Unnamed(_) => quote_spanned!(fields.span()=> (#(#prefix #values),*)),
+      Fields::Named(f) => {
+        let entries = f
+          .entries
+          .iter()
+          .map(|x| &x.key)
+          .zip(values)
+          .map(|(k, v)| quote!(#prefix #k: #v));
+        quote_spanned!(fields.span()=> {#(#entries),*})
+      }
+    }
+  }
+
+  pub fn compile_field_defs<T>(
+    &self,
+    fields: &Fields<T>,
+    values: impl Iterator<Item = TokenStream>,
+  ) -> TokenStream {
+    match fields {
+      Fields::Unnamed(f) => {
+        let entries = f
+          .attrs
+          .iter()
+          .zip(values)
+          .map(|(attrs, v)| quote!(#(#attrs)* pub #v));
+        quote_spanned!(fields.span()=> (#(#entries),*))
+      }
+      Fields::Named(f) => {
+        let entries = f.entries.iter().zip(values).map(|(x, v)| {
+          let attrs = &x.attrs;
+          let k = &x.key;
+          quote!(#(#attrs)* pub #k: #v)
+        });
+        quote_spanned!(fields.span()=> {#(#entries),*})
+      }
+    }
+  }
+}
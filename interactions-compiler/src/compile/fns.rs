@@ -12,11 +12,11 @@ impl Program {
 
   fn compile_fn(&self, f: &Fn, includes: &TokenStream) -> TokenStream {
     let crate_path = self.crate_path();
-    let parts = f.parts.iter().map(|FnPart { ty: p, .. }| match p {
-      StructField::Port(_) => quote!(pub &'a mut #crate_path::LinkHalf),
-      StructField::Payload(PayloadType { ty, .. }) => quote!(pub #ty),
+    let parts = f.parts.iter().map(|FnPart { attrs, ty: p, .. }| match p {
+      StructField::Port(_) => quote!(#(#attrs)* pub &'a mut #crate_path::LinkHalf),
+      StructField::Payload(PayloadType { ty, .. }) => quote!(#(#attrs)* pub #ty),
     });
-    let binds = f.parts.iter().map(|FnPart { name, ty: p }| {
+    let binds = f.parts.iter().map(|FnPart { name, ty: p, .. }| {
       let (_, e1) = self.edge_idents(name);
       match p {
         StructField::Port(_) => quote!(#e1),
@@ -38,19 +38,26 @@ impl Program {
       .map(|_| quote!('a,));
     let mut net = self.new_net_compilation(quote!(I), quote!(interactions));
     self.compile_net(&f.net, &mut net);
+    net.tail.extend(sets);
     let net = self.finish_net_compilation(net);
     let name = &f.name;
     let vis = &f.vis;
+    let attrs = &f.attrs;
+    let cfgs = self.cfg_attrs(&f.attrs);
     quote!(
-      #[allow(non_camel_case_types)]
+      #(#attrs)*
+      #[allow(non_camel_case_types, dead_code)]
       #vis struct #name<#lifetime>(#(#parts),*);
-      impl<#lifetime I: self::Use> #crate_path::Construct<I> for #name<#lifetime> {
+      #(#cfgs)*
+      impl<#lifetime I: self::Use, N: #crate_path::Net> #crate_path::Construct<I, N> for #name<#lifetime>
+      where
+        N::Effects: self::Effects,
+      {
         #[inline(always)]
-        fn construct<N: #crate_path::Net>(self, net: &mut N, interactions: &I) {
+        fn construct(self, net: &mut N, interactions: &I) {
           #includes
           let #name(#(#binds),*) = self;
           #net
-          #(#sets)*
         }
       }
     )
@@ -0,0 +1,294 @@
+use crate::*;
+
+use super::{Consumed, NetCompilation};
+use syn::Attribute;
+
+pub struct CompiledRule {
+  pub cfg: Option<TokenStream>,
+  pub kinds: TokenStream,
+  pub params: TokenStream,
+  pub body: TokenStream,
+}
+
+impl Program {
+  pub fn compile_impls(&self) -> Vec<CompiledRule> {
+    let impls = self.items.iter().filter_map(Item::as_impl);
+    let pairs = impls
+      .clone()
+      .filter_map(|i| Some((i, &i.left, i.right.as_ref()?)));
+    let mut rules = collect_multi_map(pairs.flat_map(|(i, a, b)| {
+      [
+        ((&a.src, &a.name, &b.src, &b.name), (a, b, i)),
+        ((&b.src, &b.name, &a.src, &a.name), (b, a, i)),
+      ]
+    }))
+    .into_iter()
+    .map(|(k, v)| self.compile_impl_group(k, v))
+    .collect::<Vec<_>>();
+    rules.extend(
+      collect_multi_map(
+        impls
+          .filter(|i| i.right.is_none())
+          .map(|i| ((&i.left.src, &i.left.name), i)),
+      )
+      .into_iter()
+      .map(|(k, v)| self.compile_wildcard_group(k, v)),
+    );
+    rules
+  }
+
+  fn compile_impl_group(
+    &self,
+    (a_src, a_name, b_src, b_name): (&Option<Ident>, &Ident, &Option<Ident>, &Ident),
+    impls: Vec<(&ImplAgent, &ImplAgent, &Impl)>,
+  ) -> CompiledRule {
+    let crate_path = self.crate_path();
+    let a_src = self.quote_src(&a_src);
+    let b_src = self.quote_src(&b_src);
+    let preds = impls
+      .iter()
+      .map(|(_, _, i)| self.cfg_predicate(&self.impl_attrs(i)))
+      .collect::<Option<Vec<_>>>();
+    let arms = impls
+      .into_iter()
+      .map(|(a, b, i)| self.compile_impl(a, b, i));
+    let a_kind_path = quote!(<#a_src #a_name<_> as #crate_path::GetKind<Self>>::KIND);
+    let b_kind_path = quote!(<#b_src #b_name<_> as #crate_path::GetKind<Self>>::KIND);
+    CompiledRule {
+      cfg: preds.map(|preds| quote!(any(#(#preds),*))),
+      kinds: quote!(#crate_path::RuleKinds::Pair(#a_kind_path, #b_kind_path)),
+      params: quote!(
+        (_, a_addr): (#crate_path::Kind, #crate_path::Addr),
+        (_, b_addr): (#crate_path::Kind, #crate_path::Addr),
+      ),
+      body: quote!(
+        match (
+          <#a_src #a_name<_> as #crate_path::Destruct>::destruct(net, a_addr),
+          <#b_src #b_name<_> as #crate_path::Destruct>::destruct(net, b_addr),
+        ) {
+          #(#arms)*
+        }
+      ),
+    }
+  }
+
+  fn compile_wildcard_group(
+    &self,
+    (a_src, a_name): (&Option<Ident>, &Ident),
+    impls: Vec<&Impl>,
+  ) -> CompiledRule {
+    let crate_path = self.crate_path();
+    let a_src = self.quote_src(a_src);
+    let preds = impls
+      .iter()
+      .map(|i| self.cfg_predicate(&self.impl_attrs(i)))
+      .collect::<Option<Vec<_>>>();
+    let arms = impls.into_iter().map(|i| self.compile_wildcard_impl(i));
+    let a_kind_path = quote!(<#a_src #a_name<_> as #crate_path::GetKind<Self>>::KIND);
+    CompiledRule {
+      cfg: preds.map(|preds| quote!(any(#(#preds),*))),
+      kinds: quote!(#crate_path::RuleKinds::Wildcard(#a_kind_path)),
+      params: quote!(
+        (x, a_addr): (#crate_path::Kind, #crate_path::Addr),
+        (y, b_addr): (#crate_path::Kind, #crate_path::Addr),
+      ),
+      body: quote!(
+        let (a_addr, (b_kind, b_addr)) = if x == #a_kind_path {
+          (a_addr, (y, b_addr))
+        } else {
+          (b_addr, (x, a_addr))
+        };
+        let partner = if b_addr.is_null() {
+          #crate_path::LinkHalf::Kind(b_kind)
+        } else if b_addr.is_immediate() {
+          #crate_path::LinkHalf::Immediate(b_kind, b_addr.as_immediate())
+        } else {
+          #crate_path::LinkHalf::Port(b_addr, #crate_path::PortMode::Principal)
+        };
+        match <#a_src #a_name<_> as #crate_path::Destruct>::destruct(net, a_addr) {
+          #(#arms)*
+        }
+        <#a_src #a_name<_> as #crate_path::Destruct>::free(net, a_addr);
+      ),
+    }
+  }
+
+  fn compile_wildcard_impl(&self, i: &Impl) -> TokenStream {
+    let a = &i.left;
+    let a_src = self.quote_src(&a.src);
+    let a_name = &a.name;
+    let cond = i.cond.as_ref().map(|x| quote!(if #x)).unwrap_or_default();
+    let attrs = self.impl_attrs(i);
+
+    let mut net = self.new_net_compilation(quote!(Self), quote!(self));
+    let principal = a
+      .fields
+      .values()
+      .position(|x| !matches!(x, ImplAgentField::Payload(_)));
+    let partner = match principal.map(|idx| a.fields.values().nth(idx).unwrap()) {
+      Some(ImplAgentField::Port(ident)) => {
+        net.seen.insert(ident);
+        self.edge_idents(ident).0
+      }
+      _ => {
+        emit_error!(
+          a.name.span(),
+          "the principal port of a wildcard rule must be named"
+        );
+        format_ident!("partner")
+      }
+    };
+    let a_fields = self.impl_agent_fields(a, &mut net, principal, None);
+    self.compile_net(&i.net, &mut net);
+    let net = self.finish_net_compilation(net);
+
+    let a_pat = quote_spanned!(a.name.span()=> #a_src #a_name #a_fields);
+
+    quote_spanned!(i.imp.span=>
+      #(#attrs)*
+      #a_pat #cond => {
+        let #partner = partner;
+        #net
+      }
+    )
+  }
+
+  fn compile_impl(&self, a: &ImplAgent, b: &ImplAgent, i: &Impl) -> TokenStream {
+    let a_src = self.quote_src(&a.src);
+    let b_src = self.quote_src(&b.src);
+    let a_name = &a.name;
+    let b_name = &b.name;
+    let cond = i.cond.as_ref().map(|x| quote!(if #x)).unwrap_or(quote!());
+    let attrs = self.impl_attrs(i);
+
+    let mut net = self.new_net_compilation(quote!(Self), quote!(self));
+    net.consumed.push(self.consumed(a, format_ident!("a_addr")));
+    net.consumed.push(self.consumed(b, format_ident!("b_addr")));
+    self.plan_reuse(&i.net, &mut net, &[a, b]);
+    let a_fields = self.impl_agent_fields(a, &mut net, None, Some(0));
+    let b_fields = self.impl_agent_fields(b, &mut net, None, Some(1));
+    self.compile_net(&i.net, &mut net);
+    let net = self.finish_net_compilation(net);
+
+    let a_pat = quote_spanned!(a.name.span()=> #a_src #a_name #a_fields);
+    let b_pat = quote_spanned!(b.name.span()=> #b_src #b_name #b_fields);
+
+    quote_spanned!(i.imp.span=>
+      #(#attrs)*
+      (#a_pat, #b_pat) #cond => {
+        #net
+      }
+    )
+  }
+
+  pub fn impl_attrs<'a>(&'a self, i: &'a Impl) -> Vec<&'a Attribute> {
+    let mut attrs = self.rule_attrs(&i.attrs);
+    for agent in i.agents() {
+      if agent.src.is_some() {
+        continue;
+      }
+      let local = self.items.iter().filter_map(Item::as_struct);
+      if let Some(s) = local.into_iter().find(|s| s.name == agent.name) {
+        attrs.extend(self.cfg_attrs(&s.attrs));
+      }
+    }
+    attrs
+  }
+
+  fn consumed(&self, a: &ImplAgent, addr: Ident) -> Consumed {
+    let crate_path = self.crate_path();
+    let src = self.quote_src(&a.src);
+    let name = &a.name;
+    let sized = a.fields.len() > 1 && matches!(a.fields, Fields::Unnamed(_));
+    Consumed {
+      ty: quote!(#src #name<_>),
+      layout: sized.then(|| quote!(#src #name<'static, #crate_path::GetKindMarker>)),
+      addr,
+      arity: a
+        .fields
+        .values()
+        .filter(|x| !matches!(x, ImplAgentField::Payload(_)))
+        .count(),
+      reused_by: None,
+    }
+  }
+
+  fn impl_agent_fields<'a>(
+    &self,
+    a: &'a ImplAgent,
+    comp: &mut NetCompilation<'a>,
+    principal: Option<usize>,
+    consumed: Option<usize>,
+  ) -> TokenStream {
+    let consumed = consumed.filter(|c| comp.consumed[*c].layout.is_some());
+    let mut delta = 0;
+    self.compile_fields(
+      &a.fields,
+      quote!(),
+      a.fields.values().enumerate().map(|(i, x)| {
+        let slot = consumed.map(|c| (c, delta));
+        if !matches!(x, ImplAgentField::Payload(_)) {
+          delta += 1;
+        }
+        match x {
+          _ if Some(i) == principal => quote!(()),
+          ImplAgentField::Implicit(_) => quote!(()),
+          ImplAgentField::Port(ident) => {
+            let (e0, e1) = self.edge_idents(ident);
+            let e = if comp.seen.insert(ident) {
+              e0
+            } else {
+              comp.links.push((e0.clone(), e1.clone()));
+              e1
+            };
+            comp.slots.extend(slot.map(|slot| (e.clone(), slot)));
+            quote!(#e)
+          }
+          ImplAgentField::Payload(PayloadPat { pat, .. }) => quote!(#pat),
+          ImplAgentField::Agent(agent) => {
+            let (e0, e1) = self.implicit_idents(&mut comp.implicit_id);
+            comp.links.push((e0.clone(), e1.clone()));
+            self.compile_net_agent(agent, comp, Some(e1));
+            comp.slots.extend(slot.map(|slot| (e0.clone(), slot)));
+            quote!(#e0)
+          }
+        }
+      }),
+    )
+  }
+}
+
+fn collect_multi_map<K: Ord, V, I: Iterator<Item = (K, V)>>(iter: I) -> BTreeMap<K, Vec<V>> {
+  let mut map = BTreeMap::new();
+  for (key, val) in iter {
+    map.entry(key).or_insert(Vec::new()).push(val);
+  }
+  map
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::*;
+
+  fn errors(source: &str) -> Vec<String> {
+    match compile_str(source) {
+      Ok(_) => vec![],
+      Err(diagnostics) => diagnostics.into_iter().map(|d| d.message).collect(),
+    }
+  }
+
+  #[test]
+  fn wildcard_rules_name_their_partner() {
+    let agents = "pub struct Unit(+Unit); pub struct Probe(-Unit, $u64);";
+    assert_eq!(
+      errors(&format!(
+        "{agents} impl Probe(p, $t) for _ {{ Probe(p, $t) }}"
+      )),
+      Vec::<String>::new()
+    );
+    assert_eq!(
+      errors(&format!("{agents} impl Probe(_, $t) for _ {{}}")),
+      ["the principal port of a wildcard rule must be named"]
+    );
+  }
+}
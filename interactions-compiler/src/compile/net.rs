@@ -0,0 +1,757 @@
+use crate::*;
+use quote::ToTokens;
+use syn::Expr;
+
+#[derive(Debug)]
+pub struct NetCompilation<'a> {
+  pub interactions_ty: TokenStream,
+  pub interactions_var: TokenStream,
+  pub body: Vec<NetPart>,
+  pub links: Vec<(Ident, Ident)>,
+  pub tail: Vec<TokenStream>,
+  pub template: Vec<TokenStream>,
+  pub ports: BTreeMap<Ident, (usize, usize)>,
+  pub consumed: Vec<Consumed>,
+  pub slots: BTreeMap<Ident, (usize, usize)>,
+  pub reuse: Vec<(&'a NetAgent, usize)>,
+  pub reuse_ports: BTreeMap<Ident, (usize, usize)>,
+  pub seen: BTreeSet<&'a Ident>,
+  pub implicit_id: usize,
+}
+
+#[derive(Debug)]
+pub struct NetPart {
+  pub alloc: TokenStream,
+  pub placed: Option<TokenStream>,
+  pub in_place: Option<TokenStream>,
+}
+
+#[derive(Debug)]
+pub struct Consumed {
+  pub ty: TokenStream,
+  pub layout: Option<TokenStream>,
+  pub addr: Ident,
+  pub arity: usize,
+  pub reused_by: Option<TokenStream>,
+}
+
+impl NetPart {
+  fn stmt(stmt: TokenStream) -> Self {
+    NetPart {
+      alloc: stmt,
+      placed: None,
+      in_place: None,
+    }
+  }
+
+  fn render(&self, placed: bool, reused: bool) -> &TokenStream {
+    match (&self.in_place, &self.placed) {
+      (Some(x), _) if reused => x,
+      (_, Some(x)) if placed => x,
+      _ => &self.alloc,
+    }
+  }
+}
+
+impl Program {
+  pub fn new_net_compilation(
+    &self,
+    interactions_ty: TokenStream,
+    interactions_var: TokenStream,
+  ) -> NetCompilation<'_> {
+    NetCompilation {
+      interactions_ty,
+      interactions_var,
+      body: Vec::new(),
+      links: Vec::new(),
+      tail: Vec::new(),
+      template: Vec::new(),
+      ports: BTreeMap::new(),
+      consumed: Vec::new(),
+      slots: BTreeMap::new(),
+      reuse: Vec::new(),
+      reuse_ports: BTreeMap::new(),
+      seen: BTreeSet::new(),
+      implicit_id: 0,
+    }
+  }
+
+  pub fn finish_net_compilation(&self, comp: NetCompilation) -> TokenStream {
+    let crate_path = self.crate_path();
+    let frees = comp
+      .consumed
+      .iter()
+      .map(|Consumed { ty, addr, .. }| quote!(<#ty as #crate_path::Destruct>::free(net, #addr);))
+      .collect::<Vec<_>>();
+    let body = self.finish_net_body(&comp, false);
+    let reused = comp
+      .consumed
+      .iter()
+      .filter(|c| c.reused_by.is_some())
+      .collect::<Vec<_>>();
+    if reused.is_empty() {
+      return quote!(
+        #body
+        #(#frees)*
+      );
+    }
+    let reused_body = self.finish_net_body(&comp, true);
+    let kept_frees = comp
+      .consumed
+      .iter()
+      .zip(&frees)
+      .filter(|(c, _)| c.reused_by.is_none())
+      .map(|(_, free)| free);
+    let same_len = reused.iter().map(|c| {
+      let ty = &c.reused_by;
+      let layout = &c.layout;
+      quote!(
+        <#ty as #crate_path::Layout>::LEN.length_words()
+          == <#layout as #crate_path::Layout>::LEN.length_words()
+          && !<#ty as #crate_path::Layout>::IMMEDIATE
+          && !<#layout as #crate_path::Layout>::IMMEDIATE
+      )
+    });
+    let ranges = comp
+      .consumed
+      .iter()
+      .filter(|c| c.layout.is_some())
+      .map(|Consumed { addr, arity, .. }| {
+        let arity = Literal::u32_unsuffixed(*arity as u32);
+        quote!(p.within(#addr, #crate_path::Length::of(#arity)))
+      })
+      .collect::<Vec<_>>();
+    let crate_path = &crate_path;
+    let ranges = &ranges;
+    let internal = reused.iter().flat_map(|Consumed { addr, arity, .. }| {
+      (1..*arity as i32).map(move |delta| {
+        let delta = Literal::i32_unsuffixed(delta);
+        quote!(|| matches!(
+          #crate_path::Net::resolve(
+            net,
+            #crate_path::LinkHalf::From(#addr + #crate_path::Delta::of(#delta)),
+          ),
+          #crate_path::LinkHalf::Port(p, _) if #(#ranges)||*
+        ))
+      })
+    });
+    quote!(
+      if const { #(#same_len)&&* } && !(false #(#internal)*) {
+        #reused_body
+        #(#kept_frees)*
+      } else {
+        #body
+        #(#frees)*
+      }
+    )
+  }
+
+  fn finish_net_body(&self, comp: &NetCompilation, reused: bool) -> TokenStream {
+    let crate_path = self.crate_path();
+    let in_place = comp
+      .links
+      .iter()
+      .filter(|(a, b)| reused && self.is_in_place_wire(comp, a, b))
+      .flat_map(|(a, b)| [a, b])
+      .collect::<BTreeSet<_>>();
+    let resolves = comp
+      .slots
+      .iter()
+      .filter(|(x, (c, _))| {
+        reused && comp.consumed[*c].reused_by.is_some() && !in_place.contains(x)
+      })
+      .map(|(x, _)| quote!(let #x = #crate_path::Net::resolve(net, #x);));
+    let links = comp
+      .links
+      .iter()
+      .filter(|(a, _)| !in_place.contains(a))
+      .collect::<Vec<_>>();
+    let alloc = comp.body.iter().map(|part| part.render(false, reused));
+    let all_links = links.iter().map(|(a, b)| self.compile_link(a, b));
+    let tail = &comp.tail;
+    if comp.template.is_empty() {
+      return quote!(
+        #(#resolves)*
+        #(#alloc)*
+        #(#all_links)*
+        #(#tail)*
+      );
+    }
+    let template = self.compile_template(comp);
+    let placed = comp.body.iter().map(|part| part.render(true, reused));
+    let boundary = links
+      .iter()
+      .filter(|(a, b)| !self.is_template_wire(comp, a, b))
+      .map(|(a, b)| self.compile_link(a, b));
+    let template_tys = &comp.template;
+    quote!(
+      #(#resolves)*
+      if <N as #crate_path::Alloc>::PARTIAL_FREE
+        && const { true #(&& !<#template_tys as #crate_path::Layout>::IMMEDIATE)* }
+        && #crate_path::Alloc::prefers_block(
+          net,
+          &[#(<#template_tys as #crate_path::Layout>::LEN),*],
+        )
+      {
+        #template
+        #(#placed)*
+        #(#boundary)*
+        #(#tail)*
+      } else {
+        #(#alloc)*
+        #(#all_links)*
+        #(#tail)*
+      }
+    )
+  }
+
+  fn is_in_place_wire(&self, comp: &NetCompilation, a: &Ident, b: &Ident) -> bool {
+    let slot = |x| comp.slots.get(x).or_else(|| comp.reuse_ports.get(x));
+    comp.slots.contains_key(a) != comp.slots.contains_key(b) && slot(a) == slot(b)
+  }
+
+  fn is_template_wire(&self, comp: &NetCompilation, a: &Ident, b: &Ident) -> bool {
+    match (comp.ports.get(a), comp.ports.get(b)) {
+      (Some((_, a)), Some((_, b))) => *a != 0 || *b != 0,
+      _ => false,
+    }
+  }
+
+  fn compile_template(&self, comp: &NetCompilation) -> TokenStream {
+    let crate_path = self.crate_path();
+    let interactions_ty = &comp.interactions_ty;
+    let offsets = (0..comp.template.len())
+      .map(|i| format_ident!("__OFFSET_{}", i))
+      .collect::<Vec<_>>();
+    let lens = comp
+      .template
+      .iter()
+      .map(|ty| quote!(<#ty as #crate_path::Layout>::LEN.length_words()))
+      .collect::<Vec<_>>();
+    let starts = std::iter::once(quote!(0)).chain(
+      offsets
+        .iter()
+        .zip(&lens)
+        .map(|(offset, len)| quote!(#offset + #len)),
+    );
+    let end = starts.clone().last();
+    let kinds = comp.template.iter().zip(&offsets).map(|(ty, offset)| {
+      quote!(
+        t[#offset] = #crate_path::Word::kind(<#ty as #crate_path::GetKind<#interactions_ty>>::KIND);
+      )
+    });
+    let wires = comp
+      .links
+      .iter()
+      .filter(|(a, b)| self.is_template_wire(comp, a, b))
+      .flat_map(|(a, b)| {
+        [
+          (&comp.ports[a], &comp.ports[b]),
+          (&comp.ports[b], &comp.ports[a]),
+        ]
+      })
+      .filter(|((_, from), _)| *from != 0)
+      .map(|(&(from_agent, from), &(to_agent, to))| {
+        let from_offset = &offsets[from_agent];
+        let to_offset = &offsets[to_agent];
+        let mode = if to == 0 {
+          quote!(Principal)
+        } else {
+          quote!(Auxiliary)
+        };
+        quote!(
+          t[#from_offset + #from] = #crate_path::Word::port(
+            #crate_path::Delta::of(
+              (#to_offset + #to) as #crate_path::IWord - (#from_offset + #from) as #crate_path::IWord,
+            ),
+            #crate_path::PortMode::#mode,
+          );
+        )
+      });
+    let template = quote!(&const {
+      let mut t = [#crate_path::Word::NULL; __TEMPLATE_LEN];
+      #(#kinds)*
+      #(#wires)*
+      t
+    });
+    let chunk = match self.redex_addr(comp) {
+      Some(near) => quote!(#crate_path::Alloc::alloc_write_near(net, #near, #template)),
+      None => quote!(#crate_path::Alloc::alloc_write(net, #template)),
+    };
+    quote!(
+      #(const #offsets: usize = #starts;)*
+      const __TEMPLATE_LEN: usize = #end;
+      let __chunk = #chunk;
+    )
+  }
+
+  // Agents created by a rule are allocated near the first consumed agent that
+  // occupies memory, since they are usually linked to what it was linked to.
+  fn redex_addr<'b>(&self, comp: &'b NetCompilation) -> Option<&'b Ident> {
+    comp
+      .consumed
+      .iter()
+      .find(|c| c.layout.is_some())
+      .map(|c| &c.addr)
+  }
+
+  pub fn compile_net<'a>(&self, net: &'a Net, comp: &mut NetCompilation<'a>) {
+    self.compile_net_from(net, 0, &net.stmts, comp);
+    for fusion in &net.fused {
+      self.compile_fusion(fusion, comp);
+    }
+  }
+
+  fn compile_fusion<'a>(&self, fusion: &'a Fusion, comp: &mut NetCompilation<'a>) {
+    let arms = fusion
+      .arms
+      .iter()
+      .map(|arm| {
+        let mut sub =
+          self.new_net_compilation(comp.interactions_ty.clone(), comp.interactions_var.clone());
+        sub.seen = comp.seen.clone();
+        sub.implicit_id = comp.implicit_id;
+        self.compile_net(&arm.net, &mut sub);
+        for (a, b) in &arm.links {
+          sub
+            .links
+            .push((self.edge_idents(a).0, self.edge_idents(b).0));
+        }
+        let pats = &arm.pats;
+        let cond = arm.cond.as_ref().map(|x| quote!(if #x));
+        let body = self.finish_net_compilation(sub);
+        quote!((#(#pats,)*) #cond => { #body })
+      })
+      .collect::<Vec<_>>();
+    let scrutinee = &fusion.scrutinee;
+    comp.tail.push(quote!(
+      match (#(#scrutinee,)*) {
+        #(#arms)*
+        #[allow(unreachable_patterns)]
+        _ => unreachable!(),
+      }
+    ));
+  }
+
+  fn compile_net_from<'a>(
+    &self,
+    net: &'a Net,
+    mut i: usize,
+    mut stmts: &'a [NetStmt],
+    comp: &mut NetCompilation<'a>,
+  ) {
+    loop {
+      while let Some((stmt, rest)) = stmts.split_first().filter(|(stmt, _)| stmt.pos <= i) {
+        if let Some(future) = stmt.awaited() {
+          return self.compile_net_await(net, i, stmt, future, rest, comp);
+        }
+        let stmt = self.compile_net_stmt(stmt);
+        comp.body.push(NetPart::stmt(stmt));
+        stmts = rest;
+      }
+      let Some(agent) = net.agents.get(i) else {
+        break;
+      };
+      self.compile_net_agent(agent, comp, None);
+      i += 1;
+    }
+  }
+
+  fn compile_net_await<'a>(
+    &self,
+    net: &'a Net,
+    i: usize,
+    stmt: &NetStmt,
+    future: &Expr,
+    rest: &'a [NetStmt],
+    comp: &mut NetCompilation<'a>,
+  ) {
+    let crate_path = self.crate_path();
+    let NetStmtKind::Let(local) = &stmt.kind else {
+      unreachable!()
+    };
+    let mut deferred =
+      self.new_net_compilation(comp.interactions_ty.clone(), quote!(&__interactions));
+    deferred.seen = comp.seen.clone();
+    deferred.implicit_id = comp.implicit_id;
+    self.compile_net_from(net, i, rest, &mut deferred);
+    let crossing = net.agents[i..]
+      .iter()
+      .flat_map(NetAgent::all_idents)
+      .filter(|x| comp.seen.contains(x))
+      .collect::<BTreeSet<_>>();
+    let e0s = crossing
+      .iter()
+      .map(|x| self.edge_idents(x).0)
+      .collect::<Vec<_>>();
+    let holes = crossing
+      .iter()
+      .map(|x| format_ident!("{}_hole", x))
+      .collect::<Vec<_>>();
+    let attrs = &local.attrs;
+    let pat = &local.pat;
+    let future = self.compile_effect_access(future.to_token_stream());
+    let interactions_var = &comp.interactions_var;
+    let deferred = self.finish_net_compilation(deferred);
+    comp.tail.push(quote!(
+      #(
+        let #holes = #crate_path::Alloc::alloc_write(net, &[#crate_path::Word::NULL; 2]);
+        #crate_path::Net::link(
+          net,
+          #e0s,
+          #crate_path::LinkHalf::Port(#holes, #crate_path::PortMode::Auxiliary),
+        );
+      )*
+      let __future = #future;
+      let __interactions = *#interactions_var;
+      #crate_path::Net::park(net, ::std::boxed::Box::pin(async move {
+        #(#attrs)* let #pat = __future.await;
+        let resume: #crate_path::Resume<N> = ::std::boxed::Box::new(move |net: &mut N| {
+          #(let #e0s = #crate_path::LinkHalf::From(#holes);)*
+          #deferred
+          #(#crate_path::Alloc::free(net, #holes, #crate_path::Length::of(2));)*
+        });
+        resume
+      }));
+    ));
+  }
+
+  fn compile_net_stmt(&self, stmt: &NetStmt) -> TokenStream {
+    match &stmt.kind {
+      NetStmtKind::Let(local) => {
+        let Some(init) = &local.init else {
+          return quote!(#local);
+        };
+        let attrs = &local.attrs;
+        let pat = &local.pat;
+        let expr = self.compile_effect_access(init.expr.to_token_stream());
+        match &init.diverge {
+          Some((_, diverge)) => quote!(#(#attrs)* let #pat = #expr else #diverge;),
+          None => quote!(#(#attrs)* let #pat = #expr;),
+        }
+      }
+      NetStmtKind::Block(block) => self.compile_effect_access(block.to_token_stream()),
+    }
+  }
+
+  pub fn compile_net_agent<'a>(
+    &self,
+    agent: &'a NetAgent,
+    comp: &mut NetCompilation<'a>,
+    mut implicit: Option<Ident>,
+  ) {
+    let crate_path = self.crate_path();
+    let src = self.quote_src(&agent.src);
+    let name = &agent.name;
+    let mut vars = vec![];
+    let mut ports = vec![];
+    let fields = self.compile_fields(
+      &agent.fields,
+      quote!(),
+      agent.fields.values().map(|x| match x {
+        NetAgentField::Implicit(token) => {
+          if let Some(implicit) = implicit.take() {
+            ports.push(Some(implicit.clone()));
+            vars.push(implicit.clone());
+            quote!(&mut #implicit)
+          } else {
+            emit_error!(token.span(), "unexpected implicit port");
+            ports.push(None);
+            quote!(&mut ())
+          }
+        }
+        NetAgentField::Port(x) => {
+          let (e0, e1) = self.edge_idents(x);
+          let e = if comp.seen.insert(x) {
+            e0
+          } else {
+            comp.links.push((e0, e1.clone()));
+            e1
+          };
+          ports.push(Some(e.clone()));
+          vars.push(e.clone());
+          quote!(&mut #e)
+        }
+        NetAgentField::Payload(PayloadExpr { expr, .. }) => {
+          quote!(#expr)
+        }
+        NetAgentField::Agent(agent) => {
+          let (e0, e1) = self.implicit_idents(&mut comp.implicit_id);
+          comp.links.push((e0.clone(), e1.clone()));
+          self.compile_net_agent(agent, comp, Some(e0));
+          ports.push(Some(e1.clone()));
+          vars.push(e1.clone());
+          quote!(&mut #e1)
+        }
+      }),
+    );
+    if implicit.is_some() {
+      emit_error!(agent.name.span(), "missing implicit port")
+    }
+    let interactions_ty = &comp.interactions_ty;
+    let interactions_var = &comp.interactions_var;
+    let construct = match self.redex_addr(comp) {
+      Some(near) => quote!(
+        #(let mut #vars = #crate_path::LinkHalf::Null;)*
+        #crate_path::Construct::<#interactions_ty, _>::construct_near(
+          #src #name #fields,
+          net,
+          #interactions_var,
+          #near,
+        );
+      ),
+      None => quote!(
+        #(let mut #vars = #crate_path::LinkHalf::Null;)*
+        #crate_path::Construct::<#interactions_ty, _>::construct(
+          #src #name #fields,
+          net,
+          #interactions_var,
+        );
+      ),
+    };
+    let placeable = self.is_placeable(agent);
+    let reuse = comp
+      .reuse
+      .iter()
+      .find(|(x, _)| std::ptr::eq(*x, agent))
+      .map(|(_, c)| *c);
+    if let Some(c) = reuse {
+      for (delta, port) in ports.into_iter().enumerate() {
+        if let Some(port) = port {
+          comp.reuse_ports.insert(port, (c, delta));
+        }
+      }
+      let addr = &comp.consumed[c].addr;
+      comp.body.push(NetPart {
+        alloc: construct,
+        placed: None,
+        in_place: Some(quote!(
+          #(let mut #vars = #crate_path::LinkHalf::Null;)*
+          *#crate_path::BufferMut::word_mut(net, #addr) = #crate_path::Word::kind(
+            <#src #name<_> as #crate_path::GetKind<#interactions_ty>>::KIND,
+          );
+          #crate_path::Place::place(#src #name #fields, net, #addr);
+        )),
+      });
+      return;
+    }
+    if !placeable {
+      comp.body.push(NetPart::stmt(construct));
+      return;
+    }
+    let i = comp.template.len();
+    let offset = format_ident!("__OFFSET_{}", i);
+    comp
+      .template
+      .push(quote!(#src #name<'static, #crate_path::GetKindMarker>));
+    for (delta, port) in ports.into_iter().enumerate() {
+      if let Some(port) = port {
+        comp.ports.insert(port, (i, delta));
+      }
+    }
+    comp.body.push(NetPart {
+      alloc: construct,
+      placed: Some(quote!(
+        #(let mut #vars = #crate_path::LinkHalf::Null;)*
+        #crate_path::Place::place(
+          #src #name #fields,
+          net,
+          __chunk + #crate_path::Delta::of(#offset as #crate_path::IWord),
+        );
+      )),
+      in_place: None,
+    });
+  }
+
+  fn is_placeable(&self, agent: &NetAgent) -> bool {
+    let is_fn = agent.src.is_none()
+      && self
+        .items
+        .iter()
+        .filter_map(Item::as_fn)
+        .any(|f| f.name == agent.name);
+    matches!(agent.fields, Fields::Unnamed(_))
+      && agent.fields.len() > 1
+      && agent.args.is_empty()
+      && self.is_agent(&agent.src, &agent.name)
+      && !is_fn
+  }
+
+  pub fn plan_reuse<'a>(&self, net: &'a Net, comp: &mut NetCompilation<'a>, lhs: &[&ImplAgent]) {
+    let crate_path = self.crate_path();
+    let end = net
+      .stmts
+      .iter()
+      .find(|stmt| stmt.awaited().is_some())
+      .map(|stmt| stmt.pos)
+      .unwrap_or(net.agents.len());
+    let mut candidates = vec![];
+    let mut stack = net.agents[..end].iter().rev().collect::<Vec<_>>();
+    while let Some(agent) = stack.pop() {
+      if self.is_placeable(agent) {
+        candidates.push(agent);
+      }
+      let nested = agent.fields.values().filter_map(|x| match x {
+        NetAgentField::Agent(x) => Some(x),
+        _ => None,
+      });
+      stack.extend(nested.collect::<Vec<_>>().into_iter().rev());
+    }
+    let shape = |payloads: usize, len: usize| (len - payloads, payloads);
+    for (c, o) in lhs.iter().enumerate() {
+      if comp.consumed[c].layout.is_none() {
+        continue;
+      }
+      let o_shape = shape(
+        o.fields
+          .values()
+          .filter(|x| matches!(x, ImplAgentField::Payload(_)))
+          .count(),
+        o.fields.len(),
+      );
+      let taken = |x: &NetAgent| comp.reuse.iter().any(|(y, _)| std::ptr::eq(*y, x));
+      let same = candidates
+        .iter()
+        .find(|x| x.src == o.src && x.name == o.name && !taken(x));
+      let similar = || {
+        candidates.iter().find(|x| {
+          let payloads = x
+            .fields
+            .values()
+            .filter(|x| matches!(x, NetAgentField::Payload(_)))
+            .count();
+          shape(payloads, x.fields.len()) == o_shape && !taken(x)
+        })
+      };
+      let Some(agent) = same.or_else(similar).copied() else {
+        continue;
+      };
+      let src = self.quote_src(&agent.src);
+      let name = &agent.name;
+      comp.consumed[c].reused_by = Some(quote!(#src #name<'static, #crate_path::GetKindMarker>));
+      comp.reuse.push((agent, c));
+    }
+  }
+
+  pub fn compile_link(&self, a: &Ident, b: &Ident) -> TokenStream {
+    let crate_path = self.crate_path();
+    quote!(
+      #crate_path::Net::link(net, #a, #b);
+    )
+  }
+
+  pub fn edge_idents(&self, ident: &Ident) -> (Ident, Ident) {
+    (format_ident!("{}_0", ident), format_ident!("{}_1", ident))
+  }
+
+  pub fn implicit_idents(&self, implicit_id: &mut usize) -> (Ident, Ident) {
+    let id = std::mem::replace(implicit_id, *implicit_id + 1);
+    (format_ident!("__{}_0", id), format_ident!("__{}_1", id))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::*;
+
+  #[test]
+  fn keeps_statements_in_place_among_agents() {
+    let output = compile_str(
+      "
+      pub struct U64(+U64, $u64);
+      pub struct Pair(+Pair, -U64, -U64);
+      pub struct Log(-Pair);
+      fn f(x: $u64) {
+        { first(); }
+        let y = x + 1;
+        U64(a, $y)
+        { second(y); }
+        Pair(p, a, U64(_, $x))
+        Log(p)
+      }
+      ",
+    )
+    .unwrap()
+    .to_string();
+    let start = output.find("let f (x) = self").unwrap();
+    let body = &output[start..];
+    let order = [
+      "first ()",
+      "let y = x + 1",
+      "U64 (& mut a_0 , y)",
+      "second (y)",
+      "U64 (& mut __0_0 , x)",
+      "Pair (& mut p_0",
+    ]
+    .map(|s| {
+      body
+        .find(s)
+        .unwrap_or_else(|| panic!("missing `{s}` in {body}"))
+    });
+    assert!(order.is_sorted(), "{order:?}");
+  }
+
+  #[test]
+  fn reuses_redex_agents_in_place() {
+    let output = compile_str(
+      "
+      pub struct Zero(+Nat);
+      pub struct Succ(+Nat, -Nat);
+      pub struct Add(-Nat, -Nat, +Nat);
+      impl Add(_, y, o) for Succ(_, x) {
+        Add(x, y, o2)
+        Succ(o, o2)
+      }
+      impl Add(_, y, y) for Zero(_) {}
+      ",
+    )
+    .unwrap()
+    .to_string();
+    let rule = |name: &str| {
+      let start = output.find(&format!("fn {name} <")).unwrap();
+      let end = output[start + 1..]
+        .find("fn __rule_")
+        .map_or(output.len(), |end| start + 1 + end);
+      &output[start..end]
+    };
+    let succ = rule("__rule_0");
+    assert!(succ.contains("if const {"), "{succ}");
+    assert!(
+      succ.contains("Place :: place (Add (& mut x_1 , & mut y_1 , & mut o2_0) , net , a_addr)"),
+      "{succ}"
+    );
+    assert!(
+      succ.contains("Place :: place (Succ (& mut o_1 , & mut o2_1) , net , b_addr)"),
+      "{succ}"
+    );
+    // Nothing is built, so both agents are just freed.
+    let zero = rule("__rule_1");
+    assert!(!zero.contains("Place :: place"), "{zero}");
+    assert!(zero.contains(":: free (net , a_addr)"), "{zero}");
+  }
+
+  #[test]
+  fn allocates_new_agents_near_the_redex() {
+    let output = compile_str(
+      "
+      pub struct U64(+U64, $u64);
+      pub struct Pair(+Pair, -U64, -U64);
+      pub struct Dup(-U64, +Pair);
+      impl Dup(_, p) for U64(_, $n) {
+        Pair(p, U64(_, $n), U64(_, $n))
+      }
+      ",
+    )
+    .unwrap()
+    .to_string();
+    assert!(output.contains(":: Alloc :: alloc_near (net , near ,"));
+    let rules = &output[output.find("fn __rule_0").unwrap()..];
+    assert!(!rules.contains(":: Alloc :: alloc ("), "{rules}");
+    assert!(!rules.contains(":: Alloc :: alloc_write ("), "{rules}");
+    assert!(rules.contains(":: Alloc :: alloc_write_near (net , a_addr ,"));
+    // Agents built one by one are each placed near the first agent of the redex.
+    let near = rules.matches("construct_near (").count();
+    assert!(near > 0);
+    assert_eq!(rules.matches(", net , self , a_addr ,)").count(), near);
+  }
+}
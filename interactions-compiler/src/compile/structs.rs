@@ -0,0 +1,497 @@
+use crate::*;
+
+impl Program {
+  pub fn compile_structs(&self) -> Vec<TokenStream> {
+    let structs = self
+      .items
+      .iter()
+      .filter_map(Item::as_struct)
+      .collect::<Vec<_>>();
+    structs
+      .iter()
+      .enumerate()
+      .map(|(i, s)| self.compile_struct(self.kind_count(structs[..i].iter().copied()), s))
+      .collect::<Vec<_>>()
+  }
+
+  fn compile_struct(&self, offset: TokenStream, s: &Struct) -> TokenStream {
+    let name = &s.name;
+    let arity = s.fields.values().filter_map(StructField::port).count() as u32;
+    if arity == 0 {
+      emit_error!(name.span(), "missing principal port");
+      return quote!();
+    }
+    let struct_def = self.compile_struct_def(s);
+    let get_kind_impl = self.compile_get_kind_impl(offset, s);
+    let construct_destruct_impls = if s.fields.len() == 1 {
+      self.compile_nilary_construct_destruct_impls(s)
+    } else if s.fields.len() == 2
+      && arity == 1
+      && s.payloads().all(|(_, (_, p))| is_immediate(&p.ty))
+    {
+      self.compile_immediate_construct_destruct_impls(s)
+    } else {
+      self.compile_construct_destruct_impls(s, arity)
+    };
+    let cfgs = self.cfg_attrs(&s.attrs);
+    if cfgs.is_empty() {
+      return quote!(
+        #struct_def
+        #get_kind_impl
+        #construct_destruct_impls
+      );
+    }
+    quote!(
+      #struct_def
+      #(#cfgs)*
+      const _: () = {
+        #get_kind_impl
+        #construct_destruct_impls
+      };
+    )
+  }
+
+  // Port types may be imported, so the impls see the module's `use` items.
+  pub fn compile_host_fn_impls(&self, includes: &TokenStream) -> TokenStream {
+    let impls = self
+      .items
+      .iter()
+      .filter_map(Item::as_struct)
+      .map(|s| self.compile_host_fn_impl(s))
+      .collect::<Vec<_>>();
+    quote!(
+      const _: () = {
+        #includes
+        #(#impls)*
+      };
+    )
+  }
+
+  fn compile_host_fn_impl(&self, s: &Struct) -> TokenStream {
+    let mut ports = s.fields.values().filter_map(StructField::port);
+    let (Some(_), Some(input), Some(output)) = (s.external, ports.next(), ports.next()) else {
+      return quote!();
+    };
+    let crate_path = self.crate_path();
+    let name = &s.name;
+    let key = name.to_string();
+    let payload = |ty: &Ident| {
+      quote_spanned!(ty.span()=>
+        <#ty<'static, #crate_path::GetKindMarker> as #crate_path::Payload>::Payload
+      )
+    };
+    let (input, output) = (payload(&input.name), payload(&output.name));
+    let cfgs = self.cfg_attrs(&s.attrs);
+    quote!(
+      #(#cfgs)*
+      impl #crate_path::HostFn for #name<'static, #crate_path::GetKindMarker> {
+        const NAME: &'static str = #key;
+        type Input = #input;
+        type Output = #output;
+        #[inline(always)]
+        fn index() -> usize {
+          static INDEX: ::std::sync::OnceLock<usize> = ::std::sync::OnceLock::new();
+          *INDEX.get_or_init(#crate_path::next_host_fn_index)
+        }
+      }
+    )
+  }
+
+  fn key(&self, s: &Struct, idx: usize) -> TokenStream {
+    match &s.fields {
+      Fields::Unnamed(_) => {
+        let idx = syn::Index::from(idx);
+        quote!(#idx)
+      }
+      Fields::Named(f) => {
+        let name = &f.entries[idx].key;
+        quote!(#name)
+      }
+    }
+  }
+
+  fn compile_struct_def(&self, s: &Struct) -> TokenStream {
+    let crate_path = self.crate_path();
+    let name = &s.name;
+    let vis = &s.vis;
+    let principal_idx = s
+      .fields
+      .values()
+      .enumerate()
+      .find(|x| x.1.port().is_some())
+      .unwrap()
+      .0;
+    let attrs = &s.attrs;
+    let fields = self.compile_field_defs(
+      &s.fields,
+      s.fields.values().enumerate().map(|(i, p)| match p {
+        StructField::Port(_) => {
+          if i == principal_idx {
+            quote!(<M as #crate_path::Marker>::Principal<'a>)
+          } else {
+            quote!(<M as #crate_path::Marker>::Auxiliary<'a>)
+          }
+        }
+        StructField::Payload(PayloadType { ty, .. }) => quote!(#ty),
+      }),
+    );
+    let semi = if s.fields.semi() { quote!(;) } else { quote!() };
+    quote!(
+      #(#attrs)*
+      #[allow(non_camel_case_types, dead_code)]
+      #vis struct #name<'a, M: #crate_path::Marker> #fields #semi
+    )
+  }
+
+  fn compile_get_kind_impl(&self, offset: TokenStream, s: &Struct) -> TokenStream {
+    let crate_path = self.crate_path();
+    let name = &s.name;
+    quote!(
+      #[allow(clippy::identity_op)]
+      impl<'a, I: self::Use> #crate_path::GetKind<I> for #name<'a, #crate_path::GetKindMarker> {
+        const KIND: #crate_path::Kind = #crate_path::Kind::of(<I as self::Use>::KIND_START + #offset);
+      }
+    )
+  }
+
+  fn compile_nilary_construct_destruct_impls(&self, s: &Struct) -> TokenStream {
+    let crate_path = self.crate_path();
+    let name = &s.name;
+    quote!(
+      impl<'a, I: self::Use, N: #crate_path::Net> #crate_path::Construct<I, N>
+        for #name<'a, #crate_path::ConstructMarker>
+      {
+        #[inline(always)]
+        fn construct(self, _: &mut N, _: &I) {
+          let kind = <#name<'a, _> as #crate_path::GetKind<I>>::KIND;
+          *self.0 = #crate_path::LinkHalf::Kind(kind);
+        }
+      }
+      impl<'a> #crate_path::Destruct for #name<'a, #crate_path::DestructMarker> {
+        #[inline(always)]
+        fn destruct<N: #crate_path::Net>(_: &mut N, _: #crate_path::Addr) -> Self {
+          #name(())
+        }
+        #[inline(always)]
+        fn free<N: #crate_path::Net>(_: &mut N, _: #crate_path::Addr) {}
+      }
+    )
+  }
+
+  fn compile_immediate_construct_destruct_impls(&self, s: &Struct) -> TokenStream {
+    let crate_path = self.crate_path();
+    let name = &s.name;
+    let (_, (port_idx, _)) = s.ports().next().unwrap();
+    let (_, (payload_idx, PayloadType { ty, .. })) = s.payloads().next().unwrap();
+    let port = self.key(s, port_idx);
+    let payload = self.key(s, payload_idx);
+    let layout = quote!(<#name<'a, #crate_path::GetKindMarker> as #crate_path::Layout>);
+    let payload_addr = quote!(addr + #crate_path::Length::of(1));
+    let fields = self.compile_fields(
+      &s.fields,
+      quote!(),
+      (0..2).map(|i| {
+        if i == port_idx {
+          quote!(())
+        } else {
+          quote!(payload)
+        }
+      }),
+    );
+    quote!(
+      impl<'a, I: self::Use, N: #crate_path::Net> #crate_path::Construct<I, N>
+        for #name<'a, #crate_path::ConstructMarker>
+      {
+        #[inline(always)]
+        fn construct(self, _: &mut N, _: &I) {
+          let kind = <#name<'a, _> as #crate_path::GetKind<I>>::KIND;
+          let bits = #crate_path::Immediate::to_bits(self.#payload);
+          *self.#port = #crate_path::LinkHalf::Immediate(kind, bits);
+        }
+        #[inline(always)]
+        fn construct_near(self, _: &mut N, _: &I, _: #crate_path::Addr) {
+          let kind = <#name<'a, _> as #crate_path::GetKind<I>>::KIND;
+          let bits = #crate_path::Immediate::to_bits(self.#payload);
+          *self.#port = #crate_path::LinkHalf::Immediate(kind, bits);
+        }
+      }
+      impl<'a> #crate_path::Payload for #name<'a, #crate_path::GetKindMarker> {
+        type Payload = #ty;
+      }
+      impl<'a> #crate_path::Layout for #name<'a, #crate_path::GetKindMarker> {
+        const LEN: #crate_path::Length = #crate_path::BOXED_IMMEDIATE_LEN;
+        const IMMEDIATE: bool = true;
+      }
+      impl<'a, N: #crate_path::Net> #crate_path::Place<N> for #name<'a, #crate_path::ConstructMarker> {
+        #[inline(always)]
+        fn place(self, net: &mut N, addr: #crate_path::Addr) {
+          *self.#port = #crate_path::LinkHalf::Port(addr, #crate_path::PortMode::Principal);
+          let bits = #crate_path::Immediate::to_bits(self.#payload);
+          #crate_path::BufferMut::write_payload::<u64>(net, #payload_addr, bits);
+        }
+      }
+      impl<'a> #crate_path::Destruct for #name<'a, #crate_path::DestructMarker> {
+        #[inline(always)]
+        fn destruct<N: #crate_path::Net>(net: &mut N, addr: #crate_path::Addr) -> Self {
+          let bits = if addr.is_immediate() {
+            addr.as_immediate()
+          } else {
+            #crate_path::Buffer::read_payload::<u64>(net, #payload_addr)
+          };
+          let payload = <#ty as #crate_path::Immediate>::from_bits(bits);
+          #name #fields
+        }
+        #[inline(always)]
+        fn free<N: #crate_path::Net>(net: &mut N, addr: #crate_path::Addr) {
+          if !addr.is_immediate() {
+            #crate_path::Alloc::free(net, addr, #layout::LEN);
+          }
+        }
+      }
+    )
+  }
+
+  fn compile_construct_destruct_impls(&self, s: &Struct, arity: u32) -> TokenStream {
+    let crate_path = self.crate_path();
+    let arity = Literal::u32_unsuffixed(arity);
+    let arity_len = quote!(#crate_path::Length::of(#arity));
+    let payload_sizes = s
+      .fields
+      .values()
+      .filter_map(StructField::payload)
+      .map(|PayloadType { ty, .. }| quote!(::std::mem::size_of::<#ty>()))
+      .collect::<Vec<_>>();
+    let payload_offsets = (0..=payload_sizes.len())
+      .map(|i| match &payload_sizes[0..i] {
+        [] => quote!(0),
+        prev_payload_sizes => quote!(#(#prev_payload_sizes)+*),
+      })
+      .collect::<Vec<_>>();
+    let payload_bytes = payload_offsets.last().unwrap();
+    let len = quote!(#arity_len.add(#crate_path::Length::of_payload_bytes(#payload_bytes)));
+    let payload_addrs = payload_offsets[..payload_sizes.len()]
+      .iter()
+      .enumerate()
+      .map(|(i, offset)| {
+        let offset = if i > 1 {
+          quote!((#offset))
+        } else {
+          offset.clone()
+        };
+        quote!(addr + #arity_len + #crate_path::Delta::of_bytes(#offset as #crate_path::IWord))
+      })
+      .collect::<Vec<_>>();
+    let layout_asserts = self.compile_layout_asserts(
+      s,
+      &arity_len,
+      &payload_offsets[..payload_sizes.len()],
+      &payload_sizes,
+    );
+    let construct_impl = self.compile_construct_impl(s, &len, &payload_addrs);
+    let destruct_impl = self.compile_destruct_impl(s, &len, &payload_addrs);
+    quote!(
+      #layout_asserts
+      #construct_impl
+      #destruct_impl
+    )
+  }
+
+  // Checks the offsets payloads are read and written at against the length
+  // the agent is allocated with: the packed payloads must fit in the words
+  // after the ports, and so must each payload at its offset.
+  fn compile_layout_asserts(
+    &self,
+    s: &Struct,
+    arity_len: &TokenStream,
+    payload_offsets: &[TokenStream],
+    payload_sizes: &[TokenStream],
+  ) -> TokenStream {
+    if payload_sizes.is_empty() {
+      return quote!();
+    }
+    let crate_path = self.crate_path();
+    let name = &s.name;
+    let layout = quote!(<#name<'static, #crate_path::GetKindMarker> as #crate_path::Layout>);
+    quote!(
+      const _: () = {
+        let span = (#layout::LEN.length_words() - #arity_len.length_words()) * #crate_path::WORD_SIZE;
+        assert!(#(#payload_sizes)+* <= span);
+        #(assert!(#payload_offsets + #payload_sizes <= span);)*
+      };
+    )
+  }
+
+  fn compile_construct_impl(
+    &self,
+    s: &Struct,
+    len: &TokenStream,
+    payload_addrs: &[TokenStream],
+  ) -> TokenStream {
+    let crate_path = self.crate_path();
+    let name = &s.name;
+    let construct_ports = s.ports().map(|(delta, (idx, _))| {
+      let key = self.key(s, idx);
+      let mode = if delta == 0 {
+        quote!(Principal)
+      } else {
+        quote!(Auxiliary)
+      };
+      let delta = Literal::i32_unsuffixed(delta as i32);
+      quote!(
+        *self.#key = #crate_path::LinkHalf::Port(
+          addr + #crate_path::Delta::of(#delta),
+          #crate_path::PortMode::#mode,
+        );
+      )
+    });
+    let construct_payloads = s
+      .payloads()
+      .map(|(payload_i, (idx, PayloadType { ty, .. }))| {
+        let payload_addr = &payload_addrs[payload_i];
+        let key = self.key(s, idx);
+        quote!(
+          #crate_path::BufferMut::write_payload::<#ty>(net, #payload_addr, self.#key);
+        )
+      });
+    quote!(
+      impl<'a, I: self::Use, N: #crate_path::Net> #crate_path::Construct<I, N>
+        for #name<'a, #crate_path::ConstructMarker>
+      {
+        #[inline(always)]
+        fn construct(self, net: &mut N, _: &I) {
+          let addr = #crate_path::Alloc::alloc(net, #len);
+          let kind = <#name<'a, _> as #crate_path::GetKind<I>>::KIND;
+          *#crate_path::BufferMut::word_mut(net, addr) = #crate_path::Word::kind(kind);
+          #crate_path::Place::place(self, net, addr);
+        }
+        #[inline(always)]
+        fn construct_near(self, net: &mut N, _: &I, near: #crate_path::Addr) {
+          let addr = #crate_path::Alloc::alloc_near(net, near, #len);
+          let kind = <#name<'a, _> as #crate_path::GetKind<I>>::KIND;
+          *#crate_path::BufferMut::word_mut(net, addr) = #crate_path::Word::kind(kind);
+          #crate_path::Place::place(self, net, addr);
+        }
+      }
+      impl<'a> #crate_path::Layout for #name<'a, #crate_path::GetKindMarker> {
+        const LEN: #crate_path::Length = #len;
+      }
+      impl<'a, N: #crate_path::Net> #crate_path::Place<N> for #name<'a, #crate_path::ConstructMarker> {
+        #[inline(always)]
+        #[allow(unused_variables)]
+        fn place(self, net: &mut N, addr: #crate_path::Addr) {
+          #(#construct_ports)*
+          #(#construct_payloads)*
+        }
+      }
+    )
+  }
+
+  fn compile_destruct_impl(
+    &self,
+    s: &Struct,
+    len: &TokenStream,
+    payload_addrs: &[TokenStream],
+  ) -> TokenStream {
+    let crate_path = self.crate_path();
+    let name = &s.name;
+    let destruct_vars = (0..s.fields.len())
+      .map(|i| format_ident!("_{}", i))
+      .collect::<Vec<_>>();
+    let destruct_ports = s.ports().map(|(delta, (idx, _))| {
+      let var = &destruct_vars[idx];
+      if delta == 0 {
+        quote!(#var = ();)
+      } else {
+        let delta = Literal::i32_unsuffixed(delta as i32);
+        quote!(#var = #crate_path::LinkHalf::From(addr + #crate_path::Delta::of(#delta));)
+      }
+    });
+    let destruct_payloads = s
+      .payloads()
+      .map(|(payload_i, (idx, PayloadType { ty, .. }))| {
+        let payload_addr = &payload_addrs[payload_i];
+        let var = &destruct_vars[idx];
+        quote!(
+          #var = #crate_path::Buffer::read_payload::<#ty>(net, #payload_addr);
+        )
+      });
+    let fields = self.compile_fields(
+      &s.fields,
+      quote!(),
+      destruct_vars.iter().map(|x| quote!(#x)),
+    );
+    quote!(
+      impl<'a> #crate_path::Destruct for #name<'a, #crate_path::DestructMarker> {
+        #[inline(always)]
+        #[allow(unused_variables, clippy::needless_late_init, clippy::just_underscores_and_digits)]
+        fn destruct<N: #crate_path::Net>(net: &mut N, addr: #crate_path::Addr) -> Self {
+          #(let #destruct_vars;)*
+          #(#destruct_ports)*
+          #(#destruct_payloads)*
+          #name #fields
+        }
+        #[inline(always)]
+        fn free<N: #crate_path::Net>(net: &mut N, addr: #crate_path::Addr) {
+          #crate_path::Alloc::free(net, addr, #len);
+        }
+      }
+    )
+  }
+}
+
+// Payload types with an `Immediate` impl in the runtime; anything else keeps
+// the regular boxed layout.
+fn is_immediate(ty: &syn::Type) -> bool {
+  const IMMEDIATE: &[&str] = &[
+    "u8", "u16", "u32", "u64", "usize", "i8", "i16", "i32", "i64", "isize", "bool", "char",
+  ];
+  matches!(ty, syn::Type::Path(p) if p.qself.is_none() && p.path.get_ident().is_some_and(|x| IMMEDIATE.iter().any(|t| x == t)))
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::*;
+
+  fn is_immediate_agent(source: &str, name: &str) -> bool {
+    let output = compile_str(source).unwrap().to_string();
+    let layout = format!("Layout for {} <", name);
+    let start = output.find(&layout).unwrap();
+    let end = start + output[start..].find('}').unwrap();
+    output[start..end].contains("const IMMEDIATE : bool = true")
+  }
+
+  #[test]
+  fn stores_integer_like_payloads_as_immediates() {
+    assert!(is_immediate_agent("pub struct N(+N, $u64);", "N"));
+    assert!(is_immediate_agent("pub struct C(+C, $char);", "C"));
+  }
+
+  #[test]
+  fn keeps_other_payloads_boxed() {
+    for ty in [
+      "(u8, u16)",
+      "Option<u32>",
+      "Box<u64>",
+      "&'static u8",
+      "[u8; 4]",
+    ] {
+      let source = format!("pub struct P(+P, ${ty});");
+      assert!(!is_immediate_agent(&source, "P"), "{ty}");
+    }
+  }
+
+  #[test]
+  fn counts_gated_kinds_when_their_cfg_holds() {
+    let output = compile_str(
+      "#[cfg(feature = \"a\")] pub struct A(+A); pub struct B(+B); #[cfg(test)] pub struct C(+C);",
+    )
+    .unwrap()
+    .to_string();
+    assert!(
+      output.contains(
+        "const KIND_COUNT : u32 = 1u32 + (cfg ! (feature = \"a\") as u32) + (cfg ! (test) as u32) ;"
+      ),
+      "{output}"
+    );
+  }
+}
@@ -0,0 +1,138 @@
+use crate::*;
+use proc_macro2::{Group, TokenTree};
+use syn::Path;
+
+impl Program {
+  pub fn compile_uses(&self) -> (TokenStream, TokenStream, Vec<TokenStream>) {
+    let mut impls = vec![];
+    let mut paths = vec![];
+    let mut includes = vec![];
+    let mut modules = BTreeSet::new();
+    for u in self.items.iter().filter_map(Item::as_use) {
+      let path = &u.path;
+      let span = path.span();
+      let attrs = self.rule_attrs(&u.attrs);
+      if let Some(module) = &u.module {
+        includes.push(quote_spanned!(span=>
+          #(#attrs)*
+          #[allow(unused)]
+          use #path as #module;
+        ));
+      }
+      if u.implicit {
+        continue;
+      }
+      // Generic agents only exist here as their instances.
+      let agents = u
+        .agents
+        .iter()
+        .filter(|a| !self.imports_generic(&u.path, &a.name))
+        .collect::<Vec<_>>();
+      let names = agents.iter().map(|a| &a.name);
+      let idents = agents.iter().map(|a| a.ident());
+      includes.push(quote_spanned!(span=>
+        #(#attrs)*
+        #[allow(unused)]
+        use #path::{#(#names as #idents),*};
+      ));
+      if let Some(glob) = &u.glob {
+        includes.push(quote_spanned!(glob.span=>
+          #(#attrs)*
+          #[allow(unused)]
+          use #path::*;
+        ));
+      }
+      if !modules.insert(quote!(#path).to_string()) {
+        continue;
+      }
+      impls.push(quote!(
+        impl #path::Use for Interactions {
+          const KIND_START: u32 = 0 #(+ <Self as #paths::Use>::KIND_COUNT)*;
+        }
+      ));
+      paths.push(quote!(#path));
+    }
+    (quote!(#(#impls)*), quote!(#(#includes)*), paths)
+  }
+
+  // Modules are compiled one macro invocation at a time, so a module that uses
+  // others first asks each of them for its source and is then compiled again
+  // with those sources in front of its own. Every module exports its source
+  // through `__interactions_source!`, which appends it and passes the request
+  // on to the next module in the queue.
+  pub fn import_modules(&self) -> Option<TokenStream> {
+    if !self.modules.is_empty() {
+      return None;
+    }
+    let mut seen = BTreeSet::new();
+    let paths = self
+      .items
+      .iter()
+      .filter_map(Item::as_use)
+      .map(|u| &u.path)
+      .filter(|path| seen.insert(quote!(#path).to_string()))
+      .collect::<Vec<_>>();
+    let (first, rest) = paths.split_first()?;
+    let source = &self.source;
+    Some(quote!(
+      #first::__interactions_source! { $ [#first] [#([#rest])*] #source }
+    ))
+  }
+
+  pub fn compile_source_macro(&self) -> TokenStream {
+    let crate_path = &self.crate_path();
+    let source = self.escape_dollars(self.source.clone());
+    quote!(
+      #[doc(hidden)]
+      #[allow(unused_macros)]
+      macro_rules! __interactions_source {
+        ($d:tt [$($me:tt)*] [] $($input:tt)*) => {
+          #crate_path::interactions! { $d @module $($me)* { #source } $($input)* }
+        };
+        ($d:tt [$($me:tt)*] [[$($next:tt)*] $($queue:tt)*] $($input:tt)*) => {
+          $($next)*::__interactions_source! {
+            $d [$($next)*] [$($queue)*] @module $($me)* { #source } $($input)*
+          }
+        };
+      }
+
+      #[doc(hidden)]
+      #[allow(unused_imports)]
+      pub(crate) use __interactions_source;
+    )
+  }
+
+  // `$` starts a metavariable in a `macro_rules!` body, so the exported source
+  // spells it `$d`, which the caller binds to a literal `$`.
+  fn escape_dollars(&self, tokens: TokenStream) -> TokenStream {
+    tokens
+      .into_iter()
+      .flat_map(|token| match token {
+        TokenTree::Punct(punct) if punct.as_char() == '$' => quote!($d),
+        TokenTree::Group(group) => {
+          let mut new_group = Group::new(group.delimiter(), self.escape_dollars(group.stream()));
+          new_group.set_span(group.span());
+          TokenTree::Group(new_group).into()
+        }
+        token => token.into(),
+      })
+      .collect()
+  }
+
+  fn imports_generic(&self, path: &Path, name: &Ident) -> bool {
+    self.module(path).is_some_and(|m| {
+      m.program
+        .items
+        .iter()
+        .filter_map(Item::as_struct)
+        .any(|s| &s.name == name && !s.generics.is_empty())
+    })
+  }
+
+  pub fn quote_src(&self, src: &Option<Ident>) -> TokenStream {
+    src
+      .as_ref()
+      .map(|trait_name| quote!(#trait_name::))
+      .unwrap_or(quote!())
+  }
+}
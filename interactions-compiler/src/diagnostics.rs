@@ -0,0 +1,50 @@
+use proc_macro2::Span;
+use std::{
+  cell::RefCell,
+  panic::{catch_unwind, resume_unwind, AssertUnwindSafe},
+};
+
+macro_rules! emit_error {
+  ($span:expr, $($fmt:tt)*) => {
+    $crate::diagnostics::emit($span, format!($($fmt)*))
+  };
+}
+
+macro_rules! abort {
+  ($span:expr, $($fmt:tt)*) => {
+    $crate::diagnostics::abort($span, format!($($fmt)*))
+  };
+}
+
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+  pub span: Span,
+  pub message: String,
+}
+
+struct Aborted;
+
+thread_local! {
+  static DIAGNOSTICS: RefCell<Vec<Diagnostic>> = const { RefCell::new(vec![]) };
+}
+
+pub fn emit(span: Span, message: String) {
+  DIAGNOSTICS.with(|d| d.borrow_mut().push(Diagnostic { span, message }));
+}
+
+pub fn abort(span: Span, message: String) -> ! {
+  emit(span, message);
+  resume_unwind(Box::new(Aborted))
+}
+
+pub fn collect_diagnostics<T>(f: impl FnOnce() -> T) -> Result<T, Vec<Diagnostic>> {
+  let outer = DIAGNOSTICS.with(|d| d.take());
+  let result = catch_unwind(AssertUnwindSafe(f));
+  let diagnostics = DIAGNOSTICS.with(|d| d.replace(outer));
+  match result {
+    Ok(value) if diagnostics.is_empty() => Ok(value),
+    Ok(_) => Err(diagnostics),
+    Err(payload) if payload.is::<Aborted>() => Err(diagnostics),
+    Err(payload) => resume_unwind(payload),
+  }
+}
@@ -0,0 +1,53 @@
+mod defs;
+mod derives;
+mod enums;
+mod exprs;
+mod externs;
+mod generics;
+mod schemas;
+
+use crate::*;
+use proc_macro2::{Group, TokenTree};
+use quote::ToTokens;
+
+impl Program {
+  pub fn expand(&mut self) {
+    self.expand_items();
+    for module in &mut self.modules {
+      module.program.expand_items();
+    }
+    self.expand_generics();
+    self.expand_derives();
+  }
+
+  // The expansions that only need the module's own source, which are also
+  // run on the sources of imported modules. Imported modules are expanded
+  // last, so that these still see the enums and defs they declare.
+  fn expand_items(&mut self) {
+    self.expand_schemas();
+    self.expand_defs();
+    self.expand_enums();
+    self.expand_externs();
+    self.expand_exprs();
+  }
+
+  pub fn subst_tokens<T: ToTokens>(
+    &self,
+    tokens: TokenStream,
+    subst: &BTreeMap<Ident, T>,
+  ) -> TokenStream {
+    tokens
+      .into_iter()
+      .flat_map(|token| match token {
+        TokenTree::Ident(ident) if subst.contains_key(&ident) => subst[&ident].to_token_stream(),
+        TokenTree::Group(group) => {
+          let mut new_group =
+            Group::new(group.delimiter(), self.subst_tokens(group.stream(), subst));
+          new_group.set_span(group.span());
+          TokenTree::Group(new_group).into()
+        }
+        token => token.into(),
+      })
+      .collect()
+  }
+}
@@ -0,0 +1,944 @@
+use crate::*;
+use syn::{parse_quote, Pat, PatIdent, Type, Visibility};
+
+#[derive(Debug, Clone)]
+pub struct AgentSig {
+  pub generics: Vec<Ident>,
+  pub keys: Option<Vec<Ident>>,
+  pub fields: Vec<Option<(Sign, DefType)>>,
+  pub principal: usize,
+  pub result: Option<usize>,
+  pub call_order: Vec<usize>,
+}
+
+#[derive(Debug)]
+pub struct DefCompilation<'a> {
+  pub sigs: &'a BTreeMap<Ident, AgentSig>,
+  pub variants: &'a BTreeMap<Ident, Vec<Ident>>,
+  pub generics: Vec<Ident>,
+  pub items: Vec<Item>,
+  pub fresh: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct Row {
+  pub pats: Vec<Pattern>,
+  pub prelude: Vec<TokenStream>,
+  pub bound: Vec<Ident>,
+  pub consumed: Vec<Ident>,
+  pub term: Term,
+}
+
+#[derive(Debug, Clone)]
+pub struct Ctor {
+  pub src: Option<Ident>,
+  pub name: Ident,
+  pub args: Vec<Type>,
+  pub sig: Option<AgentSig>,
+  pub ports: Vec<Option<DefType>>,
+  pub is_port: Vec<bool>,
+}
+
+#[derive(Debug)]
+struct GroupRow {
+  row: Row,
+  payloads: Vec<TokenStream>,
+  binds: bool,
+  refutable: bool,
+}
+
+impl Program {
+  pub fn expand_defs(&mut self) {
+    let mut sigs = self.agent_sigs();
+    let mut variants = self.enum_variants();
+    for u in self.uses() {
+      let Some(m) = self.module(&u.path) else {
+        continue;
+      };
+      let module_sigs = m.program.agent_sigs();
+      let names = self.imported_names(u, module_sigs.keys());
+      for (name, local) in &names {
+        sigs
+          .entry(local.clone())
+          .or_insert_with(|| module_sigs[name].clone());
+      }
+      let module_variants = m.program.enum_variants();
+      for (name, local) in &names {
+        let Some(siblings) = module_variants.get(name) else {
+          continue;
+        };
+        let siblings = siblings
+          .iter()
+          .filter_map(|v| names.get(v).cloned())
+          .collect();
+        variants.entry(local.clone()).or_insert(siblings);
+      }
+    }
+    for item in std::mem::take(&mut self.items) {
+      match item {
+        Item::Def(d) => {
+          let mut comp = DefCompilation {
+            sigs: &sigs,
+            variants: &variants,
+            generics: d.generics.clone(),
+            items: vec![],
+            fresh: 0,
+          };
+          self.compile_def(&mut comp, &d);
+          for mut item in comp.items {
+            item.attrs_mut().splice(0..0, d.attrs.iter().cloned());
+            self.items.push(item);
+          }
+        }
+        item => self.items.push(item),
+      }
+    }
+  }
+
+  fn enum_variants(&self) -> BTreeMap<Ident, Vec<Ident>> {
+    let mut variants = BTreeMap::new();
+    for e in self.items.iter().filter_map(Item::as_enum) {
+      let names = e
+        .variants
+        .iter()
+        .filter(|v| self.cfg_attrs(&v.attrs).is_empty())
+        .map(|v| v.name.clone())
+        .collect::<Vec<_>>();
+      for v in &e.variants {
+        variants.insert(v.name.clone(), names.clone());
+      }
+    }
+    variants
+  }
+
+  // Maps the agents of an imported module that `u` brings into scope to the
+  // names they go by here.
+  fn imported_names<'a>(
+    &self,
+    u: &Use,
+    declared: impl Iterator<Item = &'a Ident>,
+  ) -> BTreeMap<Ident, Ident> {
+    declared
+      .filter_map(|name| {
+        let local = match u.agents.iter().find(|a| &a.name == name) {
+          Some(a) => a.ident().clone(),
+          None => u.glob.as_ref().map(|_| name.clone())?,
+        };
+        Some((name.clone(), local))
+      })
+      .collect()
+  }
+
+  fn agent_sigs(&self) -> BTreeMap<Ident, AgentSig> {
+    let mut sigs = BTreeMap::new();
+    for item in &self.items {
+      match item {
+        Item::Struct(s) => {
+          let keys = match &s.fields {
+            Fields::Unnamed(_) => None,
+            Fields::Named(f) => Some(f.entries.iter().map(|x| x.key.clone()).collect()),
+          };
+          let fields = s
+            .fields
+            .values()
+            .map(|f| f.port().map(|p| (p.sign, self.def_type_of(p))))
+            .collect();
+          if let Some(sig) = self.agent_sig(s.generics.clone(), keys, fields) {
+            sigs.insert(s.name.clone(), sig);
+          }
+        }
+        Item::Enum(e) => {
+          let principal = DefType {
+            name: e.name.clone(),
+            args: e
+              .generics
+              .iter()
+              .map(|param| parse_quote!(#param))
+              .collect(),
+          };
+          for variant in &e.variants {
+            let fields = std::iter::once(Some((Sign::Plus, principal.clone())))
+              .chain(
+                variant
+                  .fields
+                  .iter()
+                  .flat_map(|f| f.values())
+                  .map(|f| f.port().map(|p| (p.sign, self.def_type_of(p)))),
+              )
+              .collect();
+            if let Some(sig) = self.agent_sig(e.generics.clone(), None, fields) {
+              sigs.insert(variant.name.clone(), sig);
+            }
+          }
+        }
+        Item::Def(d) => {
+          let Some(p) = self.principal_column(d.clauses.iter().map(|c| &c.args[..])) else {
+            continue;
+          };
+          let n = d.inputs.len();
+          let fields = std::iter::once(p)
+            .chain((0..n).filter(|&i| i != p))
+            .map(|i| Some((Sign::Minus, d.inputs[i].clone())))
+            .chain([Some((Sign::Plus, d.output.clone()))])
+            .collect();
+          let call_order = (0..n)
+            .map(|i| match i.cmp(&p) {
+              std::cmp::Ordering::Less => i + 1,
+              std::cmp::Ordering::Equal => 0,
+              std::cmp::Ordering::Greater => i,
+            })
+            .collect();
+          sigs.insert(
+            d.name.clone(),
+            AgentSig {
+              generics: d.generics.clone(),
+              keys: None,
+              fields,
+              principal: 0,
+              result: Some(n),
+              call_order,
+            },
+          );
+        }
+        _ => {}
+      }
+    }
+    sigs
+  }
+
+  fn agent_sig(
+    &self,
+    generics: Vec<Ident>,
+    keys: Option<Vec<Ident>>,
+    fields: Vec<Option<(Sign, DefType)>>,
+  ) -> Option<AgentSig> {
+    let principal = fields.iter().position(Option::is_some)?;
+    let result = fields
+      .iter()
+      .position(|f| matches!(f, Some((Sign::Plus, _))));
+    let call_order = (0..fields.len()).filter(|&i| Some(i) != result).collect();
+    Some(AgentSig {
+      generics,
+      keys,
+      fields,
+      principal,
+      result,
+      call_order,
+    })
+  }
+
+  fn def_type_of(&self, port: &PortType) -> DefType {
+    DefType {
+      name: port.name.clone(),
+      args: port.args.clone(),
+    }
+  }
+
+  fn principal_column<'a>(&self, rows: impl Iterator<Item = &'a [Pattern]>) -> Option<usize> {
+    let rows = rows.collect::<Vec<_>>();
+    let n = rows.iter().map(|r| r.len()).max()?;
+    (0..n).find(|&i| {
+      rows
+        .iter()
+        .any(|r| matches!(r.get(i), Some(Pattern::Agent(_))))
+    })
+  }
+
+  fn compile_def(&self, comp: &mut DefCompilation, d: &Def) {
+    if d.clauses.is_empty() {
+      emit_error!(d.name.span(), "expected at least one clause");
+      return;
+    }
+    let mut rows = vec![];
+    for clause in &d.clauses {
+      if clause.args.len() != d.inputs.len() {
+        emit_error!(
+          clause.name.span(),
+          "expected {} arguments, found {}",
+          d.inputs.len(),
+          clause.args.len()
+        );
+        return;
+      }
+      rows.push(Row {
+        pats: clause.args.clone(),
+        prelude: vec![],
+        bound: vec![],
+        consumed: vec![],
+        term: clause.term.clone(),
+      });
+    }
+    let cols = d.inputs.iter().cloned().map(Some).collect();
+    self.compile_def_agent(comp, &d.name, &d.vis, cols, &d.output, rows);
+  }
+
+  fn compile_def_agent(
+    &self,
+    comp: &mut DefCompilation,
+    name: &Ident,
+    vis: &Visibility,
+    cols: Vec<Option<DefType>>,
+    out: &DefType,
+    rows: Vec<Row>,
+  ) -> Option<usize> {
+    let Some(p) = self.principal_column(rows.iter().map(|r| &r.pats[..])) else {
+      emit_error!(
+        name.span(),
+        "expected an argument to be matched by a constructor pattern"
+      );
+      return None;
+    };
+    let others = (0..cols.len()).filter(|&i| i != p).collect::<Vec<_>>();
+    let mut ports = vec![];
+    for &i in std::iter::once(&p).chain(&others) {
+      let Some(ty) = &cols[i] else {
+        emit_error!(
+          name.span(),
+          "cannot match nested patterns inside an agent with unknown port types"
+        );
+        return None;
+      };
+      let ty = self.quote_def_type(ty);
+      ports.push(quote!(-#ty));
+    }
+    let out_ty = self.quote_def_type(out);
+    let generics = &comp.generics;
+    let generics = (!generics.is_empty()).then(|| quote!(<#(#generics),*>));
+    comp.items.push(Item::Struct(self.parse_generated(
+      quote_spanned!(name.span()=>
+        #vis struct #name #generics(#(#ports,)* +#out_ty);
+      ),
+    )));
+
+    for ctor in self.column_ctors(comp, &rows, p) {
+      let mut group = vec![];
+      for row in &rows {
+        if let Some(row) = self.group_row(comp, &ctor, row, p, &others) {
+          group.push(row);
+        }
+      }
+      if group.is_empty() {
+        emit_error!(name.span(), "missing a clause for `{}`", ctor.name);
+        continue;
+      }
+      let nested = group
+        .iter()
+        .any(|r| r.row.pats.iter().any(|p| matches!(p, Pattern::Agent(_))));
+      if !nested {
+        for row in group {
+          let refutable = row.refutable;
+          self.compile_def_leaf(comp, name, &ctor, row);
+          if !refutable {
+            break;
+          }
+        }
+        continue;
+      }
+      if group.iter().any(|r| r.binds) {
+        emit_error!(
+          ctor.name.span(),
+          "payload patterns cannot be combined with nested patterns"
+        );
+        continue;
+      }
+      let helper = format_ident!("{}_{}", name, ctor.name);
+      let helper_cols = ctor
+        .ports
+        .iter()
+        .cloned()
+        .chain(others.iter().map(|&i| cols[i].clone()))
+        .collect::<Vec<_>>();
+      let n = helper_cols.len();
+      let rows = group.into_iter().map(|r| r.row).collect();
+      let Some(hp) = self.compile_def_agent(
+        comp,
+        &helper,
+        &Visibility::Inherited,
+        helper_cols,
+        out,
+        rows,
+      ) else {
+        continue;
+      };
+      let wires = (0..n).map(|_| self.fresh_wire(comp)).collect::<Vec<_>>();
+      let o = self.fresh_wire(comp);
+      let ctor_ports = ctor.ports.len();
+      let a_fields = wires[ctor_ports..].iter().map(|w| quote!(#w));
+      let payloads = ctor.is_port.iter().filter(|x| !**x).map(|_| quote!($_));
+      let c_pat = self.render_ctor(
+        &ctor,
+        wires[..ctor_ports].iter().map(|w| quote!(#w)),
+        payloads,
+      );
+      let h_fields = std::iter::once(hp)
+        .chain((0..n).filter(|&i| i != hp))
+        .map(|i| &wires[i]);
+      let a_ref = self.quote_def_agent_ref(comp, name);
+      let h_ref = self.quote_def_agent_ref(comp, &helper);
+      let impl_generics = &comp.generics;
+      comp.items.push(Item::Impl(self.parse_generated(
+        quote_spanned!(name.span()=>
+          impl<#(#impl_generics),*> #a_ref(_, #(#a_fields,)* #o) for #c_pat {
+            #h_ref(#(#h_fields,)* #o)
+          }
+        ),
+      )));
+    }
+    Some(p)
+  }
+
+  fn column_ctors(&self, comp: &DefCompilation, rows: &[Row], p: usize) -> Vec<Ctor> {
+    let mut ctors: Vec<Ctor> = vec![];
+    for row in rows {
+      let Pattern::Agent(a) = &row.pats[p] else {
+        continue;
+      };
+      if ctors.iter().any(|c| c.name == a.name && c.src == a.src) {
+        continue;
+      }
+      let ctor = self.ctor_of(comp, a.src.clone(), a.name.clone(), a.args.clone(), Some(a));
+      ctors.push(ctor);
+      if a.src.is_some() {
+        continue;
+      }
+      for variant in comp.variants.get(&a.name).into_iter().flatten() {
+        if !ctors.iter().any(|c| &c.name == variant && c.src.is_none()) {
+          ctors.push(self.ctor_of(comp, None, variant.clone(), a.args.clone(), None));
+        }
+      }
+    }
+    ctors
+  }
+
+  fn ctor_of(
+    &self,
+    comp: &DefCompilation,
+    src: Option<Ident>,
+    name: Ident,
+    args: Vec<Type>,
+    pat: Option<&DefAgent<Pattern>>,
+  ) -> Ctor {
+    let sig = if src.is_none() {
+      comp.sigs.get(&name).cloned()
+    } else {
+      None
+    };
+    let (ports, is_port) = match &sig {
+      Some(sig) => {
+        let fields = sig
+          .fields
+          .iter()
+          .enumerate()
+          .filter(|(i, _)| *i != sig.principal)
+          .map(|(_, f)| f);
+        let is_port = fields.clone().map(Option::is_some).collect();
+        let subst = sig
+          .generics
+          .iter()
+          .cloned()
+          .zip(args.iter().cloned())
+          .collect();
+        let ports = fields
+          .flatten()
+          .map(|(_, ty)| Some(self.subst_def_type(ty, &subst)))
+          .collect();
+        (ports, is_port)
+      }
+      None => {
+        let is_port = pat
+          .map(|a| {
+            a.fields
+              .iter()
+              .map(|f| !matches!(f, Pattern::Payload(_)))
+              .collect::<Vec<_>>()
+          })
+          .unwrap_or_default();
+        let ports = is_port.iter().filter(|x| **x).map(|_| None).collect();
+        (ports, is_port)
+      }
+    };
+    Ctor {
+      src,
+      name,
+      args,
+      sig,
+      ports,
+      is_port,
+    }
+  }
+
+  fn group_row(
+    &self,
+    comp: &mut DefCompilation,
+    ctor: &Ctor,
+    row: &Row,
+    p: usize,
+    others: &[usize],
+  ) -> Option<GroupRow> {
+    let mut sub = vec![];
+    let mut payloads = vec![];
+    let mut binds = false;
+    let mut refutable = false;
+    let mut prelude = row.prelude.clone();
+    let mut bound = row.bound.clone();
+    let mut consumed = row.consumed.clone();
+    match &row.pats[p] {
+      Pattern::Agent(a) => {
+        if a.name != ctor.name || a.src != ctor.src {
+          return None;
+        }
+        if a.fields.len() != ctor.is_port.len() {
+          emit_error!(
+            a.name.span(),
+            "expected {} fields, found {}",
+            ctor.is_port.len(),
+            a.fields.len()
+          );
+          return None;
+        }
+        for (field, &is_port) in a.fields.iter().zip(&ctor.is_port) {
+          match (field, is_port) {
+            (Pattern::Payload(PayloadPat { pat, .. }), false) => {
+              binds |= !matches!(pat, Pat::Wild(_));
+              refutable |= !matches!(
+                pat,
+                Pat::Wild(_) | Pat::Ident(PatIdent { subpat: None, .. })
+              );
+              payloads.push(quote!($#pat));
+            }
+            (Pattern::Var(x), false) => {
+              binds = true;
+              payloads.push(quote!($#x));
+            }
+            (Pattern::Wild(_), false) => payloads.push(quote!($_)),
+            (Pattern::Payload(p), true) => {
+              emit_error!(p.dollar.span, "expected a port pattern");
+              return None;
+            }
+            (_, false) => {
+              emit_error!(a.name.span(), "expected a payload pattern");
+              return None;
+            }
+            (field, true) => sub.push(field.clone()),
+          }
+        }
+      }
+      Pattern::Var(x) => {
+        let mut fields = vec![];
+        let mut payload_fields = vec![];
+        for &is_port in &ctor.is_port {
+          if is_port {
+            let w = self.fresh_wire(comp);
+            sub.push(Pattern::Var(w.clone()));
+            fields.push(quote!(#w));
+            consumed.push(w);
+          } else {
+            let v = self.fresh_wire(comp);
+            binds = true;
+            payloads.push(quote!($#v));
+            payload_fields.push(quote!($#v));
+          }
+        }
+        let principal = quote!(#x);
+        prelude.push(self.render_agent(
+          &ctor.src,
+          &ctor.name,
+          &ctor.args,
+          ctor.sig.as_ref(),
+          self.ctor_fields(
+            ctor,
+            principal,
+            fields.into_iter(),
+            payload_fields.into_iter(),
+          ),
+        ));
+        bound.push(x.clone());
+      }
+      Pattern::Wild(_) => {
+        for &is_port in &ctor.is_port {
+          if is_port {
+            sub.push(Pattern::Wild(Default::default()));
+          } else {
+            payloads.push(quote!($_));
+          }
+        }
+      }
+      Pattern::Payload(p) => {
+        emit_error!(p.dollar.span, "expected a port pattern");
+        return None;
+      }
+    }
+    sub.extend(others.iter().map(|&i| row.pats[i].clone()));
+    Some(GroupRow {
+      row: Row {
+        pats: sub,
+        prelude,
+        bound,
+        consumed,
+        term: row.term.clone(),
+      },
+      payloads,
+      binds,
+      refutable,
+    })
+  }
+
+  fn compile_def_leaf(&self, comp: &mut DefCompilation, name: &Ident, ctor: &Ctor, row: GroupRow) {
+    let mut body = vec![];
+    let mut bound = vec![];
+    let mut fields = vec![];
+    for pat in &row.row.pats {
+      match pat {
+        Pattern::Var(x) => {
+          bound.push(x.clone());
+          fields.push(quote!(#x));
+        }
+        Pattern::Wild(token) => {
+          let w = self.fresh_wire(comp);
+          body.push(quote_spanned!(token.span=> Erase(#w)));
+          fields.push(quote!(#w));
+        }
+        _ => unreachable!(),
+      }
+    }
+    body.extend(row.row.prelude);
+    bound.extend(row.row.bound);
+    bound.retain(|x| !row.row.consumed.contains(x));
+    for (i, x) in bound.iter().enumerate() {
+      if bound[..i].contains(x) {
+        emit_error!(x.span(), "variable bound more than once");
+      }
+    }
+
+    let mut counts = BTreeMap::new();
+    self.count_vars(&row.row.term, &mut counts);
+    let mut uses = BTreeMap::new();
+    for x in &bound {
+      let n = counts.remove(x).unwrap_or(0);
+      let wires = match n {
+        0 => {
+          body.push(quote!(Erase(#x)));
+          vec![]
+        }
+        1 => vec![x.clone()],
+        n => {
+          let mut wires = vec![];
+          let mut src = x.clone();
+          for _ in 1..n {
+            let a = self.fresh_wire(comp);
+            let b = self.fresh_wire(comp);
+            body.push(quote!(Clone(#src, #a, #b)));
+            wires.push(a);
+            src = b;
+          }
+          wires.push(src);
+          wires
+        }
+      };
+      uses.insert(x.clone(), wires);
+    }
+    if !counts.is_empty() {
+      for x in counts.keys() {
+        emit_error!(x.span(), "unbound variable `{}`", x);
+      }
+      return;
+    }
+
+    let out = match &row.row.term {
+      Term::Hole(_) | Term::Payload(_) => {
+        emit_error!(name.span(), "expected a port expression");
+        return;
+      }
+      term => self.compile_term(comp, term, &mut body, &mut uses),
+    };
+
+    let ctor_ports = ctor.ports.len();
+    let a_fields = &fields[ctor_ports..];
+    let c_pat = self.render_ctor(
+      ctor,
+      fields[..ctor_ports].iter().cloned(),
+      row.payloads.into_iter(),
+    );
+    let a_ref = self.quote_def_agent_ref(comp, name);
+    let impl_generics = &comp.generics;
+    comp.items.push(Item::Impl(self.parse_generated(
+      quote_spanned!(name.span()=>
+        impl<#(#impl_generics),*> #a_ref(_, #(#a_fields,)* #out) for #c_pat {
+          #(#body)*
+        }
+      ),
+    )));
+  }
+
+  fn count_vars<'a>(&self, term: &'a Term, counts: &mut BTreeMap<&'a Ident, usize>) {
+    match term {
+      Term::Var(x) => *counts.entry(x).or_insert(0) += 1,
+      Term::Agent(a) => {
+        for field in &a.fields {
+          self.count_vars(field, counts);
+        }
+      }
+      _ => {}
+    }
+  }
+
+  fn compile_term(
+    &self,
+    comp: &mut DefCompilation,
+    term: &Term,
+    body: &mut Vec<TokenStream>,
+    uses: &mut BTreeMap<Ident, Vec<Ident>>,
+  ) -> TokenStream {
+    match term {
+      Term::Var(x) => match uses.get_mut(x).and_then(Vec::pop) {
+        Some(w) => quote!(#w),
+        None => quote!(#x),
+      },
+      Term::Hole(token) => {
+        emit_error!(token.span, "unexpected `_`");
+        quote!(_)
+      }
+      Term::Payload(p) => {
+        emit_error!(p.dollar.span, "unexpected payload");
+        quote!(_)
+      }
+      Term::Agent(a) => {
+        let r = self.fresh_wire(comp);
+        let holes = a
+          .fields
+          .iter()
+          .filter(|f| matches!(f, Term::Hole(_)))
+          .count();
+        if holes > 1 {
+          emit_error!(a.name.span(), "expected at most one `_`");
+        }
+        let args = a
+          .fields
+          .iter()
+          .map(|f| match f {
+            Term::Hole(_) => quote!(#r),
+            Term::Payload(PayloadExpr { expr, .. }) => quote!($#expr),
+            f => self.compile_term(comp, f, body, uses),
+          })
+          .collect::<Vec<_>>();
+        let sig = if a.src.is_none() {
+          comp.sigs.get(&a.name)
+        } else {
+          None
+        };
+        let fields = match sig {
+          _ if holes > 0 => args,
+          Some(sig) => {
+            let Some(result) = sig.result else {
+              emit_error!(a.name.span(), "`{}` has no output port", a.name);
+              return quote!(#r);
+            };
+            if args.len() != sig.call_order.len() {
+              emit_error!(
+                a.name.span(),
+                "expected {} arguments, found {}",
+                sig.call_order.len(),
+                args.len()
+              );
+              return quote!(#r);
+            }
+            let mut fields = vec![quote!(); sig.fields.len()];
+            fields[result] = quote!(#r);
+            for (&i, arg) in sig.call_order.iter().zip(args) {
+              fields[i] = arg;
+            }
+            fields
+          }
+          None => args.into_iter().chain([quote!(#r)]).collect(),
+        };
+        body.push(self.render_agent(&a.src, &a.name, &a.args, sig, fields));
+        quote!(#r)
+      }
+    }
+  }
+
+  fn ctor_fields(
+    &self,
+    ctor: &Ctor,
+    principal: TokenStream,
+    mut ports: impl Iterator<Item = TokenStream>,
+    mut payloads: impl Iterator<Item = TokenStream>,
+  ) -> Vec<TokenStream> {
+    let mut fields = ctor
+      .is_port
+      .iter()
+      .map(|&is_port| {
+        if is_port {
+          ports.next().unwrap()
+        } else {
+          payloads.next().unwrap()
+        }
+      })
+      .collect::<Vec<_>>();
+    let principal_idx = ctor.sig.as_ref().map(|sig| sig.principal).unwrap_or(0);
+    fields.insert(principal_idx, principal);
+    fields
+  }
+
+  fn render_ctor(
+    &self,
+    ctor: &Ctor,
+    ports: impl Iterator<Item = TokenStream>,
+    payloads: impl Iterator<Item = TokenStream>,
+  ) -> TokenStream {
+    let fields = self.ctor_fields(ctor, quote!(_), ports, payloads);
+    self.render_agent(&ctor.src, &ctor.name, &ctor.args, ctor.sig.as_ref(), fields)
+  }
+
+  fn render_agent(
+    &self,
+    src: &Option<Ident>,
+    name: &Ident,
+    args: &[Type],
+    sig: Option<&AgentSig>,
+    fields: Vec<TokenStream>,
+  ) -> TokenStream {
+    let src = self.quote_src(src);
+    let args = (!args.is_empty()).then(|| quote!(<#(#args),*>));
+    match sig.and_then(|sig| sig.keys.as_ref()) {
+      Some(keys) => quote!(#src #name #args { #(#keys: #fields),* }),
+      None => quote!(#src #name #args (#(#fields),*)),
+    }
+  }
+
+  fn quote_def_type(&self, ty: &DefType) -> TokenStream {
+    let name = &ty.name;
+    let args = &ty.args;
+    if args.is_empty() {
+      quote!(#name)
+    } else {
+      quote!(#name<#(#args),*>)
+    }
+  }
+
+  fn subst_def_type(&self, ty: &DefType, subst: &BTreeMap<Ident, Type>) -> DefType {
+    let tokens = self.subst_tokens(self.quote_def_type(ty), subst);
+    let (name, args) = self.port_type_of(&self.parse_generated(tokens));
+    DefType { name, args }
+  }
+
+  fn quote_def_agent_ref(&self, comp: &DefCompilation, name: &Ident) -> TokenStream {
+    let generics = &comp.generics;
+    if generics.is_empty() {
+      quote!(#name)
+    } else {
+      quote!(#name<#(#generics),*>)
+    }
+  }
+
+  fn fresh_wire(&self, comp: &mut DefCompilation) -> Ident {
+    comp.fresh += 1;
+    format_ident!("__d{}", comp.fresh - 1)
+  }
+
+  fn parse_generated<T: syn::parse::Parse>(&self, tokens: TokenStream) -> T {
+    match syn::parse2(tokens) {
+      Ok(x) => x,
+      Err(err) => abort!(err.span(), "{}", err),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::*;
+
+  // Each rule lowered from `name`'s clauses, as the agents of its redex
+  // followed by the agents it creates.
+  fn rules(source: &str, name: &str) -> Vec<String> {
+    let mut program = syn::parse_str::<Program>(source).unwrap();
+    collect_diagnostics(|| program.expand_defs()).unwrap();
+    program
+      .items
+      .iter()
+      .filter_map(Item::as_impl)
+      .filter(|i| {
+        i.agents()
+          .next()
+          .is_some_and(|a| a.name.to_string().starts_with(name))
+      })
+      .map(|i| {
+        let redex = i.agents().map(|a| a.name.to_string()).collect::<Vec<_>>();
+        let net = i
+          .net
+          .agents
+          .iter()
+          .map(|a| a.name.to_string())
+          .collect::<Vec<_>>();
+        format!("{} => {}", redex.join(" ~ "), net.join(" "))
+      })
+      .collect()
+  }
+
+  const NAT: &str = "
+    pub struct Clone(-Nat, +Nat, +Nat);
+    pub struct Erase(-Nat);
+    pub enum Nat {
+      Zero,
+      Succ(-Nat),
+    }
+    def Add(Nat, Nat) -> Nat {
+      Add(Zero, y) = y;
+      Add(Succ(x), y) = Succ(Add(x, y));
+    }
+  ";
+
+  #[test]
+  fn clones_variables_used_more_than_once() {
+    let source = format!(
+      "{NAT}
+      def Double(Nat) -> Nat {{
+        Double(Zero) = Zero;
+        Double(Succ(x)) = Succ(Succ(Add(x, x)));
+      }}"
+    );
+    assert_eq!(
+      rules(&source, "Double"),
+      [
+        "Double ~ Zero => Zero",
+        "Double ~ Succ => Clone Add Succ Succ"
+      ]
+    );
+  }
+
+  #[test]
+  fn erases_unused_variables() {
+    let source = format!(
+      "{NAT}
+      def First(Nat, Nat) -> Nat {{
+        First(Zero, y) = Zero;
+        First(Succ(x), y) = Succ(x);
+      }}"
+    );
+    assert_eq!(
+      rules(&source, "First"),
+      ["First ~ Zero => Erase Zero", "First ~ Succ => Erase Succ"]
+    );
+  }
+
+  #[test]
+  fn matches_nested_patterns_with_helper_agents() {
+    let source = format!(
+      "{NAT}
+      def Half(Nat) -> Nat {{
+        Half(Zero) = Zero;
+        Half(Succ(Zero)) = Zero;
+        Half(Succ(Succ(x))) = Succ(Half(x));
+      }}"
+    );
+    assert_eq!(
+      rules(&source, "Half"),
+      [
+        "Half ~ Zero => Zero",
+        "Half_Succ ~ Zero => Zero",
+        "Half_Succ ~ Succ => Half Succ",
+        "Half ~ Succ => Half_Succ"
+      ]
+    );
+  }
+}
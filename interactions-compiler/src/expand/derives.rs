@@ -0,0 +1,355 @@
+use crate::*;
+
+impl Program {
+  pub fn expand_derives(&mut self) {
+    let mut uses = vec![];
+    let impls = self
+      .items
+      .iter()
+      .filter_map(Item::as_struct)
+      .flat_map(|s| self.derive_impls(s, &mut uses))
+      .collect::<Vec<_>>();
+    self.items.extend(impls.into_iter().map(Item::Impl));
+    self.items.extend(uses.into_iter().map(Item::Use));
+  }
+
+  fn derive_impls(&self, s: &Struct, uses: &mut Vec<Use>) -> Vec<Impl> {
+    let derives = &s.derives;
+    if derives.erase.is_none() && derives.clone.is_none() {
+      return vec![];
+    }
+    let mut ports = s.ports().map(|(_, (_, port))| port);
+    let Some(principal) = ports.next() else {
+      return vec![];
+    };
+    if principal.sign != Sign::Plus {
+      emit_error!(
+        principal.name.span(),
+        "derived agents must have a positive principal port"
+      );
+      return vec![];
+    }
+    if let Some(port) = ports.find(|port| port.sign != Sign::Minus) {
+      emit_error!(
+        port.name.span(),
+        "derived agents must have only negative auxiliary ports"
+      );
+      return vec![];
+    }
+    let mut impls = vec![];
+    if let Some(derive) = &derives.erase {
+      impls.push(self.derive_erase(s, &principal.name, derive, uses));
+    }
+    if let Some(derive) = &derives.clone {
+      impls.push(self.derive_clone(s, &principal.name, derive, uses));
+    }
+    for i in &mut impls {
+      i.attrs = self.cfg_attrs(&s.attrs).into_iter().cloned().collect();
+    }
+    impls
+  }
+
+  fn derive_erase(&self, s: &Struct, ty: &Ident, derive: &Ident, uses: &mut Vec<Use>) -> Impl {
+    let name = &s.name;
+    let eraser = self.derive_agent(&s.derives.erasers, &s.derives.erase_src, ty, derive);
+    let mut erasers = vec![];
+    let pat = self.derive_fields(
+      s,
+      s.fields.values().enumerate().map(|(i, field)| match field {
+        StructField::Port(port) => {
+          if self.is_principal(s, i) {
+            return quote!(_);
+          }
+          let eraser = self.port_agent(s, port, DeriveKind::Erase, derive, uses);
+          let x = format_ident!("x{}", i);
+          erasers.push(quote!(#eraser(#x)));
+          quote!(#x)
+        }
+        StructField::Payload(_) => quote!($_),
+      }),
+    );
+    self.parse_derived_impl(quote_spanned!(derive.span()=>
+      impl #eraser(_) for #name #pat {
+        #(#erasers)*
+      }
+    ))
+  }
+
+  fn derive_clone(&self, s: &Struct, ty: &Ident, derive: &Ident, uses: &mut Vec<Use>) -> Impl {
+    let name = &s.name;
+    let cloner = self.derive_agent(&s.derives.cloners, &s.derives.clone_src, ty, derive);
+    let mut cloners = vec![];
+    let mut pat = vec![];
+    let mut a = vec![];
+    let mut b = vec![];
+    for (i, field) in s.fields.values().enumerate() {
+      match field {
+        StructField::Port(port) => {
+          if self.is_principal(s, i) {
+            pat.push(quote!(_));
+            a.push(quote!(a));
+            b.push(quote!(b));
+            continue;
+          }
+          let cloner = self.port_agent(s, port, DeriveKind::Clone, derive, uses);
+          let x = format_ident!("x{}", i);
+          let xa = format_ident!("x{}a", i);
+          let xb = format_ident!("x{}b", i);
+          cloners.push(quote!(#cloner(#x, #xa, #xb)));
+          pat.push(quote!(#x));
+          a.push(quote!(#xa));
+          b.push(quote!(#xb));
+        }
+        StructField::Payload(_) => {
+          let p = format_ident!("p{}", i);
+          pat.push(quote!($#p));
+          a.push(quote!($::core::clone::Clone::clone(&#p)));
+          b.push(quote!($#p));
+        }
+      }
+    }
+    let pat = self.derive_fields(s, pat.into_iter());
+    let a = self.derive_fields(s, a.into_iter());
+    let b = self.derive_fields(s, b.into_iter());
+    self.parse_derived_impl(quote_spanned!(derive.span()=>
+      impl #cloner(_, a, b) for #name #pat {
+        #(#cloners)*
+        #name #a
+        #name #b
+      }
+    ))
+  }
+
+  fn is_principal(&self, s: &Struct, idx: usize) -> bool {
+    s.ports().next().map(|(_, (i, _))| i) == Some(idx)
+  }
+
+  fn derive_agent(
+    &self,
+    agents: &[DeriveAgent],
+    src: &Option<Ident>,
+    ty: &Ident,
+    derive: &Ident,
+  ) -> TokenStream {
+    let (src, name) = match agents.iter().find(|x| &x.ty == ty) {
+      Some(DeriveAgent { src, name, .. }) => (src, name),
+      None => (src, derive),
+    };
+    let src = self.quote_src(src);
+    quote!(#src #name)
+  }
+
+  // The agent that erases or clones the auxiliary `port` of `s`: the one `s`
+  // picks for its type, or else the one the agents of that type derive with,
+  // looked up in this module and then in the modules it imports.
+  fn port_agent(
+    &self,
+    s: &Struct,
+    port: &PortType,
+    kind: DeriveKind,
+    derive: &Ident,
+    uses: &mut Vec<Use>,
+  ) -> TokenStream {
+    let (_, agents, _) = kind.of(&s.derives);
+    if let Some(DeriveAgent { src, name, .. }) = agents.iter().find(|x| x.ty == port.name) {
+      let src = self.quote_src(src);
+      return quote!(#src #name);
+    }
+    let derived = |program: &Program| {
+      program
+        .items
+        .iter()
+        .filter_map(Item::as_struct)
+        .filter(|t| {
+          t.ports()
+            .next()
+            .is_some_and(|(_, (_, p))| p.name == port.name)
+        })
+        .find_map(|t| kind.agent(&t.derives, &port.name))
+    };
+    if let Some((src, name)) = derived(self) {
+      let src = self.quote_src(&src);
+      return quote!(#src #name);
+    }
+    let imported = self.modules.iter().enumerate().find_map(|(i, m)| {
+      let (src, name) = derived(&m.program)?;
+      Some(self.resolve_agent(Some(i), &src, &name))
+    });
+    match imported {
+      Some(Some(agent)) => {
+        let src = agent.module.map(|path| self.module_alias(&path, uses));
+        let src = self.quote_src(&src);
+        let name = agent.name;
+        quote!(#src #name)
+      }
+      _ => {
+        emit_error!(
+          port.name.span(),
+          "no agent to {} `{}` with: derive `{}` for it, or pick one with `#[{}({} = ...)]`",
+          kind.verb(),
+          port.name,
+          derive,
+          kind.verb(),
+          port.name
+        );
+        quote!(#derive)
+      }
+    }
+  }
+
+  fn derive_fields(&self, s: &Struct, values: impl Iterator<Item = TokenStream>) -> TokenStream {
+    match &s.fields {
+      Fields::Unnamed(_) => quote!((#(#values),*)),
+      Fields::Named(f) => {
+        let keys = f.entries.iter().map(|x| &x.key);
+        quote!({ #(#keys: #values),* })
+      }
+    }
+  }
+
+  fn parse_derived_impl(&self, tokens: TokenStream) -> Impl {
+    match syn::parse2(tokens) {
+      Ok(i) => i,
+      Err(err) => abort!(err.span(), "{}", err),
+    }
+  }
+}
+
+#[derive(Clone, Copy)]
+enum DeriveKind {
+  Erase,
+  Clone,
+}
+
+impl DeriveKind {
+  // The derive, the agents picked per port type, and the module the derived
+  // agent is named in.
+  fn of(self, derives: &Derives) -> (&Option<Ident>, &[DeriveAgent], &Option<Ident>) {
+    match self {
+      DeriveKind::Erase => (&derives.erase, &derives.erasers, &derives.erase_src),
+      DeriveKind::Clone => (&derives.clone, &derives.cloners, &derives.clone_src),
+    }
+  }
+
+  // The agent that `derives` uses for ports of type `ty`, if any.
+  fn agent(self, derives: &Derives, ty: &Ident) -> Option<(Option<Ident>, Ident)> {
+    let (derive, agents, src) = self.of(derives);
+    match agents.iter().find(|x| &x.ty == ty) {
+      Some(a) => Some((a.src.clone(), a.name.clone())),
+      None => Some((src.clone(), derive.clone()?)),
+    }
+  }
+
+  fn verb(self) -> &'static str {
+    match self {
+      DeriveKind::Erase => "erase",
+      DeriveKind::Clone => "clone",
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::*;
+
+  // The agents each derived rule for `name` creates.
+  fn derived(source: &str, name: &str) -> Result<Vec<Vec<String>>, Vec<String>> {
+    let mut program = syn::parse_str::<Program>(source).unwrap();
+    collect_diagnostics(|| program.expand()).map_err(|diagnostics| {
+      diagnostics
+        .into_iter()
+        .map(|d| d.message)
+        .collect::<Vec<_>>()
+    })?;
+    Ok(
+      program
+        .items
+        .iter()
+        .filter_map(Item::as_impl)
+        .filter(|i| i.agents().any(|a| a.name == name))
+        .map(|i| {
+          i.net
+            .agents
+            .iter()
+            .map(|a| match &a.src {
+              Some(src) => format!("{}::{}", src, a.name),
+              None => a.name.to_string(),
+            })
+            .collect()
+        })
+        .collect(),
+    )
+  }
+
+  const MODULES: &str = "
+    @module libs::nat {
+      #[derive(Erase)]
+      pub enum Nat {
+        Zero,
+        Succ(-Nat),
+      }
+      pub struct Erase(-Nat);
+    }
+    @module libs::std {
+      #[derive(Erase)]
+      pub struct U64(+U64, $u64);
+      pub struct Erase(-U64);
+    }
+  ";
+
+  #[test]
+  fn erases_ports_with_the_agent_their_type_derives() {
+    let source = format!(
+      "{MODULES}
+      use libs::nat::Succ;
+      use libs::std::{{Erase, U64}};
+      #[derive(Erase)]
+      pub struct Pair(+Pair, -U64, -Nat);
+      "
+    );
+    assert_eq!(
+      derived(&source, "Pair"),
+      Ok(vec![vec![
+        "__libs_3a_3astd::Erase".to_string(),
+        "__libs_3a_3anat::Erase".to_string()
+      ]])
+    );
+  }
+
+  #[test]
+  fn prefers_the_agent_picked_for_a_port_type() {
+    let source = format!(
+      "{MODULES}
+      use libs::std::{{Erase, U64}};
+      pub struct EraseNat(-Nat);
+      #[derive(Erase)]
+      #[erase(Nat = EraseNat)]
+      pub struct Pair(+Pair, -U64, -Nat);
+      "
+    );
+    assert_eq!(
+      derived(&source, "Pair"),
+      Ok(vec![vec![
+        "__libs_3a_3astd::Erase".to_string(),
+        "EraseNat".to_string()
+      ]])
+    );
+  }
+
+  #[test]
+  fn rejects_ports_of_types_without_an_agent() {
+    let source = "
+      pub struct Erase(-Pair);
+      #[derive(Erase)]
+      pub struct Pair(+Pair, -Stream);
+    ";
+    assert_eq!(
+      derived(source, "Pair"),
+      Err(vec![
+        "no agent to erase `Stream` with: derive `Erase` for it, or pick one with \
+         `#[erase(Stream = ...)]`"
+          .to_string()
+      ])
+    );
+  }
+}
@@ -0,0 +1,217 @@
+use crate::*;
+use syn::{parse_quote, punctuated::Punctuated, token::Paren, Pat, PatIdent, Token};
+
+impl Program {
+  pub fn expand_enums(&mut self) {
+    let enums = self
+      .items
+      .iter()
+      .filter_map(Item::as_enum)
+      .cloned()
+      .collect::<Vec<_>>();
+    for item in std::mem::take(&mut self.items) {
+      match item {
+        Item::Enum(e) => {
+          let structs = self.enum_structs(&e);
+          self.items.extend(structs.into_iter().map(Item::Struct));
+        }
+        Item::Match(m) => {
+          let impls = self.match_impls(&enums, m);
+          self.items.extend(impls.into_iter().map(Item::Impl));
+        }
+        item => self.items.push(item),
+      }
+    }
+  }
+
+  fn enum_structs(&self, e: &Enum) -> Vec<Struct> {
+    let name = &e.name;
+    let args = e.generics.iter().map(|param| parse_quote!(#param));
+    let principal = StructField::Port(PortType {
+      sign: Sign::Plus,
+      name: name.clone(),
+      args: args.collect(),
+    });
+    e.variants
+      .iter()
+      .map(|variant| {
+        let mut attrs = vec![vec![]];
+        let mut entries = Punctuated::new();
+        entries.push(principal.clone());
+        let paren = match &variant.fields {
+          Some(fields) => {
+            attrs.extend(fields.attrs.iter().cloned());
+            entries.extend(fields.entries.iter().cloned());
+            fields.paren
+          }
+          None => Paren(variant.name.span()),
+        };
+        Struct {
+          attrs: e.attrs.iter().chain(&variant.attrs).cloned().collect(),
+          derives: e.derives.clone(),
+          vis: e.vis.clone(),
+          external: None,
+          name: variant.name.clone(),
+          generics: e.generics.clone(),
+          fields: Fields::Unnamed(UnnamedFields {
+            paren,
+            attrs,
+            entries,
+          }),
+        }
+      })
+      .collect()
+  }
+
+  fn match_impls(&self, enums: &[Enum], m: Match) -> Vec<Impl> {
+    let Some(first) = m.arms.iter().find_map(|arm| arm.pat.as_agent()) else {
+      emit_error!(m.mat.span, "expected a match arm for a constructor");
+      return vec![];
+    };
+    let Some(e) = enums
+      .iter()
+      .find(|e| e.variants.iter().any(|v| v.name == first.name))
+    else {
+      emit_error!(
+        first.name.span(),
+        "expected a constructor of an enum declared in this block"
+      );
+      return vec![];
+    };
+    for agent in m.arms.iter().filter_map(|arm| arm.pat.as_agent()) {
+      if agent.src.is_some() || !e.variants.iter().any(|v| v.name == agent.name) {
+        emit_error!(agent.name.span(), "expected a constructor of `{}`", e.name);
+      }
+    }
+    // Guards and payload patterns can reject an agent, so only arms without
+    // either cover their constructor.
+    let covers = |arm: &MatchArm, name: &Ident| {
+      arm.cond.is_none()
+        && arm.pat.as_agent().is_none_or(|agent| {
+          &agent.name == name
+            && agent.fields.values().all(|field| match field {
+              ImplAgentField::Payload(PayloadPat { pat, .. }) => {
+                matches!(
+                  pat,
+                  Pat::Wild(_) | Pat::Ident(PatIdent { subpat: None, .. })
+                )
+              }
+              _ => true,
+            })
+        })
+    };
+    let missing = e
+      .variants
+      .iter()
+      .filter(|v| self.cfg_attrs(&v.attrs).is_empty())
+      .filter(|v| !m.arms.iter().any(|arm| covers(arm, &v.name)))
+      .map(|v| format!("`{}`", v.name))
+      .collect::<Vec<_>>();
+    if !missing.is_empty() {
+      emit_error!(
+        m.mat.span,
+        "non-exhaustive match: missing {}",
+        missing.join(", ")
+      );
+    }
+    m.arms
+      .into_iter()
+      .map(|arm| {
+        let mut left = m.agent.clone();
+        let right = match arm.pat {
+          MatchPat::Agent(agent) => Some(agent),
+          MatchPat::Wildcard(partner) => {
+            if let Some(partner) = partner {
+              self.name_principal(&mut left, partner);
+            }
+            None
+          }
+        };
+        Impl {
+          attrs: m.attrs.iter().chain(&arm.attrs).cloned().collect(),
+          imp: Token![impl](m.mat.span),
+          generics: m.generics.clone(),
+          left,
+          right,
+          cond: arm.cond,
+          net: arm.net,
+        }
+      })
+      .collect()
+  }
+
+  fn name_principal(&self, agent: &mut ImplAgent, name: Ident) {
+    let principal = self
+      .fields_mut(&mut agent.fields)
+      .into_iter()
+      .find(|field| !matches!(field, ImplAgentField::Payload(_)));
+    match principal {
+      Some(field @ ImplAgentField::Implicit(_)) => *field = ImplAgentField::Port(name),
+      _ => emit_error!(
+        name.span(),
+        "expected the principal port of `{}` to be `_`",
+        agent.name
+      ),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::*;
+
+  fn compile_match(arms: &str) -> Result<TokenStream, Vec<Diagnostic>> {
+    compile_str(&format!(
+      "
+      pub enum Opt {{
+        Nothing,
+        Just($u64),
+      }}
+      pub struct Keep(-Opt, +Opt);
+      pub struct Drop(-Opt);
+      match Keep(_, o) {{
+        Nothing(_) => {{ Nothing(o) }}
+        {arms}
+      }}
+      "
+    ))
+  }
+
+  fn messages(arms: &str) -> Vec<String> {
+    compile_match(arms)
+      .unwrap_err()
+      .into_iter()
+      .map(|d| d.message)
+      .collect()
+  }
+
+  #[test]
+  fn guarded_arms_do_not_cover_their_constructor() {
+    assert_eq!(
+      messages("Just(_, $x) if x > 0 => { Just(o, $x) }"),
+      ["non-exhaustive match: missing `Just`"]
+    );
+    assert_eq!(
+      messages("Just(_, $0) => { Nothing(o) }"),
+      ["non-exhaustive match: missing `Just`"]
+    );
+  }
+
+  #[test]
+  fn unguarded_or_wildcard_arms_cover_guarded_constructors() {
+    compile_match(
+      "
+      Just(_, $x) if x > 0 => { Just(o, $x) }
+      Just(_, $_) => { Nothing(o) }
+      ",
+    )
+    .unwrap();
+    compile_match(
+      "
+      Just(_, $x) if x > 0 => { Just(o, $x) }
+      x => { Drop(x) Nothing(o) }
+      ",
+    )
+    .unwrap();
+  }
+}
@@ -0,0 +1,386 @@
+use crate::*;
+use syn::{parse_quote, BinOp};
+
+#[derive(Debug)]
+pub struct OperatorTable {
+  pub ops: BTreeMap<String, (OperatorAgent, Option<OperatorAgent>)>,
+  pub literal: Option<OperatorAgent>,
+}
+
+#[derive(Debug)]
+pub struct ExprLowering<'a> {
+  pub table: &'a OperatorTable,
+  pub agents: Vec<NetAgent>,
+  pub fresh: usize,
+}
+
+#[derive(Debug)]
+pub enum Operand {
+  Wire(Ident),
+  Const(Box<PayloadExpr>),
+}
+
+impl Program {
+  pub fn expand_exprs(&mut self) {
+    let table = self.operator_table();
+    let mut items = std::mem::take(&mut self.items);
+    for item in &mut items {
+      match item {
+        Item::Impl(i) => {
+          let defs = i
+            .agents()
+            .flat_map(|a| a.fields.values())
+            .flat_map(|f| match f {
+              ImplAgentField::Port(x) => vec![x.clone()],
+              ImplAgentField::Agent(a) => a.all_idents().cloned().collect(),
+              _ => vec![],
+            })
+            .collect();
+          self.expand_net_exprs(&table, &mut i.net, defs);
+        }
+        Item::Fn(f) => {
+          let defs = f.input_idents().cloned().collect();
+          self.expand_net_exprs(&table, &mut f.net, defs);
+        }
+        _ => {}
+      }
+    }
+    self.items = items;
+  }
+
+  fn operator_table(&self) -> OperatorTable {
+    let mut declared = self.items.iter().filter_map(|item| match item {
+      Item::Operators(o) => Some(o),
+      _ => None,
+    });
+    // Operator agents take their operands in order: `a op b` becomes
+    // `Op(a, b, o)`, or `OpX(b, o, $a)` when `a` is a constant. A constant `b`
+    // only avoids a literal agent for operators that commute or, like the
+    // comparisons, have a mirror image.
+    let default: Operators = parse_quote! {
+      operators {
+        + => Add, AddX;
+        - => Sub, SubX;
+        * => Mul, MulX;
+        / => Div, DivX;
+        % => Mod, ModX;
+        == => Eq, EqX;
+        != => Ne, NeX;
+        < => Lt, LtX;
+        <= => Le, LeX;
+        > => Gt, GtX;
+        >= => Ge, GeX;
+        $ => U64;
+      }
+    };
+    let operators = declared.next().unwrap_or(&default);
+    if let Some(o) = declared.next() {
+      emit_error!(o.kw.span, "duplicate operator table");
+    }
+    let mut table = OperatorTable {
+      ops: BTreeMap::new(),
+      literal: None,
+    };
+    for entry in &operators.entries {
+      match &entry.op {
+        Operator::Literal(dollar) => {
+          if table.literal.replace(entry.agent.clone()).is_some() {
+            emit_error!(dollar.span, "duplicate literal agent");
+          }
+        }
+        Operator::Binary(op) => {
+          let prev = table.ops.insert(
+            self.op_str(op),
+            (entry.agent.clone(), entry.agent_x.clone()),
+          );
+          if prev.is_some() {
+            emit_error!(op.span(), "duplicate operator `{}`", self.op_str(op));
+          }
+        }
+      }
+    }
+    table
+  }
+
+  fn op_str(&self, op: &BinOp) -> String {
+    quote!(#op).to_string()
+  }
+
+  fn expand_net_exprs(&self, table: &OperatorTable, net: &mut Net, defs: BTreeSet<Ident>) {
+    if net.exprs.is_empty() {
+      return;
+    }
+    let mut lowering = ExprLowering {
+      table,
+      agents: vec![],
+      fresh: 0,
+    };
+    self.clone_operands(&mut lowering, net, &defs);
+    for e in std::mem::take(&mut net.exprs) {
+      match e.expr {
+        InfixExpr::Wire(x) => {
+          emit_error!(x.span(), "expected an operator expression");
+        }
+        InfixExpr::Const(c) => self.lower_literal(&mut lowering, &c, e.target),
+        InfixExpr::Binary(a, op, b) => {
+          let a = self.lower_operand(&mut lowering, *a);
+          let b = self.lower_operand(&mut lowering, *b);
+          self.lower_binary(&mut lowering, &op, a, b, e.target);
+        }
+      }
+    }
+    net.agents.extend(lowering.agents);
+  }
+
+  fn clone_operands(&self, lowering: &mut ExprLowering, net: &mut Net, defs: &BTreeSet<Ident>) {
+    let mut operands = BTreeMap::<Ident, usize>::new();
+    for e in &mut net.exprs {
+      for x in e.expr.wires_mut() {
+        *operands.entry(x.clone()).or_default() += 1;
+      }
+    }
+    for (x, operand_uses) in operands {
+      let agent_uses = net.all_idents().filter(|y| **y == x).count();
+      let targets = net.exprs.iter().filter(|e| e.target == x).count();
+      let defined = defs.contains(&x) as usize;
+      let total = operand_uses + agent_uses + targets + defined;
+      if total <= 2 {
+        continue;
+      }
+      let skip_agent = if defined + targets > 0 {
+        false
+      } else if agent_uses == 1 {
+        true
+      } else {
+        continue;
+      };
+      let mut wires = vec![];
+      let mut src = x.clone();
+      for _ in 2..total {
+        let a = self.fresh_expr_wire(lowering);
+        let b = self.fresh_expr_wire(lowering);
+        lowering.agents.push(parse_quote!(Clone(#src, #a, #b)));
+        wires.push(a);
+        src = b;
+      }
+      wires.push(src);
+      let mut wires = wires.into_iter();
+      let mut skip = skip_agent;
+      for agent in &mut net.agents {
+        self.rename_wires(agent, &x, &mut skip, &mut wires);
+      }
+      for e in &mut net.exprs {
+        for y in e.expr.wires_mut() {
+          if *y == x {
+            *y = self.respan(wires.next().unwrap(), y);
+          }
+        }
+      }
+    }
+  }
+
+  fn rename_wires(
+    &self,
+    agent: &mut NetAgent,
+    x: &Ident,
+    skip: &mut bool,
+    wires: &mut impl Iterator<Item = Ident>,
+  ) {
+    for field in self.fields_mut(&mut agent.fields) {
+      match field {
+        NetAgentField::Port(y) if y == x => {
+          if *skip {
+            *skip = false;
+          } else {
+            *y = self.respan(wires.next().unwrap(), y);
+          }
+        }
+        NetAgentField::Agent(agent) => self.rename_wires(agent, x, skip, wires),
+        _ => {}
+      }
+    }
+  }
+
+  fn respan(&self, wire: Ident, original: &Ident) -> Ident {
+    Ident::new(&wire.to_string(), original.span())
+  }
+
+  fn lower_operand(&self, lowering: &mut ExprLowering, expr: InfixExpr) -> Operand {
+    match expr {
+      InfixExpr::Wire(x) => Operand::Wire(x),
+      InfixExpr::Const(c) => Operand::Const(Box::new(c)),
+      InfixExpr::Binary(a, op, b) => {
+        let a = self.lower_operand(lowering, *a);
+        let b = self.lower_operand(lowering, *b);
+        let o = self.fresh_expr_wire(lowering);
+        self.lower_binary(lowering, &op, a, b, o.clone());
+        Operand::Wire(o)
+      }
+    }
+  }
+
+  fn lower_binary(
+    &self,
+    lowering: &mut ExprLowering,
+    op: &BinOp,
+    a: Operand,
+    b: Operand,
+    o: Ident,
+  ) {
+    let table = lowering.table;
+    let Some((agent, agent_x)) = table.ops.get(&self.op_str(op)) else {
+      emit_error!(
+        op.span(),
+        "no agent declared for operator `{}`",
+        self.op_str(op)
+      );
+      return;
+    };
+    match (a, b, agent_x) {
+      (Operand::Const(c), Operand::Wire(b), Some(agent_x)) => {
+        self.push_op_x(lowering, agent_x, b, o, &c);
+      }
+      (Operand::Wire(a), Operand::Const(c), agent_x) => {
+        let flipped = match op {
+          BinOp::Add(_) | BinOp::Mul(_) | BinOp::Eq(_) | BinOp::Ne(_) => agent_x.as_ref(),
+          BinOp::BitAnd(_) | BinOp::BitOr(_) | BinOp::BitXor(_) => agent_x.as_ref(),
+          BinOp::Lt(_) => self.op_x(table, quote!(>)),
+          BinOp::Le(_) => self.op_x(table, quote!(>=)),
+          BinOp::Gt(_) => self.op_x(table, quote!(<)),
+          BinOp::Ge(_) => self.op_x(table, quote!(<=)),
+          _ => None,
+        };
+        match flipped {
+          Some(agent_x) => self.push_op_x(lowering, agent_x, a, o, &c),
+          None => {
+            let b = self.wire_of(lowering, Operand::Const(c));
+            self.push_op(lowering, agent, a, b, o);
+          }
+        }
+      }
+      (a, b, _) => {
+        let a = self.wire_of(lowering, a);
+        let b = self.wire_of(lowering, b);
+        self.push_op(lowering, agent, a, b, o);
+      }
+    }
+  }
+
+  fn op_x<'a>(&self, table: &'a OperatorTable, op: TokenStream) -> Option<&'a OperatorAgent> {
+    table.ops.get(&op.to_string())?.1.as_ref()
+  }
+
+  fn wire_of(&self, lowering: &mut ExprLowering, operand: Operand) -> Ident {
+    match operand {
+      Operand::Wire(x) => x,
+      Operand::Const(c) => {
+        let w = self.fresh_expr_wire(lowering);
+        self.lower_literal(lowering, &c, w.clone());
+        w
+      }
+    }
+  }
+
+  fn lower_literal(&self, lowering: &mut ExprLowering, c: &PayloadExpr, o: Ident) {
+    let Some(literal) = &lowering.table.literal else {
+      emit_error!(c.dollar.span, "no agent declared for literals");
+      return;
+    };
+    let literal = self.quote_operator_agent(literal);
+    let expr = &c.expr;
+    lowering.agents.push(parse_quote!(#literal(#o, $#expr)));
+  }
+
+  fn push_op(
+    &self,
+    lowering: &mut ExprLowering,
+    agent: &OperatorAgent,
+    a: Ident,
+    b: Ident,
+    o: Ident,
+  ) {
+    let agent = self.quote_operator_agent(agent);
+    lowering.agents.push(parse_quote!(#agent(#a, #b, #o)));
+  }
+
+  fn push_op_x(
+    &self,
+    lowering: &mut ExprLowering,
+    agent: &OperatorAgent,
+    a: Ident,
+    o: Ident,
+    c: &PayloadExpr,
+  ) {
+    let agent = self.quote_operator_agent(agent);
+    let expr = &c.expr;
+    lowering.agents.push(parse_quote!(#agent(#a, #o, $#expr)));
+  }
+
+  fn quote_operator_agent(&self, agent: &OperatorAgent) -> TokenStream {
+    let src = self.quote_src(&agent.src);
+    let name = &agent.name;
+    quote!(#src #name)
+  }
+
+  fn fresh_expr_wire(&self, lowering: &mut ExprLowering) -> Ident {
+    lowering.fresh += 1;
+    format_ident!("__e{}", lowering.fresh - 1)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::*;
+
+  fn render(agent: &NetAgent) -> String {
+    let fields = agent
+      .fields
+      .values()
+      .map(|field| match field {
+        NetAgentField::Implicit(_) => "_".to_string(),
+        NetAgentField::Port(x) => x.to_string(),
+        NetAgentField::Payload(_) => "$".to_string(),
+        NetAgentField::Agent(x) => render(x),
+      })
+      .collect::<Vec<_>>();
+    format!("{}({})", agent.name, fields.join(", "))
+  }
+
+  fn lower(net: &str) -> Vec<String> {
+    let mut program = syn::parse_str::<Program>(&format!(
+      "
+      pub struct U64(+U64, $u64);
+      pub enum Bool {{ False, True }}
+      for (Op, OpX) in [(Sub, SubX), (Div, DivX), (Mod, ModX)] {{
+        pub struct Op(-U64, -U64, +U64);
+        pub struct OpX(-U64, +U64, $u64);
+      }}
+      for (Op, OpX) in [(Lt, LtX), (Gt, GtX)] {{
+        pub struct Op(-U64, -U64, +Bool);
+        pub struct OpX(-U64, +Bool, $u64);
+      }}
+      pub struct F(-F, -U64, -U64, +U64);
+      impl F(_, b, c, o) for F(_, _, _, _) {{ {net} }}
+      "
+    ))
+    .unwrap();
+    collect_diagnostics(|| program.expand()).unwrap();
+    let i = program.items.iter().find_map(Item::as_impl).unwrap();
+    i.net.agents.iter().map(render).collect()
+  }
+
+  #[test]
+  fn passes_operands_in_order() {
+    assert_eq!(lower("o = b % c"), ["Mod(b, c, o)"]);
+    assert_eq!(lower("o = b / c"), ["Div(b, c, o)"]);
+    assert_eq!(lower("o = b - c"), ["Sub(b, c, o)"]);
+    assert_eq!(lower("o = $3 % c"), ["ModX(c, o, $)"]);
+    assert_eq!(lower("o = b % $3"), ["U64(__e0, $)", "Mod(b, __e0, o)"]);
+  }
+
+  #[test]
+  fn flips_comparisons_with_constants_on_the_right() {
+    assert_eq!(lower("o = b < $3"), ["GtX(b, o, $)"]);
+    assert_eq!(lower("o = $3 < b"), ["LtX(b, o, $)"]);
+  }
+}
@@ -0,0 +1,114 @@
+use crate::*;
+
+impl Program {
+  pub fn expand_externs(&mut self) {
+    let mut impls = vec![];
+    for s in self.items.iter().filter_map(Item::as_struct) {
+      if s.external.is_some() {
+        impls.extend(self.extern_impl(s));
+      }
+    }
+    self.items.extend(impls.into_iter().map(Item::Impl));
+  }
+
+  fn extern_impl(&self, s: &Struct) -> Option<Impl> {
+    let name = &s.name;
+    let fields = s.fields.values().collect::<Vec<_>>();
+    let (input, output) = match &fields[..] {
+      [StructField::Port(i), StructField::Port(o)]
+        if i.sign == Sign::Minus && o.sign == Sign::Plus && s.generics.is_empty() =>
+      {
+        (i, o)
+      }
+      _ => {
+        emit_error!(
+          name.span(),
+          "external agents must have the form `{}(-T, +R)`",
+          name
+        );
+        return None;
+      }
+    };
+    if !self.is_host_value(input) | !self.is_host_value(output) {
+      return None;
+    }
+    let (input, output) = (&input.name, &output.name);
+    let crate_path = self.crate_path();
+    let attrs = self.cfg_attrs(&s.attrs);
+    let tokens = quote_spanned!(name.span()=>
+      #(#attrs)*
+      impl #name(_, o) for #input(_, $x) {
+        let y = #crate_path::Host::host_fns(effects).call::<#name<_>>(x);
+        #output(o, $y)
+      }
+    );
+    match syn::parse2(tokens) {
+      Ok(i) => Some(i),
+      Err(err) => abort!(err.span(), "{}", err),
+    }
+  }
+
+  // Values cross to the host as the payload of an agent with one port and one
+  // payload. Imported agents are checked by rustc through `Payload`.
+  fn is_host_value(&self, port: &PortType) -> bool {
+    let local = self
+      .items
+      .iter()
+      .filter_map(Item::as_struct)
+      .find(|s| s.name == port.name);
+    let valid = port.args.is_empty()
+      && local.is_none_or(|s| {
+        s.generics.is_empty()
+          && s.fields.len() == 2
+          && s.fields.values().filter_map(StructField::payload).count() == 1
+      });
+    if !valid {
+      emit_error!(
+        port.name.span(),
+        "`{}` cannot cross to the host: expected an agent with one port and one payload",
+        port.name
+      );
+    }
+    valid
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::*;
+
+  fn host_errors(source: &str) -> Vec<String> {
+    compile_str(source)
+      .err()
+      .unwrap_or_default()
+      .into_iter()
+      .map(|d| d.message)
+      .filter(|m| m.contains("cannot cross to the host"))
+      .collect()
+  }
+
+  #[test]
+  fn accepts_single_payload_agents() {
+    let errors = host_errors(
+      "
+      pub struct U64(+U64, $u64);
+      extern struct Square(-U64, +U64);
+      ",
+    );
+    assert!(errors.is_empty(), "{errors:?}");
+  }
+
+  #[test]
+  fn rejects_agents_without_exactly_one_payload() {
+    let errors = host_errors(
+      "
+      pub struct Pair(+Pair, $u64, $u64);
+      pub struct Zero(+Zero);
+      extern struct F(-Pair, +Zero);
+      ",
+    );
+    assert_eq!(errors.len(), 2, "{errors:?}");
+    assert!(errors[0].starts_with("`Pair`"));
+    assert!(errors[1].starts_with("`Zero`"));
+  }
+}
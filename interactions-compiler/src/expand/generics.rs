@@ -0,0 +1,578 @@
+use crate::*;
+use proc_macro2::Span;
+use quote::ToTokens;
+use std::collections::VecDeque;
+use syn::{parse::Parser, Expr, GenericArgument, Pat, PathArguments, Type, TypePath};
+
+type Subst = BTreeMap<Ident, Type>;
+
+// A generic agent: the module that declares it, as an index into
+// `Program::modules` or `None` for this one, and its name there.
+type Generic = (Option<usize>, Ident);
+
+#[derive(Debug)]
+pub struct Monomorphization {
+  pub structs: BTreeMap<Generic, Struct>,
+  pub impls: Vec<(Option<usize>, Impl)>,
+  pub queue: VecDeque<(Generic, Vec<Type>)>,
+  pub seen_structs: BTreeSet<Ident>,
+  pub seen_impls: BTreeSet<(usize, Vec<String>)>,
+  pub items: Vec<Item>,
+  pub uses: Vec<Use>,
+}
+
+impl Program {
+  // Generic agents are instantiated in the module that uses them, including
+  // those declared in imported modules: their items are copied here from the
+  // module's source, with their references resolved as they were written.
+  pub fn expand_generics(&mut self) {
+    let mut mono = Monomorphization {
+      structs: BTreeMap::new(),
+      impls: vec![],
+      queue: VecDeque::new(),
+      seen_structs: BTreeSet::new(),
+      seen_impls: BTreeSet::new(),
+      items: vec![],
+      uses: vec![],
+    };
+    for (i, module) in self.modules.iter().enumerate() {
+      for item in &module.program.items {
+        match item {
+          Item::Struct(s) if !s.generics.is_empty() => {
+            mono.structs.insert((Some(i), s.name.clone()), s.clone());
+          }
+          Item::Impl(imp) if !imp.generics.is_empty() => mono.impls.push((Some(i), imp.clone())),
+          _ => {}
+        }
+      }
+    }
+    let mut items = vec![];
+    for item in std::mem::take(&mut self.items) {
+      match item {
+        Item::Struct(s) if !s.generics.is_empty() => {
+          mono.structs.insert((None, s.name.clone()), s);
+        }
+        Item::Impl(i) if !i.generics.is_empty() => {
+          self.check_impl_generics(&i);
+          mono.impls.push((None, i));
+        }
+        item => items.push(item),
+      }
+    }
+    self.items = items;
+    for idx in 0..self.items.len() {
+      let mut item = self.items[idx].clone();
+      match &mut item {
+        Item::Impl(i) => self.mono_impl(&mut mono, None, i),
+        Item::Fn(f) => self.mono_net(&mut mono, None, &mut f.net),
+        _ => continue,
+      }
+      self.items[idx] = item;
+    }
+    while let Some((generic, args)) = mono.queue.pop_front() {
+      self.instantiate_struct(&mut mono, &generic, &args);
+      for impl_i in 0..mono.impls.len() {
+        let (scope, i) = &mono.impls[impl_i];
+        let subst = i
+          .agents()
+          .filter(|agent| {
+            self.resolve_generic(&mono, *scope, &agent.src, &agent.name) == Some(generic.clone())
+          })
+          .find_map(|agent| self.bind_generics(&i.generics, &agent.args, &args));
+        if let Some(subst) = subst {
+          self.instantiate_impl(&mut mono, impl_i, subst);
+        }
+      }
+    }
+    self.items.extend(mono.items);
+    self.items.extend(mono.uses.into_iter().map(Item::Use));
+  }
+
+  fn resolve_generic(
+    &self,
+    mono: &Monomorphization,
+    scope: Option<usize>,
+    src: &Option<Ident>,
+    name: &Ident,
+  ) -> Option<Generic> {
+    let local = (None, name.clone());
+    if scope.is_none() && src.is_none() && mono.structs.contains_key(&local) {
+      return Some(local);
+    }
+    let agent = self.resolve_agent(scope, src, name)?;
+    let module = match &agent.module {
+      Some(path) => Some(self.module_index(path)?),
+      None => None,
+    };
+    Some((module, agent.name))
+  }
+
+  fn check_impl_generics(&self, i: &Impl) {
+    for param in &i.generics {
+      let bound = i
+        .agents()
+        .any(|agent| agent.args.iter().any(|arg| self.is_param(arg, param)));
+      if !bound {
+        emit_error!(
+          param.span(),
+          "type parameter must be an argument of one of the rule's agents"
+        );
+      }
+    }
+  }
+
+  fn is_param(&self, ty: &Type, param: &Ident) -> bool {
+    matches!(ty, Type::Path(TypePath { qself: None, path }) if path.is_ident(param))
+  }
+
+  fn bind_generics(&self, params: &[Ident], pattern: &[Type], args: &[Type]) -> Option<Subst> {
+    if pattern.len() != args.len() {
+      return None;
+    }
+    let mut subst = Subst::new();
+    for (pat, arg) in pattern.iter().zip(args) {
+      if let Some(param) = params.iter().find(|param| self.is_param(pat, param)) {
+        if let Some(prev) = subst.get(param) {
+          if !self.same_type(prev, arg) {
+            return None;
+          }
+        } else {
+          subst.insert(param.clone(), arg.clone());
+        }
+      }
+    }
+    if subst.len() != params.len() {
+      return None;
+    }
+    let all_match = pattern
+      .iter()
+      .zip(args)
+      .all(|(pat, arg)| self.same_type(&self.subst_type(pat, &subst), arg));
+    all_match.then_some(subst)
+  }
+
+  fn same_type(&self, a: &Type, b: &Type) -> bool {
+    a.to_token_stream().to_string() == b.to_token_stream().to_string()
+  }
+
+  fn instantiate_struct(&self, mono: &mut Monomorphization, generic: &Generic, args: &[Type]) {
+    let mut s = mono.structs[generic].clone();
+    let subst = s
+      .generics
+      .drain(..)
+      .zip(args.iter().cloned())
+      .collect::<Subst>();
+    s.name = self.instance_name(generic, args);
+    if let (Some(scope), _) = generic {
+      self.mono_derives(mono, *scope, &mut s.derives);
+    }
+    for field in self.fields_mut(&mut s.fields) {
+      match field {
+        StructField::Port(port) => {
+          if let Some(ty) = subst.get(&port.name) {
+            (port.name, port.args) = self.port_type_of(ty);
+          } else {
+            for arg in &mut port.args {
+              *arg = self.subst_type(arg, &subst);
+            }
+          }
+        }
+        StructField::Payload(payload) => payload.ty = self.subst_type(&payload.ty, &subst),
+      }
+    }
+    mono.items.push(Item::Struct(s));
+  }
+
+  // The agents that erase and clone an imported agent are named as they are
+  // in its module.
+  fn mono_derives(&self, mono: &mut Monomorphization, scope: usize, derives: &mut Derives) {
+    for agent in derives.erasers.iter_mut().chain(&mut derives.cloners) {
+      self.mono_agent_ref(
+        mono,
+        Some(scope),
+        &mut agent.src,
+        &mut agent.name,
+        &mut vec![],
+      );
+    }
+    for (derive, src) in [
+      (&mut derives.erase, &mut derives.erase_src),
+      (&mut derives.clone, &mut derives.clone_src),
+    ] {
+      if let Some(derive) = derive {
+        self.mono_agent_ref(mono, Some(scope), src, derive, &mut vec![]);
+      }
+    }
+  }
+
+  fn instantiate_impl(&self, mono: &mut Monomorphization, impl_i: usize, subst: Subst) {
+    let key = subst
+      .values()
+      .map(|ty| ty.to_token_stream().to_string())
+      .collect::<Vec<_>>();
+    if !mono.seen_impls.insert((impl_i, key)) {
+      return;
+    }
+    let (scope, mut i) = mono.impls[impl_i].clone();
+    i.generics.clear();
+    for agent in i.agents_mut() {
+      self.subst_impl_agent(agent, &subst);
+    }
+    i.cond = i.cond.map(|cond| self.subst_expr(&cond, &subst));
+    for agent in &mut i.net.agents {
+      self.subst_net_agent(agent, &subst);
+    }
+    self.mono_impl(mono, scope, &mut i);
+    mono.items.push(Item::Impl(i));
+  }
+
+  fn subst_impl_agent(&self, agent: &mut ImplAgent, subst: &Subst) {
+    for arg in &mut agent.args {
+      *arg = self.subst_type(arg, subst);
+    }
+    for field in self.fields_mut(&mut agent.fields) {
+      match field {
+        ImplAgentField::Payload(payload) => payload.pat = self.subst_pat(&payload.pat, subst),
+        ImplAgentField::Agent(agent) => self.subst_net_agent(agent, subst),
+        _ => {}
+      }
+    }
+  }
+
+  fn subst_net_agent(&self, agent: &mut NetAgent, subst: &Subst) {
+    for arg in &mut agent.args {
+      *arg = self.subst_type(arg, subst);
+    }
+    for field in self.fields_mut(&mut agent.fields) {
+      match field {
+        NetAgentField::Payload(payload) => payload.expr = self.subst_expr(&payload.expr, subst),
+        NetAgentField::Agent(agent) => self.subst_net_agent(agent, subst),
+        _ => {}
+      }
+    }
+  }
+
+  fn mono_impl(&self, mono: &mut Monomorphization, scope: Option<usize>, i: &mut Impl) {
+    for agent in i.agents_mut() {
+      self.mono_agent_ref(
+        mono,
+        scope,
+        &mut agent.src,
+        &mut agent.name,
+        &mut agent.args,
+      );
+      for field in self.fields_mut(&mut agent.fields) {
+        if let ImplAgentField::Agent(agent) = field {
+          self.mono_net_agent(mono, scope, agent);
+        }
+      }
+    }
+    self.mono_net(mono, scope, &mut i.net);
+  }
+
+  fn mono_net(&self, mono: &mut Monomorphization, scope: Option<usize>, net: &mut Net) {
+    for agent in &mut net.agents {
+      self.mono_net_agent(mono, scope, agent);
+    }
+  }
+
+  fn mono_net_agent(
+    &self,
+    mono: &mut Monomorphization,
+    scope: Option<usize>,
+    agent: &mut NetAgent,
+  ) {
+    self.mono_agent_ref(
+      mono,
+      scope,
+      &mut agent.src,
+      &mut agent.name,
+      &mut agent.args,
+    );
+    for field in self.fields_mut(&mut agent.fields) {
+      if let NetAgentField::Agent(agent) = field {
+        self.mono_net_agent(mono, scope, agent);
+      }
+    }
+  }
+
+  // Points a reference written in `scope` at the agent it names here: an
+  // instance of a generic agent, or an agent of another module when the
+  // reference was copied from an imported one.
+  fn mono_agent_ref(
+    &self,
+    mono: &mut Monomorphization,
+    scope: Option<usize>,
+    src: &mut Option<Ident>,
+    name: &mut Ident,
+    args: &mut Vec<Type>,
+  ) {
+    if args.is_empty() {
+      if scope.is_some() {
+        let Some(agent) = self.resolve_agent(scope, src, name) else {
+          emit_error!(
+            name.span(),
+            "cannot resolve `{}` outside of its module",
+            name
+          );
+          return;
+        };
+        *src = agent
+          .module
+          .map(|path| self.module_alias(&path, &mut mono.uses));
+        *name = agent.name;
+      }
+      return;
+    }
+    let generic = self.resolve_generic(mono, scope, src, name);
+    let Some(s) = generic
+      .as_ref()
+      .and_then(|generic| mono.structs.get(generic))
+    else {
+      emit_error!(name.span(), "unexpected type arguments");
+      return;
+    };
+    if args.len() != s.generics.len() {
+      emit_error!(
+        name.span(),
+        "expected {} type arguments, found {}",
+        s.generics.len(),
+        args.len()
+      );
+      return;
+    }
+    let generic = generic.unwrap();
+    let instance = self.instance_name(&generic, args);
+    if mono.seen_structs.insert(instance.clone()) {
+      mono.queue.push_back((generic, std::mem::take(args)));
+    }
+    args.clear();
+    *src = None;
+    *name = instance;
+  }
+
+  pub fn fields_mut<'a, T>(&self, fields: &'a mut Fields<T>) -> Vec<&'a mut T> {
+    match fields {
+      Fields::Unnamed(f) => f.entries.iter_mut().collect(),
+      Fields::Named(f) => f.entries.iter_mut().map(|x| &mut x.val).collect(),
+    }
+  }
+
+  // Instances of imported agents are named after the module's path as well,
+  // `libs::std::List<U64>` becoming `libs_3a_3astd_3a_3aList__3U64`.
+  fn instance_name(&self, (module, name): &Generic, args: &[Type]) -> Ident {
+    match module {
+      Some(i) => {
+        let path = self.path_str(&self.modules[*i].path);
+        self.mangle(&format!("{}::{}", path, name), name.span(), args)
+      }
+      None => self.mangle(&name.to_string(), name.span(), args),
+    }
+  }
+
+  // `Foo<U64, X>` becomes `Foo__3U641X`: the name, then each argument's
+  // length and text, with every character other than an ASCII letter or digit
+  // escaped so that distinct instantiations never share a name.
+  fn mangle(&self, name: &str, span: Span, args: &[Type]) -> Ident {
+    let mut mangled = self.escape(name);
+    mangled.push_str("__");
+    for arg in args {
+      let arg = self.escape(&self.type_str(arg));
+      mangled.push_str(&arg.len().to_string());
+      mangled.push_str(&arg);
+    }
+    Ident::new(&mangled, span)
+  }
+
+  pub fn escape(&self, s: &str) -> String {
+    let mut escaped = String::new();
+    for c in s.chars() {
+      match c {
+        'a'..='z' | 'A'..='Z' | '0'..='9' => escaped.push(c),
+        c if c.is_ascii() => escaped.push_str(&format!("_{:02x}", c as u32)),
+        c => escaped.push_str(&format!("_u{:06x}", c as u32)),
+      }
+    }
+    escaped
+  }
+
+  // Token text without the spaces that only separate punctuation.
+  fn type_str(&self, ty: &Type) -> String {
+    let s = ty.to_token_stream().to_string();
+    let chars = s.chars().collect::<Vec<_>>();
+    let is_word = |c: Option<&char>| c.is_some_and(|c| c.is_alphanumeric() || *c == '_');
+    (0..chars.len())
+      .filter(|&i| {
+        chars[i] != ' ' || (is_word(chars.get(i.wrapping_sub(1))) && is_word(chars.get(i + 1)))
+      })
+      .map(|i| chars[i])
+      .collect()
+  }
+
+  pub fn port_type_of(&self, ty: &Type) -> (Ident, Vec<Type>) {
+    if let Type::Path(TypePath { qself: None, path }) = ty {
+      if let [segment] = path.segments.iter().collect::<Vec<_>>()[..] {
+        let args = match &segment.arguments {
+          PathArguments::None => vec![],
+          PathArguments::AngleBracketed(args) => args
+            .args
+            .iter()
+            .filter_map(|arg| match arg {
+              GenericArgument::Type(ty) => Some(ty.clone()),
+              _ => None,
+            })
+            .collect(),
+          PathArguments::Parenthesized(_) => abort!(ty.span(), "expected a port type"),
+        };
+        return (segment.ident.clone(), args);
+      }
+    }
+    abort!(ty.span(), "expected a port type")
+  }
+
+  fn subst_type(&self, ty: &Type, subst: &Subst) -> Type {
+    self.reparse(syn::parse2, ty, subst)
+  }
+
+  fn subst_expr(&self, expr: &Expr, subst: &Subst) -> Expr {
+    self.reparse(syn::parse2, expr, subst)
+  }
+
+  fn subst_pat(&self, pat: &Pat, subst: &Subst) -> Pat {
+    self.reparse(|tokens| Pat::parse_multi.parse2(tokens), pat, subst)
+  }
+
+  fn reparse<T: ToTokens>(
+    &self,
+    parse: impl FnOnce(TokenStream) -> syn::Result<T>,
+    value: &T,
+    subst: &Subst,
+  ) -> T {
+    match parse(self.subst_tokens(value.to_token_stream(), subst)) {
+      Ok(value) => value,
+      Err(err) => abort!(err.span(), "{}", err),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use syn::parse_quote;
+
+  fn mangle(name: &str, args: &[Type]) -> String {
+    let program = Program {
+      items: vec![],
+      modules: vec![],
+      source: TokenStream::new(),
+      call_site: None,
+    };
+    program.mangle(name, Span::call_site(), args).to_string()
+  }
+
+  fn expand(source: &str) -> Program {
+    let mut program = syn::parse_str::<Program>(source).unwrap();
+    collect_diagnostics(|| program.expand()).unwrap();
+    program
+  }
+
+  fn render(agent: &NetAgent) -> String {
+    let fields = agent
+      .fields
+      .values()
+      .map(|field| match field {
+        NetAgentField::Implicit(_) => "_".to_string(),
+        NetAgentField::Port(x) => x.to_string(),
+        NetAgentField::Payload(_) => "$".to_string(),
+        NetAgentField::Agent(x) => render(x),
+      })
+      .collect::<Vec<_>>();
+    format!("{}({})", agent.name, fields.join(", "))
+  }
+
+  const LIST: &str = "
+    @module libs::list {
+      pub enum List<T> {
+        Nil,
+        Cons(-T, -List<T>),
+      }
+    }
+  ";
+
+  #[test]
+  fn mangling_is_injective() {
+    let names = [
+      mangle("Foo", &[parse_quote!(U64_X)]),
+      mangle("Foo", &[parse_quote!(U64), parse_quote!(X)]),
+      mangle("Foo", &[parse_quote!(List<U64>)]),
+      mangle("Foo", &[parse_quote!(List), parse_quote!(U64)]),
+      mangle("Foo_", &[parse_quote!(X)]),
+      mangle("Foo", &[parse_quote!(_X)]),
+      mangle("a::Foo", &[parse_quote!(X)]),
+      "Foo_U64".to_string(),
+    ];
+    let unique = names.iter().collect::<BTreeSet<_>>();
+    assert_eq!(unique.len(), names.len(), "{names:?}");
+    assert_eq!(names[1], "Foo__3U641X");
+  }
+
+  #[test]
+  fn mangling_ignores_token_spacing() {
+    assert_eq!(
+      mangle("Foo", &[parse_quote!(List<List<U64>>)]),
+      mangle("Foo", &[syn::parse_str("List < List < U64 > >").unwrap()]),
+    );
+  }
+
+  #[test]
+  fn instantiates_generic_agents_from_other_modules() {
+    let program = expand(&format!(
+      "{LIST}
+      use libs::list::{{Cons, Nil}};
+      pub struct Keep(-List<U64>);
+      fn _main() {{
+        Nil<U64>(l)
+        Keep(l)
+      }}
+      "
+    ));
+    let structs = program
+      .items
+      .iter()
+      .filter_map(Item::as_struct)
+      .map(|s| s.name.to_string())
+      .collect::<Vec<_>>();
+    assert!(
+      structs.contains(&"libs_3a_3alist_3a_3aNil__3U64".to_string()),
+      "{structs:?}"
+    );
+  }
+
+  #[test]
+  fn lowers_defs_over_constructors_from_other_modules() {
+    let program = expand(&format!(
+      "{LIST}
+      use libs::list::*;
+      def Push(List<U64>, U64) -> List<U64> {{
+        Push(Nil<U64>, v) = Cons<U64>(v, Nil<U64>);
+        Push(Cons<U64>(x, xs), v) = Cons<U64>(x, Push(xs, v));
+      }}
+      "
+    ));
+    let nil = format_ident!("libs_3a_3alist_3a_3aNil__3U64");
+    let push = program
+      .items
+      .iter()
+      .filter_map(Item::as_impl)
+      .find(|i| i.left.name == "Push" && i.right.as_ref().is_some_and(|r| r.name == nil))
+      .unwrap();
+    let agents = push.net.agents.iter().map(render).collect::<Vec<_>>();
+    assert_eq!(
+      agents,
+      [
+        "libs_3a_3alist_3a_3aNil__3U64(__d1)",
+        "libs_3a_3alist_3a_3aCons__3U64(__d0, v, __d1)",
+      ]
+    );
+  }
+}
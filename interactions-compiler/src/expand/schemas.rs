@@ -0,0 +1,81 @@
+use crate::*;
+
+impl Program {
+  pub fn expand_schemas(&mut self) {
+    let items = std::mem::take(&mut self.items);
+    for item in items {
+      match item {
+        Item::Schema(schema) => self.expand_schema(schema),
+        item => self.items.push(item),
+      }
+    }
+  }
+
+  fn expand_schema(&mut self, schema: Schema) {
+    for entry in schema.entries {
+      let subst = schema
+        .vars
+        .iter()
+        .cloned()
+        .zip(entry)
+        .collect::<BTreeMap<_, _>>();
+      let body = self.subst_tokens(schema.body.clone(), &subst);
+      let mut program = match syn::parse2::<Program>(body) {
+        Ok(program) => program,
+        Err(err) => abort!(err.span(), "{}", err),
+      };
+      program.expand_schemas();
+      for mut item in program.items {
+        item.attrs_mut().splice(0..0, schema.attrs.iter().cloned());
+        self.items.push(item);
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::*;
+
+  fn structs(source: &str) -> Vec<String> {
+    let mut program = syn::parse_str::<Program>(source).unwrap();
+    collect_diagnostics(|| program.expand_schemas()).unwrap();
+    program
+      .items
+      .iter()
+      .filter_map(Item::as_struct)
+      .map(|s| format!("{} {}", s.name, s.fields.len()))
+      .collect()
+  }
+
+  #[test]
+  fn expands_nested_schemas_in_order() {
+    let source = "
+      for (A, B) in [(Add, AddX), (Sub, SubX)] {
+        pub struct A(-A, -A, +A);
+        for C in [B] {
+          pub struct C(-C, +C);
+        }
+      }
+    ";
+    assert_eq!(structs(source), ["Add 3", "AddX 2", "Sub 3", "SubX 2"]);
+  }
+
+  #[test]
+  fn keeps_generic_arguments_in_one_entry() {
+    let source = "
+      for (A, T) in [(PairA, Pair<U64, U64>), (ListA, List<Pair<U64, U64>>)] {
+        pub struct A(+A, -T);
+      }
+    ";
+    assert_eq!(structs(source), ["PairA 2", "ListA 2"]);
+  }
+
+  #[test]
+  fn rejects_entries_of_the_wrong_arity() {
+    let err = syn::parse_str::<Program>("for (A, B) in [(X, Y, Z)] { pub struct A(+A); }")
+      .err()
+      .unwrap();
+    assert_eq!(err.to_string(), "expected 2 values, found 3");
+  }
+}
@@ -0,0 +1,792 @@
+use crate::*;
+use std::env;
+use syn::{parse_quote, BinOp, Expr, Lit, Pat, RangeLimits, UnOp};
+
+const FUSION_DEPTH: usize = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Const {
+  Int(i128),
+  Bool(bool),
+}
+
+type Env = BTreeMap<Ident, Const>;
+
+enum Elem {
+  Stmt(NetStmtKind),
+  Agent(NetAgent),
+}
+
+struct FusionRule<'a> {
+  consumed: Vec<usize>,
+  arms: Vec<(&'a Impl, Vec<&'a ImplAgent>)>,
+}
+
+struct FusionPlan {
+  consumed: Vec<usize>,
+  lets: Vec<(Vec<NetAgent>, Option<NetStmtKind>)>,
+  fusion: Fusion,
+  scopes: Vec<(BTreeSet<Ident>, Env)>,
+  total: usize,
+}
+
+impl Program {
+  pub fn fuse(&mut self) {
+    let report = env::var_os("INTERACTIONS_DEBUG_FUSION").is_some();
+    let mut fresh = 0;
+    let nets = self
+      .items
+      .iter()
+      .enumerate()
+      .filter_map(|(idx, item)| {
+        let (ctx, net, bound) = match item {
+          Item::Impl(i) => {
+            let right = i.right.as_ref().map(|x| x.name.to_string());
+            let ctx = format!(
+              "impl {} for {}",
+              i.left.name,
+              right.as_deref().unwrap_or("_")
+            );
+            let bound = i
+              .agents()
+              .flat_map(|a| a.fields.values())
+              .flat_map(|f| match f {
+                ImplAgentField::Port(x) => vec![x.clone()],
+                ImplAgentField::Agent(a) => a.all_idents().cloned().collect(),
+                _ => vec![],
+              })
+              .collect();
+            (ctx, &i.net, bound)
+          }
+          Item::Fn(f) => (format!("fn {}", f.name), &f.net, BTreeSet::new()),
+          _ => return None,
+        };
+        let mut net = net.clone();
+        let ctx = report.then_some(&ctx[..]);
+        self.fuse_net(&mut net, &bound, &Env::new(), 0, ctx, &mut fresh);
+        Some((idx, net))
+      })
+      .collect::<Vec<_>>();
+    for (idx, net) in nets {
+      match &mut self.items[idx] {
+        Item::Impl(i) => i.net = net,
+        Item::Fn(f) => f.net = net,
+        _ => unreachable!(),
+      }
+    }
+  }
+
+  fn fuse_net(
+    &self,
+    net: &mut Net,
+    bound: &BTreeSet<Ident>,
+    env: &Env,
+    depth: usize,
+    ctx: Option<&str>,
+    fresh: &mut usize,
+  ) {
+    if depth >= FUSION_DEPTH || net.stmts.iter().any(|x| x.awaited().is_some()) {
+      return;
+    }
+    let env = if net.stmts.is_empty() {
+      env.clone()
+    } else {
+      Env::new()
+    };
+    let mut elems = net_elems(net);
+    self.flatten_principals(&mut elems, fresh);
+    let mut fused = vec![];
+    while let Some(mut plan) = self.next_fusion(&elems, bound, &env, fresh) {
+      if let Some(ctx) = ctx {
+        let names = plan
+          .consumed
+          .iter()
+          .filter_map(|&e| match &elems[e] {
+            Elem::Agent(a) => Some(format!("`{}`", a.name)),
+            Elem::Stmt(_) => None,
+          })
+          .collect::<Vec<_>>();
+        eprintln!(
+          "fusion: in `{}`: inlined {} (depth {}, {} of {} arms)",
+          ctx,
+          names.join(" with "),
+          depth,
+          plan.fusion.arms.len(),
+          plan.total,
+        );
+      }
+      for (arm, (bound, env)) in plan.fusion.arms.iter_mut().zip(&plan.scopes) {
+        self.fuse_net(&mut arm.net, bound, env, depth + 1, ctx, fresh);
+      }
+      for (&e, (outer, stmt)) in plan.consumed.iter().zip(plan.lets).rev() {
+        let replacement = outer
+          .into_iter()
+          .map(Elem::Agent)
+          .chain(stmt.map(Elem::Stmt));
+        elems.splice(e..e + 1, replacement);
+      }
+      fused.push(plan.fusion);
+    }
+    set_net_elems(net, elems);
+    net.fused.extend(fused);
+  }
+
+  fn flatten_principals(&self, elems: &mut Vec<Elem>, fresh: &mut usize) {
+    let mut e = 0;
+    while e < elems.len() {
+      let Elem::Agent(agent) = &mut elems[e] else {
+        e += 1;
+        continue;
+      };
+      let Some(p) = principal(&agent.fields, is_net_payload) else {
+        e += 1;
+        continue;
+      };
+      let Some(NetAgentField::Agent(nested)) =
+        self.fields_mut(&mut agent.fields).into_iter().nth(p)
+      else {
+        e += 1;
+        continue;
+      };
+      let nested_principal = principal(&nested.fields, is_net_payload);
+      let implicit = nested
+        .fields
+        .values()
+        .position(|x| matches!(x, NetAgentField::Implicit(_)));
+      if nested_principal.is_none() || nested_principal != implicit {
+        e += 1;
+        continue;
+      }
+      let mut nested = nested.clone();
+      if self.fusion_rule(agent, &nested).is_none() {
+        e += 1;
+        continue;
+      }
+      let wire = fresh_ident(&nested.name, fresh);
+      *self.fields_mut(&mut agent.fields)[p] = NetAgentField::Port(wire.clone());
+      *self.fields_mut(&mut nested.fields)[implicit.unwrap()] = NetAgentField::Port(wire);
+      elems.insert(e, Elem::Agent(nested));
+      e += 2;
+    }
+  }
+
+  fn next_fusion(
+    &self,
+    elems: &[Elem],
+    bound: &BTreeSet<Ident>,
+    env: &Env,
+    fresh: &mut usize,
+  ) -> Option<FusionPlan> {
+    let principals = elems
+      .iter()
+      .enumerate()
+      .filter_map(|(e, x)| match x {
+        Elem::Agent(a) => match a.fields.values().nth(principal(&a.fields, is_net_payload)?) {
+          Some(NetAgentField::Port(x)) => Some((e, x)),
+          _ => None,
+        },
+        Elem::Stmt(_) => None,
+      })
+      .collect::<Vec<_>>();
+    for (i, &(e1, x1)) in principals.iter().enumerate() {
+      for &(e2, x2) in &principals[i + 1..] {
+        if x1 != x2 {
+          continue;
+        }
+        if let Some(plan) = self.plan_fusion(elems, [e1, e2], x1, bound, env, fresh) {
+          return Some(plan);
+        }
+      }
+    }
+    None
+  }
+
+  fn plan_fusion(
+    &self,
+    elems: &[Elem],
+    pair: [usize; 2],
+    wire: &Ident,
+    bound: &BTreeSet<Ident>,
+    env: &Env,
+    fresh: &mut usize,
+  ) -> Option<FusionPlan> {
+    let agent = |e: usize| match &elems[e] {
+      Elem::Agent(a) => a,
+      Elem::Stmt(_) => unreachable!(),
+    };
+    let rule = self.fusion_rule(agent(pair[0]), agent(pair[1]))?;
+    if rule.arms.iter().any(|(i, _)| !self.is_fusable(i)) {
+      return None;
+    }
+    let consumed = rule.consumed.iter().map(|&c| pair[c]).collect::<Vec<_>>();
+    let mut usages = vec![];
+    let mut outers = vec![];
+    for &e in &consumed {
+      let mut usage = agent(e).clone();
+      let mut outer = vec![];
+      for field in self.fields_mut(&mut usage.fields) {
+        if let NetAgentField::Agent(nested) = field {
+          let implicit = nested
+            .fields
+            .values()
+            .position(|x| matches!(x, NetAgentField::Implicit(_)))?;
+          let ident = fresh_ident(&nested.name, fresh);
+          let mut nested = nested.clone();
+          *self.fields_mut(&mut nested.fields)[implicit] = NetAgentField::Port(ident.clone());
+          *field = NetAgentField::Port(ident);
+          outer.push(nested);
+        }
+      }
+      usages.push(usage);
+      outers.push(outer);
+    }
+
+    let mut counts = BTreeMap::new();
+    for usage in &usages {
+      let p = principal(&usage.fields, is_net_payload)?;
+      for (k, field) in usage.fields.values().enumerate() {
+        match field {
+          NetAgentField::Port(x) if k != p => *counts.entry(x).or_insert(0) += 1,
+          NetAgentField::Port(_) | NetAgentField::Payload(_) => {}
+          _ => return None,
+        }
+      }
+    }
+    let outside = elems
+      .iter()
+      .enumerate()
+      .filter(|(e, _)| !consumed.contains(e))
+      .filter_map(|(_, x)| match x {
+        Elem::Agent(a) => Some(a),
+        Elem::Stmt(_) => None,
+      })
+      .chain(outers.iter().flatten())
+      .flat_map(NetAgent::all_idents)
+      .collect::<BTreeSet<_>>();
+    let mut crossing = BTreeSet::new();
+    for (x, count) in counts {
+      if count == 1 {
+        if !bound.contains(x) && !outside.contains(x) {
+          return None;
+        }
+        crossing.insert(x.clone());
+      }
+    }
+    if consumed.len() == 1 {
+      crossing.insert(wire.clone());
+    }
+
+    let mut scrutinee = vec![];
+    let mut values = vec![];
+    let mut lets = vec![];
+    for (usage, outer) in usages.iter().zip(outers) {
+      let (vars, exprs): (Vec<_>, Vec<_>) = usage
+        .fields
+        .values()
+        .filter_map(|x| match x {
+          NetAgentField::Payload(PayloadExpr { expr, .. }) => Some(expr),
+          _ => None,
+        })
+        .map(|expr| {
+          values.push(eval_expr(expr, env));
+          let var = format_ident!("__fused_{}", *fresh);
+          *fresh += 1;
+          scrutinee.push(var.clone());
+          (var, expr)
+        })
+        .unzip();
+      let stmt = (!vars.is_empty()).then(|| {
+        let stmt: syn::Stmt = parse_quote!(let (#(#vars,)*) = (#(#exprs,)*););
+        match stmt {
+          syn::Stmt::Local(local) => NetStmtKind::Let(local),
+          _ => unreachable!(),
+        }
+      });
+      lets.push((outer, stmt));
+    }
+
+    let mut arms = vec![];
+    let mut scopes = vec![];
+    let total = rule.arms.len();
+    for (i, pats) in rule.arms {
+      let mut subst = BTreeMap::new();
+      let mut links = vec![];
+      let mut agents = vec![];
+      let mut arm_pats = vec![];
+      let mut arm_bound = BTreeSet::new();
+      for (usage, pat) in usages.iter().zip(pats) {
+        if usage.fields.len() != pat.fields.len() || !matches!(pat.fields, Fields::Unnamed(_)) {
+          return None;
+        }
+        let p = principal(&pat.fields, |x| matches!(x, ImplAgentField::Payload(_)));
+        if p != principal(&usage.fields, is_net_payload) {
+          return None;
+        }
+        for (k, (u, f)) in usage.fields.values().zip(pat.fields.values()).enumerate() {
+          match (u, f) {
+            (NetAgentField::Port(_), ImplAgentField::Implicit(_))
+              if Some(k) == p && consumed.len() == 2 => {}
+            (NetAgentField::Port(u), ImplAgentField::Port(x)) if Some(k) == p => {
+              if consumed.len() == 2 || subst.insert(x.clone(), u.clone()).is_some() {
+                return None;
+              }
+              arm_bound.insert(u.clone());
+            }
+            (NetAgentField::Port(u), ImplAgentField::Port(x)) => match subst.get(x) {
+              Some(prev) if prev == u => {}
+              Some(prev) if crossing.contains(prev) && crossing.contains(u) => {
+                links.push((prev.clone(), u.clone()));
+              }
+              Some(_) => return None,
+              None => {
+                if crossing.contains(u) {
+                  arm_bound.insert(u.clone());
+                }
+                subst.insert(x.clone(), u.clone());
+              }
+            },
+            (NetAgentField::Port(u), ImplAgentField::Agent(nested)) => {
+              agents.push((nested.clone(), u.clone()));
+              if crossing.contains(u) {
+                arm_bound.insert(u.clone());
+              }
+            }
+            (NetAgentField::Payload(_), ImplAgentField::Payload(PayloadPat { pat, .. })) => {
+              arm_pats.push(pat.clone());
+            }
+            _ => return None,
+          }
+        }
+      }
+
+      let mut arm_env = Env::new();
+      let mut matched = Some(true);
+      for (pat, value) in arm_pats.iter().zip(&values) {
+        matched = match (matched, match_pat(pat, *value, &mut arm_env)) {
+          (Some(false), _) | (_, Some(false)) => Some(false),
+          (Some(true), Some(true)) => Some(true),
+          _ => None,
+        };
+      }
+      let cond = i.cond.as_ref().map(|x| eval_expr(x, &arm_env));
+      let status = match (matched, cond) {
+        (Some(false), _) | (_, Some(Some(Const::Bool(false)))) => Some(false),
+        (Some(true), None | Some(Some(Const::Bool(true)))) => Some(true),
+        _ => None,
+      };
+      if status == Some(false) {
+        continue;
+      }
+
+      for x in i.all_idents() {
+        if !subst.contains_key(x) {
+          subst.insert(x.clone(), fresh_ident(x, fresh));
+        }
+      }
+      let mut net = i.net.clone();
+      for agent in &mut net.agents {
+        self.rename_agent(agent, &subst);
+      }
+      let prefix = agents
+        .into_iter()
+        .map(|(mut agent, u)| {
+          self.rename_agent(&mut agent, &subst);
+          let implicit = self
+            .fields_mut(&mut agent.fields)
+            .into_iter()
+            .find(|x| matches!(x, NetAgentField::Implicit(_)));
+          if let Some(implicit) = implicit {
+            *implicit = NetAgentField::Port(u);
+          }
+          agent
+        })
+        .collect::<Vec<_>>();
+      for stmt in &mut net.stmts {
+        stmt.pos += prefix.len();
+      }
+      net.agents.splice(0..0, prefix);
+
+      arms.push(FusionArm {
+        pats: arm_pats,
+        cond: i.cond.clone(),
+        links,
+        net,
+      });
+      scopes.push((arm_bound, arm_env));
+      if status == Some(true) {
+        break;
+      }
+    }
+    if arms.is_empty() {
+      return None;
+    }
+    Some(FusionPlan {
+      consumed,
+      lets,
+      fusion: Fusion { scrutinee, arms },
+      scopes,
+      total,
+    })
+  }
+
+  fn fusion_rule<'a>(&'a self, x: &NetAgent, y: &NetAgent) -> Option<FusionRule<'a>> {
+    let exact = |a: &ImplAgent, b: &NetAgent| a.src == b.src && a.name == b.name;
+    let loose = |a: &ImplAgent, b: &NetAgent| a.name == b.name;
+    if (x.src == y.src && x.name == y.name)
+      || !x.args.is_empty()
+      || !y.args.is_empty()
+      || !matches!(x.fields, Fields::Unnamed(_))
+      || !matches!(y.fields, Fields::Unnamed(_))
+    {
+      return None;
+    }
+    let impls = self.items.iter().filter_map(Item::as_impl);
+    let pairs = impls
+      .clone()
+      .filter_map(|i| Some((i, &i.left, i.right.as_ref()?)));
+    let mut arms = vec![];
+    let mut loose_count = 0;
+    for (i, a, b) in pairs {
+      if (loose(a, x) && loose(b, y)) || (loose(a, y) && loose(b, x)) {
+        loose_count += 1;
+      }
+      if exact(a, x) && exact(b, y) {
+        arms.push((i, vec![a, b]));
+      } else if exact(a, y) && exact(b, x) {
+        arms.push((i, vec![b, a]));
+      }
+    }
+    if loose_count != arms.len() {
+      return None;
+    }
+    if !arms.is_empty() {
+      return Some(FusionRule {
+        consumed: vec![0, 1],
+        arms,
+      });
+    }
+    let mut groups = [x, y]
+      .into_iter()
+      .enumerate()
+      .map(|(c, z)| {
+        let wildcards = impls.clone().filter(|i| i.right.is_none());
+        let arms = wildcards
+          .clone()
+          .filter(|i| exact(&i.left, z))
+          .map(|i| (i, vec![&i.left]))
+          .collect::<Vec<_>>();
+        let loose_count = wildcards.filter(|i| loose(&i.left, z)).count();
+        (c, (&z.src, &z.name), arms, loose_count)
+      })
+      .collect::<Vec<_>>();
+    if groups
+      .iter()
+      .any(|(_, _, arms, count)| arms.len() != *count)
+    {
+      return None;
+    }
+    groups.retain(|(_, _, arms, _)| !arms.is_empty());
+    let (c, _, arms, _) = groups.into_iter().min_by(|a, b| a.1.cmp(&b.1))?;
+    Some(FusionRule {
+      consumed: vec![c],
+      arms,
+    })
+  }
+
+  fn is_fusable(&self, i: &Impl) -> bool {
+    let stmts = i.net.stmts.iter().all(|stmt| match &stmt.kind {
+      NetStmtKind::Let(local) => {
+        local.init.as_ref().is_some_and(|x| x.diverge.is_none()) && stmt.awaited().is_none()
+      }
+      NetStmtKind::Block(_) => true,
+    });
+    let implicit = i.agents().any(|a| {
+      let p = principal(&a.fields, |x| matches!(x, ImplAgentField::Payload(_)));
+      a.fields
+        .values()
+        .enumerate()
+        .any(|(k, x)| matches!(x, ImplAgentField::Implicit(_)) && Some(k) != p)
+    });
+    stmts
+      && !implicit
+      && i.generics.is_empty()
+      && self.impl_attrs(i).is_empty()
+      && !mentions_scope(self.impl_tokens(i))
+  }
+
+  fn impl_tokens(&self, i: &Impl) -> TokenStream {
+    let mut tokens = i.cond.as_ref().map(|x| quote!(#x)).unwrap_or_default();
+    for stmt in &i.net.stmts {
+      tokens.extend(match &stmt.kind {
+        NetStmtKind::Let(x) => quote!(#x),
+        NetStmtKind::Block(x) => quote!(#x),
+      });
+    }
+    let mut stack = i.net.agents.iter().collect::<Vec<_>>();
+    stack.extend(
+      i.agents()
+        .flat_map(|a| a.fields.values())
+        .filter_map(|x| match x {
+          ImplAgentField::Agent(x) => Some(x),
+          _ => None,
+        }),
+    );
+    while let Some(agent) = stack.pop() {
+      for field in agent.fields.values() {
+        match field {
+          NetAgentField::Payload(PayloadExpr { expr, .. }) => tokens.extend(quote!(#expr)),
+          NetAgentField::Agent(x) => stack.push(x),
+          _ => {}
+        }
+      }
+    }
+    tokens
+  }
+
+  fn rename_agent(&self, agent: &mut NetAgent, subst: &BTreeMap<Ident, Ident>) {
+    for field in self.fields_mut(&mut agent.fields) {
+      match field {
+        NetAgentField::Port(x) => {
+          if let Some(y) = subst.get(x) {
+            *x = y.clone();
+          }
+        }
+        NetAgentField::Agent(nested) => self.rename_agent(nested, subst),
+        _ => {}
+      }
+    }
+  }
+}
+
+fn principal<T>(fields: &Fields<T>, is_payload: impl std::ops::Fn(&T) -> bool) -> Option<usize> {
+  match fields {
+    Fields::Unnamed(_) => fields.values().position(|x| !is_payload(x)),
+    Fields::Named(_) => None,
+  }
+}
+
+fn is_net_payload(field: &NetAgentField) -> bool {
+  matches!(field, NetAgentField::Payload(_))
+}
+
+// Wires are often named after agents, so the name is lowercased to keep the
+// variables it ends up in snake case.
+fn fresh_ident(ident: &Ident, fresh: &mut usize) -> Ident {
+  let id = std::mem::replace(fresh, *fresh + 1);
+  let name = ident.to_string().to_lowercase();
+  format_ident!("__{}_f{}", name, id, span = ident.span())
+}
+
+fn net_elems(net: &mut Net) -> Vec<Elem> {
+  let mut stmts = std::mem::take(&mut net.stmts).into_iter().peekable();
+  let mut elems = vec![];
+  for (i, agent) in std::mem::take(&mut net.agents).into_iter().enumerate() {
+    while let Some(stmt) = stmts.next_if(|x| x.pos <= i) {
+      elems.push(Elem::Stmt(stmt.kind));
+    }
+    elems.push(Elem::Agent(agent));
+  }
+  elems.extend(stmts.map(|x| Elem::Stmt(x.kind)));
+  elems
+}
+
+fn set_net_elems(net: &mut Net, elems: Vec<Elem>) {
+  for elem in elems {
+    match elem {
+      Elem::Stmt(kind) => net.stmts.push(NetStmt {
+        pos: net.agents.len(),
+        kind,
+      }),
+      Elem::Agent(agent) => net.agents.push(agent),
+    }
+  }
+}
+
+fn mentions_scope(tokens: TokenStream) -> bool {
+  tokens.into_iter().any(|token| match token {
+    proc_macro2::TokenTree::Ident(ident) => ident == "self" || ident == "Self" || ident == "return",
+    proc_macro2::TokenTree::Group(group) => mentions_scope(group.stream()),
+    _ => false,
+  })
+}
+
+// Rust patterns bind lowercase names and compare against uppercase constants.
+fn is_const_name(ident: &Ident) -> bool {
+  ident.to_string().starts_with(char::is_uppercase)
+}
+
+fn match_pat(pat: &Pat, value: Option<Const>, env: &mut Env) -> Option<bool> {
+  match pat {
+    Pat::Wild(_) => Some(true),
+    Pat::Paren(x) => match_pat(&x.pat, value, env),
+    Pat::Ident(x) if x.by_ref.is_none() && !is_const_name(&x.ident) => {
+      if let Some(value) = value {
+        env.insert(x.ident.clone(), value);
+      }
+      match &x.subpat {
+        Some((_, sub)) => match_pat(sub, value, env),
+        None => Some(true),
+      }
+    }
+    Pat::Lit(x) => Some(eval_lit(&x.lit)? == value?),
+    Pat::Or(x) => {
+      let cases = x
+        .cases
+        .iter()
+        .map(|x| match_pat(x, value, &mut Env::new()))
+        .collect::<Vec<_>>();
+      if cases.contains(&Some(true)) {
+        Some(true)
+      } else if cases.iter().all(|x| *x == Some(false)) {
+        Some(false)
+      } else {
+        None
+      }
+    }
+    Pat::Range(x) => {
+      let Const::Int(value) = value? else {
+        return None;
+      };
+      let bound = |x: &Option<Box<Expr>>| match x.as_deref().map(|x| eval_expr(x, &Env::new())) {
+        Some(Some(Const::Int(x))) => Some(Some(x)),
+        Some(_) => None,
+        None => Some(None),
+      };
+      let start = bound(&x.start)?.unwrap_or(i128::MIN);
+      let end = bound(&x.end)?;
+      Some(
+        value >= start
+          && match (x.limits, end) {
+            (_, None) => true,
+            (RangeLimits::HalfOpen(_), Some(end)) => value < end,
+            (RangeLimits::Closed(_), Some(end)) => value <= end,
+          },
+      )
+    }
+    _ => None,
+  }
+}
+
+fn eval_lit(lit: &Lit) -> Option<Const> {
+  match lit {
+    Lit::Int(x) => x.base10_parse().ok().map(Const::Int),
+    Lit::Bool(x) => Some(Const::Bool(x.value)),
+    _ => None,
+  }
+}
+
+fn eval_expr(expr: &Expr, env: &Env) -> Option<Const> {
+  let value = match expr {
+    Expr::Lit(x) => eval_lit(&x.lit)?,
+    Expr::Paren(x) => eval_expr(&x.expr, env)?,
+    Expr::Group(x) => eval_expr(&x.expr, env)?,
+    Expr::Path(x) => *env.get(x.path.get_ident()?)?,
+    Expr::Unary(x) => match (x.op, eval_expr(&x.expr, env)?) {
+      (UnOp::Not(_), Const::Bool(x)) => Const::Bool(!x),
+      _ => return None,
+    },
+    Expr::Binary(x) => {
+      let a = eval_expr(&x.left, env)?;
+      let b = eval_expr(&x.right, env)?;
+      match (a, x.op, b) {
+        (Const::Int(a), BinOp::Add(_), Const::Int(b)) => Const::Int(a + b),
+        (Const::Int(a), BinOp::Sub(_), Const::Int(b)) => Const::Int(a - b),
+        (Const::Int(a), BinOp::Mul(_), Const::Int(b)) => Const::Int(a.checked_mul(b)?),
+        (Const::Int(a), BinOp::Div(_), Const::Int(b)) => Const::Int(a.checked_div(b)?),
+        (Const::Int(a), BinOp::Rem(_), Const::Int(b)) => Const::Int(a.checked_rem(b)?),
+        (Const::Int(a), BinOp::Lt(_), Const::Int(b)) => Const::Bool(a < b),
+        (Const::Int(a), BinOp::Le(_), Const::Int(b)) => Const::Bool(a <= b),
+        (Const::Int(a), BinOp::Gt(_), Const::Int(b)) => Const::Bool(a > b),
+        (Const::Int(a), BinOp::Ge(_), Const::Int(b)) => Const::Bool(a >= b),
+        (a, BinOp::Eq(_), b) => Const::Bool(a == b),
+        (a, BinOp::Ne(_), b) => Const::Bool(a != b),
+        (Const::Bool(a), BinOp::And(_), Const::Bool(b)) => Const::Bool(a && b),
+        (Const::Bool(a), BinOp::Or(_), Const::Bool(b)) => Const::Bool(a || b),
+        _ => return None,
+      }
+    }
+    _ => return None,
+  };
+  match value {
+    Const::Int(x) if !(0..=u64::MAX as i128).contains(&x) => None,
+    value => Some(value),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use syn::parse::Parser;
+
+  fn eval(expr: &str, env: &[(&str, Const)]) -> Option<Const> {
+    let env = env
+      .iter()
+      .map(|(name, value)| (Ident::new(name, proc_macro2::Span::call_site()), *value))
+      .collect();
+    eval_expr(&syn::parse_str(expr).unwrap(), &env)
+  }
+
+  fn matches(pat: &str, value: Option<Const>) -> (Option<bool>, Env) {
+    let pat = Pat::parse_multi_with_leading_vert.parse_str(pat).unwrap();
+    let mut env = Env::new();
+    (match_pat(&pat, value, &mut env), env)
+  }
+
+  #[test]
+  fn evaluates_constant_expressions() {
+    assert_eq!(eval("2 + 3 * 4", &[]), Some(Const::Int(14)));
+    assert_eq!(eval("(7 - 2) % 3", &[]), Some(Const::Int(2)));
+    assert_eq!(
+      eval("x * 2 == 6", &[("x", Const::Int(3))]),
+      Some(Const::Bool(true))
+    );
+    assert_eq!(eval("!(1 < 2) || 3 >= 3", &[]), Some(Const::Bool(true)));
+    assert_eq!(
+      eval("18446744073709551615", &[]),
+      Some(Const::Int(u64::MAX as i128))
+    );
+  }
+
+  #[test]
+  fn leaves_unknown_or_out_of_range_expressions_to_runtime() {
+    assert_eq!(eval("y + 1", &[]), None);
+    assert_eq!(eval("1 + true", &[]), None);
+    assert_eq!(eval("1 / 0", &[]), None);
+    assert_eq!(eval("1 - 2", &[]), None);
+    assert_eq!(eval("18446744073709551615 + 1", &[]), None);
+    assert_eq!(eval("f(1)", &[]), None);
+  }
+
+  #[test]
+  fn matches_patterns_against_constants() {
+    let int = |x| Some(Const::Int(x));
+    assert_eq!(matches("_", None).0, Some(true));
+    assert_eq!(matches("0", int(0)).0, Some(true));
+    assert_eq!(matches("0", int(1)).0, Some(false));
+    assert_eq!(matches("0", None).0, None);
+    assert_eq!(matches("1..=5", int(5)).0, Some(true));
+    assert_eq!(matches("1..5", int(5)).0, Some(false));
+    assert_eq!(matches("0 | 2", int(2)).0, Some(true));
+    assert_eq!(matches("0 | 2", int(1)).0, Some(false));
+    assert_eq!(matches("true", Some(Const::Bool(true))).0, Some(true));
+    let (matched, env) = matches("n @ 2..", int(7));
+    assert_eq!(matched, Some(true));
+    assert_eq!(env.values().collect::<Vec<_>>(), [&Const::Int(7)]);
+  }
+
+  #[test]
+  fn names_wires_after_agents_in_snake_case() {
+    let output = crate::compile_str(
+      "
+      pub struct Unit(+Unit);
+      pub struct Probe(-Unit, $u64);
+      impl Probe(p, $tag) for _ { Probe(p, $tag) }
+      fn f() { Probe(Unit(_), $2) }
+      ",
+    )
+    .unwrap()
+    .to_string();
+    assert!(output.contains("__unit_f"), "{output}");
+    assert!(!output.contains("__Unit_f"), "{output}");
+  }
+}
@@ -0,0 +1,19 @@
+#[macro_use]
+mod diagnostics;
+
+mod build;
+mod check;
+mod compile;
+mod expand;
+mod fuse;
+mod parser;
+mod resolve;
+pub use build::*;
+pub use diagnostics::*;
+pub use parser::*;
+pub use resolve::*;
+
+use proc_macro2::{Literal, TokenStream};
+use quote::{format_ident, quote, quote_spanned};
+use std::collections::{BTreeMap, BTreeSet};
+use syn::{spanned::Spanned, Ident};
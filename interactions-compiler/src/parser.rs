@@ -0,0 +1,75 @@
+mod defs;
+mod derives;
+mod effects;
+mod enums;
+mod exprs;
+mod fields;
+mod fns;
+mod generics;
+mod impls;
+mod items;
+pub mod kw;
+mod matches;
+mod modules;
+mod net;
+mod operators;
+mod schemas;
+mod structs;
+mod types;
+mod uses;
+
+pub use defs::*;
+pub use derives::*;
+pub use effects::*;
+pub use enums::*;
+pub use exprs::*;
+pub use fields::*;
+pub use fns::*;
+pub use generics::*;
+pub use impls::*;
+pub use items::*;
+pub use matches::*;
+pub use modules::*;
+pub use net::*;
+pub use operators::*;
+pub use schemas::*;
+pub use structs::*;
+pub use types::*;
+pub use uses::*;
+
+use proc_macro2::{Span, TokenStream};
+use syn::{parse::Parse, Token};
+
+#[derive(Debug)]
+pub struct Program {
+  pub items: Vec<Item>,
+  pub modules: Vec<Module>,
+  pub source: TokenStream,
+  pub call_site: Option<Span>,
+}
+
+impl Parse for Program {
+  fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+    let call_site = match input.peek(Token![$]) {
+      true => Some(input.parse::<Token![$]>()?.span),
+      false => None,
+    };
+    let mut modules = vec![];
+    while input.peek(Token![@]) {
+      modules.push(input.parse()?);
+    }
+    let source = input.fork().parse()?;
+    let mut items: Vec<Item> = vec![];
+    while !input.is_empty() {
+      items.push(input.parse()?);
+    }
+    let mut program = Program {
+      items,
+      modules,
+      source,
+      call_site,
+    };
+    program.resolve_uses();
+    Ok(program)
+  }
+}
@@ -0,0 +1,195 @@
+use crate::*;
+use syn::{
+  braced, parenthesized,
+  parse::{Parse, ParseStream},
+  punctuated::Punctuated,
+  token::Paren,
+  Attribute, Token, Type, Visibility,
+};
+
+#[derive(Debug, Clone)]
+pub struct Def {
+  pub attrs: Vec<Attribute>,
+  pub vis: Visibility,
+  pub name: Ident,
+  pub generics: Vec<Ident>,
+  pub inputs: Vec<DefType>,
+  pub output: DefType,
+  pub clauses: Vec<Clause>,
+}
+
+impl Parse for Def {
+  fn parse(input: ParseStream) -> syn::Result<Self> {
+    let attrs = input.call(Attribute::parse_outer)?;
+    let vis: Visibility = input.parse()?;
+    let _: kw::def = input.parse()?;
+    let name: Ident = input.parse()?;
+    let generics = parse_generics(input)?;
+    let inner;
+    parenthesized!(inner in input);
+    let inputs = inner.parse_terminated(DefType::parse, Token![,])?;
+    let _: Token![->] = input.parse()?;
+    let output: DefType = input.parse()?;
+    let inner;
+    braced!(inner in input);
+    let mut clauses = vec![];
+    while !inner.is_empty() {
+      let clause: Clause = inner.parse()?;
+      if clause.name != name {
+        return Err(syn::Error::new(
+          clause.name.span(),
+          format!("expected a clause of `{}`", name),
+        ));
+      }
+      clauses.push(clause);
+    }
+    Ok(Def {
+      attrs,
+      vis,
+      name,
+      generics,
+      inputs: inputs.into_iter().collect(),
+      output,
+      clauses,
+    })
+  }
+}
+
+#[derive(Debug, Clone)]
+pub struct DefType {
+  pub name: Ident,
+  pub args: Vec<Type>,
+}
+
+impl Parse for DefType {
+  fn parse(input: ParseStream) -> syn::Result<Self> {
+    let name: Ident = input.parse()?;
+    let args = parse_type_args(input)?;
+    Ok(DefType { name, args })
+  }
+}
+
+#[derive(Debug, Clone)]
+pub struct Clause {
+  pub name: Ident,
+  pub args: Vec<Pattern>,
+  pub term: Term,
+}
+
+impl Parse for Clause {
+  fn parse(input: ParseStream) -> syn::Result<Self> {
+    let name: Ident = input.parse()?;
+    let inner;
+    parenthesized!(inner in input);
+    let args = inner.parse_terminated(Pattern::parse, Token![,])?;
+    let _: Token![=] = input.parse()?;
+    let term: Term = input.parse()?;
+    let _: Token![;] = input.parse()?;
+    Ok(Clause {
+      name,
+      args: args.into_iter().collect(),
+      term,
+    })
+  }
+}
+
+#[derive(Debug, Clone)]
+pub enum Pattern {
+  Wild(Token![_]),
+  Var(Ident),
+  Payload(PayloadPat),
+  Agent(DefAgent<Pattern>),
+}
+
+impl Parse for Pattern {
+  fn parse(input: ParseStream) -> syn::Result<Self> {
+    let lookahead = input.lookahead1();
+    if lookahead.peek(Token![_]) {
+      input.parse().map(Pattern::Wild)
+    } else if lookahead.peek(Token![$]) {
+      input.parse().map(Pattern::Payload)
+    } else if lookahead.peek(Ident) {
+      if is_agent(input) {
+        input.parse().map(Pattern::Agent)
+      } else {
+        input.parse().map(Pattern::Var)
+      }
+    } else {
+      Err(lookahead.error())
+    }
+  }
+}
+
+#[derive(Debug, Clone)]
+pub enum Term {
+  Hole(Token![_]),
+  Var(Ident),
+  Payload(PayloadExpr),
+  Agent(DefAgent<Term>),
+}
+
+impl Parse for Term {
+  fn parse(input: ParseStream) -> syn::Result<Self> {
+    let lookahead = input.lookahead1();
+    if lookahead.peek(Token![_]) {
+      input.parse().map(Term::Hole)
+    } else if lookahead.peek(Token![$]) {
+      input.parse().map(Term::Payload)
+    } else if lookahead.peek(Ident) {
+      if is_agent(input) {
+        input.parse().map(Term::Agent)
+      } else {
+        input.parse().map(Term::Var)
+      }
+    } else {
+      Err(lookahead.error())
+    }
+  }
+}
+
+fn is_agent(input: ParseStream) -> bool {
+  let fork = input.fork();
+  let Ok(ident) = fork.parse::<Ident>() else {
+    return false;
+  };
+  fork.peek(Paren)
+    || fork.peek(Token![::])
+    || fork.peek(Token![<])
+    || ident.to_string().starts_with(char::is_uppercase)
+}
+
+#[derive(Debug, Clone)]
+pub struct DefAgent<T> {
+  pub src: Option<Ident>,
+  pub name: Ident,
+  pub args: Vec<Type>,
+  pub fields: Vec<T>,
+}
+
+impl<T: Parse> Parse for DefAgent<T> {
+  fn parse(input: ParseStream) -> syn::Result<Self> {
+    let mut src = None;
+    let mut name: Ident = input.parse()?;
+    if input.lookahead1().peek(Token![::]) {
+      let _: Token![::] = input.parse()?;
+      src = Some(name);
+      name = input.parse()?;
+    }
+    let args = parse_type_args(input)?;
+    let fields = if input.peek(Paren) {
+      let inner;
+      parenthesized!(inner in input);
+      Punctuated::<T, Token![,]>::parse_terminated(&inner)?
+        .into_iter()
+        .collect()
+    } else {
+      vec![]
+    };
+    Ok(DefAgent {
+      src,
+      name,
+      args,
+      fields,
+    })
+  }
+}
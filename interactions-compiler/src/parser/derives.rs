@@ -0,0 +1,70 @@
+use syn::{
+  parse::{Parse, ParseStream},
+  punctuated::Punctuated,
+  Attribute, Ident, Token,
+};
+
+#[derive(Debug, Clone, Default)]
+pub struct Derives {
+  pub erase: Option<Ident>,
+  pub clone: Option<Ident>,
+  pub erasers: Vec<DeriveAgent>,
+  pub cloners: Vec<DeriveAgent>,
+  pub erase_src: Option<Ident>,
+  pub clone_src: Option<Ident>,
+}
+
+impl Derives {
+  pub fn from_attrs(attrs: &mut Vec<Attribute>) -> syn::Result<Self> {
+    let mut derives = Derives::default();
+    for attr in std::mem::take(attrs) {
+      if attr.path().is_ident("derive") {
+        let names = attr.parse_args_with(Punctuated::<Ident, Token![,]>::parse_terminated)?;
+        for name in names {
+          if name == "Erase" {
+            derives.erase = Some(name);
+          } else if name == "Clone" {
+            derives.clone = Some(name);
+          } else {
+            return Err(syn::Error::new(name.span(), "expected `Erase` or `Clone`"));
+          }
+        }
+      } else if attr.path().is_ident("erase") {
+        derives
+          .erasers
+          .extend(attr.parse_args_with(Punctuated::<DeriveAgent, Token![,]>::parse_terminated)?);
+      } else if attr.path().is_ident("clone") {
+        derives
+          .cloners
+          .extend(attr.parse_args_with(Punctuated::<DeriveAgent, Token![,]>::parse_terminated)?);
+      } else {
+        attrs.push(attr);
+      }
+    }
+    Ok(derives)
+  }
+}
+
+/// An agent chosen to erase or clone ports of a given type, written
+/// `List = EraseList` or `U64 = std::Erase`.
+#[derive(Debug, Clone)]
+pub struct DeriveAgent {
+  pub ty: Ident,
+  pub src: Option<Ident>,
+  pub name: Ident,
+}
+
+impl Parse for DeriveAgent {
+  fn parse(input: ParseStream) -> syn::Result<Self> {
+    let ty: Ident = input.parse()?;
+    let _: Token![=] = input.parse()?;
+    let mut src = None;
+    let mut name: Ident = input.parse()?;
+    if input.lookahead1().peek(Token![::]) {
+      let _: Token![::] = input.parse()?;
+      src = Some(name);
+      name = input.parse()?;
+    }
+    Ok(DeriveAgent { ty, src, name })
+  }
+}
@@ -0,0 +1,31 @@
+use crate::*;
+use syn::{braced, parse::Parse, Attribute, TraitItem, Visibility};
+
+#[derive(Debug, Clone)]
+pub struct Effect {
+  pub attrs: Vec<Attribute>,
+  pub vis: Visibility,
+  pub name: Ident,
+  pub items: Vec<TraitItem>,
+}
+
+impl Parse for Effect {
+  fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+    let attrs = input.call(Attribute::parse_outer)?;
+    let vis: Visibility = input.parse()?;
+    let _: kw::effect = input.parse()?;
+    let name: Ident = input.parse()?;
+    let inner;
+    braced!(inner in input);
+    let mut items = vec![];
+    while !inner.is_empty() {
+      items.push(inner.parse()?);
+    }
+    Ok(Effect {
+      attrs,
+      vis,
+      name,
+      items,
+    })
+  }
+}
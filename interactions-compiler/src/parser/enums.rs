@@ -0,0 +1,58 @@
+use crate::*;
+use syn::{braced, parse::Parse, token::Paren, Attribute, Ident, Token, Visibility};
+
+#[derive(Debug, Clone)]
+pub struct Enum {
+  pub attrs: Vec<Attribute>,
+  pub derives: Derives,
+  pub vis: Visibility,
+  pub name: Ident,
+  pub generics: Vec<Ident>,
+  pub variants: Vec<Variant>,
+}
+
+impl Parse for Enum {
+  fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+    let mut attrs = input.call(Attribute::parse_outer)?;
+    let derives = Derives::from_attrs(&mut attrs)?;
+    let vis: Visibility = input.parse()?;
+    let _: Token![enum] = input.parse()?;
+    let name: Ident = input.parse()?;
+    let generics = parse_generics(input)?;
+    let inner;
+    braced!(inner in input);
+    let variants = inner.parse_terminated(Variant::parse, Token![,])?;
+    Ok(Enum {
+      attrs,
+      derives,
+      vis,
+      name,
+      generics,
+      variants: variants.into_iter().collect(),
+    })
+  }
+}
+
+#[derive(Debug, Clone)]
+pub struct Variant {
+  pub attrs: Vec<Attribute>,
+  pub name: Ident,
+  pub fields: Option<UnnamedFields<StructField>>,
+}
+
+impl Parse for Variant {
+  fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+    let attrs = input.call(Attribute::parse_outer)?;
+    let name: Ident = input.parse()?;
+    let fields = if input.peek(Paren) {
+      Some(input.parse()?)
+    } else {
+      None
+    };
+    Ok(Variant {
+      attrs,
+      name,
+      fields,
+    })
+  }
+}
@@ -0,0 +1,116 @@
+use crate::*;
+use syn::{
+  parenthesized,
+  parse::{Parse, ParseStream},
+  token::{Brace, Paren},
+  BinOp, Expr, Lit, Token,
+};
+
+#[derive(Debug, Clone)]
+pub struct NetExpr {
+  pub target: Ident,
+  pub expr: InfixExpr,
+}
+
+impl NetExpr {
+  pub fn peek(input: ParseStream) -> bool {
+    let fork = input.fork();
+    fork.parse::<Ident>().is_ok() && !fork.peek(Token![==]) && fork.peek(Token![=])
+  }
+}
+
+impl Parse for NetExpr {
+  fn parse(input: ParseStream) -> syn::Result<Self> {
+    let target: Ident = input.parse()?;
+    let _: Token![=] = input.parse()?;
+    let expr: InfixExpr = input.parse()?;
+    Ok(NetExpr { target, expr })
+  }
+}
+
+#[derive(Debug, Clone)]
+pub enum InfixExpr {
+  Wire(Ident),
+  Const(PayloadExpr),
+  Binary(Box<InfixExpr>, BinOp, Box<InfixExpr>),
+}
+
+impl InfixExpr {
+  pub fn wires_mut(&mut self) -> Vec<&mut Ident> {
+    match self {
+      InfixExpr::Wire(x) => vec![x],
+      InfixExpr::Const(_) => vec![],
+      InfixExpr::Binary(a, _, b) => {
+        let mut wires = a.wires_mut();
+        wires.extend(b.wires_mut());
+        wires
+      }
+    }
+  }
+}
+
+impl Parse for InfixExpr {
+  fn parse(input: ParseStream) -> syn::Result<Self> {
+    parse_binary(input, 0)
+  }
+}
+
+fn parse_binary(input: ParseStream, min_prec: u8) -> syn::Result<InfixExpr> {
+  let mut expr = parse_operand(input)?;
+  while let Some(prec) = peek_op(input) {
+    if prec < min_prec {
+      break;
+    }
+    let op: BinOp = input.parse()?;
+    let rhs = parse_binary(input, prec + 1)?;
+    expr = InfixExpr::Binary(Box::new(expr), op, Box::new(rhs));
+  }
+  Ok(expr)
+}
+
+fn parse_operand(input: ParseStream) -> syn::Result<InfixExpr> {
+  let lookahead = input.lookahead1();
+  if lookahead.peek(Paren) {
+    let inner;
+    parenthesized!(inner in input);
+    let expr = inner.parse()?;
+    if !inner.is_empty() {
+      return Err(inner.error("unexpected token"));
+    }
+    Ok(expr)
+  } else if lookahead.peek(Token![$]) {
+    let dollar: Token![$] = input.parse()?;
+    let lookahead = input.lookahead1();
+    if !(lookahead.peek(Lit)
+      || lookahead.peek(Ident)
+      || lookahead.peek(Paren)
+      || lookahead.peek(Brace))
+    {
+      return Err(lookahead.error());
+    }
+    let operand: proc_macro2::TokenTree = input.parse()?;
+    let expr = match syn::parse2(operand.into())? {
+      Expr::Paren(paren) => *paren.expr,
+      expr => expr,
+    };
+    Ok(InfixExpr::Const(PayloadExpr { dollar, expr }))
+  } else if lookahead.peek(Ident) {
+    input.parse().map(InfixExpr::Wire)
+  } else {
+    Err(lookahead.error())
+  }
+}
+
+fn peek_op(input: ParseStream) -> Option<u8> {
+  let op = input.fork().parse::<BinOp>().ok()?;
+  Some(match op {
+    BinOp::Mul(_) | BinOp::Div(_) | BinOp::Rem(_) => 10,
+    BinOp::Add(_) | BinOp::Sub(_) => 9,
+    BinOp::Shl(_) | BinOp::Shr(_) => 8,
+    BinOp::BitAnd(_) => 7,
+    BinOp::BitXor(_) => 6,
+    BinOp::BitOr(_) => 5,
+    BinOp::Eq(_) | BinOp::Ne(_) | BinOp::Lt(_) | BinOp::Le(_) | BinOp::Gt(_) | BinOp::Ge(_) => 4,
+    _ => return None,
+  })
+}
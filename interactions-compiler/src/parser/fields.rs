@@ -6,10 +6,10 @@ use syn::{
   punctuated::Punctuated,
   spanned::Spanned,
   token::{Brace, Comma, Paren},
-  Ident, Token,
+  Attribute, Ident, Token,
 };
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum Fields<T> {
   Unnamed(UnnamedFields<T>),
   Named(NamedFields<T>),
@@ -28,9 +28,27 @@ impl<T> Fields<T> {
       Fields::Named(x) => x.len(),
     }
   }
+  pub fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
   pub fn semi(&self) -> bool {
     matches!(self, Fields::Unnamed(_))
   }
+  pub fn attrs(&self) -> impl Iterator<Item = &[Attribute]> {
+    match self {
+      Fields::Unnamed(x) => Either::Left(x.attrs.iter().map(|x| &x[..])),
+      Fields::Named(x) => Either::Right(x.entries.iter().map(|x| &x.attrs[..])),
+    }
+  }
+  pub fn reject_attrs(&self) -> syn::Result<()> {
+    match self.attrs().flatten().next() {
+      Some(attr) => Err(syn::Error::new_spanned(
+        attr,
+        "attributes are only supported on agent declarations",
+      )),
+      None => Ok(()),
+    }
+  }
   pub fn span(&self) -> Span {
     match self {
       Fields::Unnamed(x) => x.paren.span.span(),
@@ -52,9 +70,10 @@ impl<T: Parse + TryFrom<Ident>> Parse for Fields<T> {
   }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct UnnamedFields<T> {
   pub paren: Paren,
+  pub attrs: Vec<Vec<Attribute>>,
   pub entries: Punctuated<T, Comma>,
 }
 
@@ -65,20 +84,34 @@ impl<T> UnnamedFields<T> {
   pub fn len(&self) -> usize {
     self.entries.len()
   }
+  pub fn is_empty(&self) -> bool {
+    self.entries.is_empty()
+  }
 }
 
 impl<T: Parse> Parse for UnnamedFields<T> {
   fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
     let inner;
     let paren = parenthesized!(inner in input);
+    let mut attrs = vec![];
+    let mut entries = Punctuated::new();
+    while !inner.is_empty() {
+      attrs.push(inner.call(Attribute::parse_outer)?);
+      entries.push_value(inner.parse()?);
+      if inner.is_empty() {
+        break;
+      }
+      entries.push_punct(inner.parse()?);
+    }
     Ok(UnnamedFields {
       paren,
-      entries: inner.parse_terminated(T::parse, Token![,])?,
+      attrs,
+      entries,
     })
   }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct NamedFields<T> {
   pub brace: Brace,
   pub entries: Punctuated<NamedField<T>, Comma>,
@@ -91,6 +124,9 @@ impl<T> NamedFields<T> {
   pub fn len(&self) -> usize {
     self.entries.len()
   }
+  pub fn is_empty(&self) -> bool {
+    self.entries.is_empty()
+  }
 }
 
 impl<T: Parse + TryFrom<Ident>> Parse for NamedFields<T> {
@@ -104,14 +140,16 @@ impl<T: Parse + TryFrom<Ident>> Parse for NamedFields<T> {
   }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct NamedField<T> {
+  pub attrs: Vec<Attribute>,
   pub key: Ident,
   pub val: T,
 }
 
 impl<T: Parse + TryFrom<Ident>> Parse for NamedField<T> {
   fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+    let attrs = input.call(Attribute::parse_outer)?;
     let key: Ident = input.parse()?;
     let lookahead = input.lookahead1();
     let val = if lookahead.peek(Token![:]) {
@@ -120,6 +158,6 @@ impl<T: Parse + TryFrom<Ident>> Parse for NamedField<T> {
     } else {
       key.clone().try_into().map_err(|_| lookahead.error())?
     };
-    Ok(NamedField { key, val })
+    Ok(NamedField { attrs, key, val })
   }
 }
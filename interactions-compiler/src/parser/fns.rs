@@ -1,8 +1,9 @@
 use crate::*;
-use syn::{parenthesized, parse::Parse, Ident, Token, Visibility};
+use syn::{parenthesized, parse::Parse, Attribute, Ident, Token, Visibility};
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Fn {
+  pub attrs: Vec<Attribute>,
   pub vis: Visibility,
   pub name: Ident,
   pub parts: Vec<FnPart>,
@@ -11,6 +12,7 @@ pub struct Fn {
 
 impl Parse for Fn {
   fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+    let attrs = input.call(Attribute::parse_outer)?;
     let vis: Visibility = input.parse()?;
     let _: Token![fn] = input.parse()?;
     let name: Ident = input.parse()?;
@@ -20,6 +22,7 @@ impl Parse for Fn {
     let inputs = inputs.into_iter().collect::<Vec<_>>();
     let net: Net = input.parse()?;
     Ok(Fn {
+      attrs,
       vis,
       name,
       parts: inputs,
@@ -28,18 +31,20 @@ impl Parse for Fn {
   }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct FnPart {
+  pub attrs: Vec<Attribute>,
   pub name: Ident,
   pub ty: StructField,
 }
 
 impl Parse for FnPart {
   fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+    let attrs = input.call(Attribute::parse_outer)?;
     let name: Ident = input.parse()?;
     let _: Token![:] = input.parse()?;
     let ty: StructField = input.parse()?;
-    Ok(FnPart { name, ty })
+    Ok(FnPart { attrs, name, ty })
   }
 }
 
@@ -0,0 +1,31 @@
+use syn::{parse::ParseStream, Ident, Token, Type};
+
+pub fn parse_generics(input: ParseStream) -> syn::Result<Vec<Ident>> {
+  let mut params = vec![];
+  if input.peek(Token![<]) {
+    let _: Token![<] = input.parse()?;
+    while !input.peek(Token![>]) {
+      params.push(input.parse()?);
+      if !input.peek(Token![>]) {
+        let _: Token![,] = input.parse()?;
+      }
+    }
+    let _: Token![>] = input.parse()?;
+  }
+  Ok(params)
+}
+
+pub fn parse_type_args(input: ParseStream) -> syn::Result<Vec<Type>> {
+  let mut args = vec![];
+  if input.peek(Token![<]) {
+    let _: Token![<] = input.parse()?;
+    while !input.peek(Token![>]) {
+      args.push(input.parse()?);
+      if !input.peek(Token![>]) {
+        let _: Token![,] = input.parse()?;
+      }
+    }
+    let _: Token![>] = input.parse()?;
+  }
+  Ok(args)
+}
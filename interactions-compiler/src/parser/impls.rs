@@ -3,24 +3,33 @@ use itertools::Either;
 use syn::{
   parse::Parse,
   token::{Brace, Paren},
-  Expr, Ident, Pat, Token,
+  Attribute, Expr, Ident, Pat, Token, Type,
 };
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Impl {
+  pub attrs: Vec<Attribute>,
   pub imp: Token![impl],
+  pub generics: Vec<Ident>,
   pub left: ImplAgent,
-  pub right: ImplAgent,
+  pub right: Option<ImplAgent>,
   pub cond: Option<Expr>,
   pub net: Net,
 }
 
 impl Parse for Impl {
   fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+    let attrs = input.call(Attribute::parse_outer)?;
     let imp: Token![impl] = input.parse()?;
+    let generics = parse_generics(input)?;
     let left: ImplAgent = input.parse()?;
     let _: Token![for] = input.parse()?;
-    let right: ImplAgent = input.parse()?;
+    let right = if input.peek(Token![_]) {
+      let _: Token![_] = input.parse()?;
+      None
+    } else {
+      Some(input.parse()?)
+    };
     let lookahead = input.lookahead1();
     let cond = if lookahead.peek(Token![if]) {
       let _: Token![if] = input.parse()?;
@@ -31,7 +40,9 @@ impl Parse for Impl {
     };
     let net: Net = input.parse()?;
     Ok(Impl {
+      attrs,
       imp,
+      generics,
       left,
       right,
       cond,
@@ -41,12 +52,22 @@ impl Parse for Impl {
 }
 
 impl Impl {
+  pub fn agents(&self) -> impl Iterator<Item = &ImplAgent> {
+    [Some(&self.left), self.right.as_ref()]
+      .into_iter()
+      .flatten()
+  }
+
+  pub fn agents_mut(&mut self) -> impl Iterator<Item = &mut ImplAgent> {
+    [Some(&mut self.left), self.right.as_mut()]
+      .into_iter()
+      .flatten()
+  }
+
   pub fn all_idents<'a>(&'a self) -> impl Iterator<Item = &'a Ident> {
     self
-      .left
-      .fields
-      .values()
-      .chain(self.right.fields.values())
+      .agents()
+      .flat_map(|a| a.fields.values())
       .flat_map(|f| match f {
         ImplAgentField::Port(x) => Some(Either::Left([x].into_iter())),
         ImplAgentField::Agent(a) => Some(Either::Right(a.all_idents())),
@@ -57,10 +78,11 @@ impl Impl {
   }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ImplAgent {
   pub src: Option<Ident>,
   pub name: Ident,
+  pub args: Vec<Type>,
   pub fields: Fields<ImplAgentField>,
 }
 
@@ -73,12 +95,19 @@ impl Parse for ImplAgent {
       src = Some(name);
       name = input.parse()?;
     }
+    let args = parse_type_args(input)?;
     let fields: Fields<ImplAgentField> = input.parse()?;
-    Ok(ImplAgent { src, name, fields })
+    fields.reject_attrs()?;
+    Ok(ImplAgent {
+      src,
+      name,
+      args,
+      fields,
+    })
   }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum ImplAgentField {
   Implicit(Token![_]),
   Port(Ident),
@@ -95,7 +124,11 @@ impl Parse for ImplAgentField {
       let fork = input.fork();
       let _: Ident = fork.parse()?;
       let lookahead = fork.lookahead1();
-      if lookahead.peek(Paren) || lookahead.peek(Brace) || lookahead.peek(Token![::]) {
+      if lookahead.peek(Paren)
+        || lookahead.peek(Brace)
+        || lookahead.peek(Token![::])
+        || lookahead.peek(Token![<])
+      {
         input.parse().map(ImplAgentField::Agent)
       } else {
         input.parse().map(ImplAgentField::Port)
@@ -115,7 +148,7 @@ impl TryFrom<Ident> for ImplAgentField {
   }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct PayloadPat {
   pub dollar: Token![$],
   pub pat: Pat,
@@ -0,0 +1,102 @@
+use crate::*;
+use syn::{parse::Parse, Attribute, Token, Visibility};
+
+#[derive(Debug, Clone)]
+#[allow(clippy::large_enum_variant)]
+pub enum Item {
+  Struct(Struct),
+  Impl(Impl),
+  Fn(Fn),
+  Use(Use),
+  Schema(Schema),
+  Enum(Enum),
+  Match(Match),
+  Def(Def),
+  Operators(Operators),
+  Effect(Effect),
+}
+
+impl Item {
+  pub fn as_struct(&self) -> Option<&Struct> {
+    match self {
+      Item::Struct(x) => Some(x),
+      _ => None,
+    }
+  }
+  pub fn as_impl(&self) -> Option<&Impl> {
+    match self {
+      Item::Impl(x) => Some(x),
+      _ => None,
+    }
+  }
+  pub fn as_fn(&self) -> Option<&Fn> {
+    match self {
+      Item::Fn(x) => Some(x),
+      _ => None,
+    }
+  }
+  pub fn as_use(&self) -> Option<&Use> {
+    match self {
+      Item::Use(x) => Some(x),
+      _ => None,
+    }
+  }
+  pub fn attrs_mut(&mut self) -> &mut Vec<Attribute> {
+    match self {
+      Item::Struct(x) => &mut x.attrs,
+      Item::Impl(x) => &mut x.attrs,
+      Item::Fn(x) => &mut x.attrs,
+      Item::Use(x) => &mut x.attrs,
+      Item::Schema(x) => &mut x.attrs,
+      Item::Enum(x) => &mut x.attrs,
+      Item::Match(x) => &mut x.attrs,
+      Item::Def(x) => &mut x.attrs,
+      Item::Operators(x) => &mut x.attrs,
+      Item::Effect(x) => &mut x.attrs,
+    }
+  }
+  pub fn as_effect(&self) -> Option<&Effect> {
+    match self {
+      Item::Effect(x) => Some(x),
+      _ => None,
+    }
+  }
+  pub fn as_enum(&self) -> Option<&Enum> {
+    match self {
+      Item::Enum(x) => Some(x),
+      _ => None,
+    }
+  }
+}
+
+impl Parse for Item {
+  fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+    let fork = input.fork();
+    let _ = fork.call(Attribute::parse_outer)?;
+    let _: Visibility = fork.parse()?;
+    let lookahead = fork.lookahead1();
+    if lookahead.peek(Token![struct]) || lookahead.peek(Token![extern]) {
+      input.parse().map(Item::Struct)
+    } else if lookahead.peek(Token![impl]) {
+      input.parse().map(Item::Impl)
+    } else if lookahead.peek(Token![fn]) {
+      input.parse().map(Item::Fn)
+    } else if lookahead.peek(Token![use]) {
+      input.parse().map(Item::Use)
+    } else if lookahead.peek(Token![for]) {
+      input.parse().map(Item::Schema)
+    } else if lookahead.peek(Token![enum]) {
+      input.parse().map(Item::Enum)
+    } else if lookahead.peek(Token![match]) {
+      input.parse().map(Item::Match)
+    } else if lookahead.peek(kw::def) {
+      input.parse().map(Item::Def)
+    } else if lookahead.peek(kw::operators) {
+      input.parse().map(Item::Operators)
+    } else if lookahead.peek(kw::effect) {
+      input.parse().map(Item::Effect)
+    } else {
+      Err(lookahead.error())
+    }
+  }
+}
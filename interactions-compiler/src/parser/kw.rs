@@ -0,0 +1,4 @@
+syn::custom_keyword!(def);
+syn::custom_keyword!(effect);
+syn::custom_keyword!(operators);
+syn::custom_keyword!(module);
@@ -0,0 +1,91 @@
+use crate::*;
+use syn::{braced, parse::Parse, Attribute, Expr, Token};
+
+#[derive(Debug, Clone)]
+pub struct Match {
+  pub attrs: Vec<Attribute>,
+  pub mat: Token![match],
+  pub generics: Vec<Ident>,
+  pub agent: ImplAgent,
+  pub arms: Vec<MatchArm>,
+}
+
+impl Parse for Match {
+  fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+    let attrs = input.call(Attribute::parse_outer)?;
+    let mat: Token![match] = input.parse()?;
+    let generics = parse_generics(input)?;
+    let agent: ImplAgent = input.parse()?;
+    let inner;
+    braced!(inner in input);
+    let mut arms = vec![];
+    while !inner.is_empty() {
+      arms.push(inner.parse()?);
+    }
+    Ok(Match {
+      attrs,
+      mat,
+      generics,
+      agent,
+      arms,
+    })
+  }
+}
+
+#[derive(Debug, Clone)]
+pub struct MatchArm {
+  pub attrs: Vec<Attribute>,
+  pub pat: MatchPat,
+  pub cond: Option<Expr>,
+  pub net: Net,
+}
+
+impl Parse for MatchArm {
+  fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+    let attrs = input.call(Attribute::parse_outer)?;
+    let pat = if input.peek(Token![_]) {
+      let _: Token![_] = input.parse()?;
+      MatchPat::Wildcard(None)
+    } else if input.peek(Ident) && (input.peek2(Token![=>]) || input.peek2(Token![if])) {
+      MatchPat::Wildcard(Some(input.parse()?))
+    } else {
+      MatchPat::Agent(input.parse()?)
+    };
+    let lookahead = input.lookahead1();
+    let cond = if lookahead.peek(Token![if]) {
+      let _: Token![if] = input.parse()?;
+      let cond: Expr = input.parse()?;
+      Some(cond)
+    } else {
+      None
+    };
+    let _: Token![=>] = input.parse()?;
+    let net: Net = input.parse()?;
+    if input.peek(Token![,]) {
+      let _: Token![,] = input.parse()?;
+    }
+    Ok(MatchArm {
+      attrs,
+      pat,
+      cond,
+      net,
+    })
+  }
+}
+
+/// What an arm matches: a constructor, or any agent for `_`, or for `x`,
+/// which also names the agent's principal port.
+#[derive(Debug, Clone)]
+pub enum MatchPat {
+  Agent(ImplAgent),
+  Wildcard(Option<Ident>),
+}
+
+impl MatchPat {
+  pub fn as_agent(&self) -> Option<&ImplAgent> {
+    match self {
+      MatchPat::Agent(agent) => Some(agent),
+      MatchPat::Wildcard(_) => None,
+    }
+  }
+}
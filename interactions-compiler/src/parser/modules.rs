@@ -0,0 +1,23 @@
+use crate::*;
+use syn::{braced, parse::Parse, Path, Token};
+
+/// The source of an imported module, written `@module libs::std { ... }`.
+/// Modules put these in front of their own source when they re-invoke the
+/// macro with the items of the modules they use.
+#[derive(Debug)]
+pub struct Module {
+  pub path: Path,
+  pub program: Program,
+}
+
+impl Parse for Module {
+  fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+    let _: Token![@] = input.parse()?;
+    let _: kw::module = input.parse()?;
+    let path = input.call(Path::parse_mod_style)?;
+    let inner;
+    braced!(inner in input);
+    let program: Program = inner.parse()?;
+    Ok(Module { path, program })
+  }
+}
@@ -4,12 +4,29 @@ use syn::{
   braced,
   parse::Parse,
   token::{Brace, Paren},
-  Expr, Token,
+  Block, Expr, Local, Pat, Stmt, Token, Type,
 };
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Net {
   pub agents: Vec<NetAgent>,
+  pub exprs: Vec<NetExpr>,
+  pub stmts: Vec<NetStmt>,
+  pub fused: Vec<Fusion>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Fusion {
+  pub scrutinee: Vec<Ident>,
+  pub arms: Vec<FusionArm>,
+}
+
+#[derive(Debug, Clone)]
+pub struct FusionArm {
+  pub pats: Vec<Pat>,
+  pub cond: Option<Expr>,
+  pub links: Vec<(Ident, Ident)>,
+  pub net: Net,
 }
 
 impl Net {
@@ -39,17 +56,74 @@ impl Parse for Net {
     let inner;
     braced!(inner in input);
     let mut agents: Vec<NetAgent> = vec![];
+    let mut exprs: Vec<NetExpr> = vec![];
+    let mut stmts: Vec<NetStmt> = vec![];
     while !inner.is_empty() {
-      agents.push(inner.parse()?);
+      if inner.peek(Token![let]) || inner.peek(Brace) {
+        let kind = inner.parse()?;
+        stmts.push(NetStmt {
+          pos: agents.len(),
+          kind,
+        });
+      } else if NetExpr::peek(&inner) {
+        exprs.push(inner.parse()?);
+      } else {
+        agents.push(inner.parse()?);
+      }
+    }
+    Ok(Net {
+      agents,
+      exprs,
+      stmts,
+      fused: vec![],
+    })
+  }
+}
+
+#[derive(Debug, Clone)]
+pub struct NetStmt {
+  pub pos: usize,
+  pub kind: NetStmtKind,
+}
+
+impl NetStmt {
+  pub fn awaited(&self) -> Option<&Expr> {
+    match &self.kind {
+      NetStmtKind::Let(Local {
+        init: Some(init), ..
+      }) if init.diverge.is_none() => match &*init.expr {
+        Expr::Await(x) => Some(&x.base),
+        _ => None,
+      },
+      _ => None,
+    }
+  }
+}
+
+#[derive(Debug, Clone)]
+#[allow(clippy::large_enum_variant)]
+pub enum NetStmtKind {
+  Let(Local),
+  Block(Block),
+}
+
+impl Parse for NetStmtKind {
+  fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+    if input.peek(Brace) {
+      return input.parse().map(NetStmtKind::Block);
+    }
+    match input.parse()? {
+      Stmt::Local(local) => Ok(NetStmtKind::Let(local)),
+      stmt => Err(syn::Error::new_spanned(stmt, "expected a `let` binding")),
     }
-    Ok(Net { agents })
   }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct NetAgent {
   pub src: Option<Ident>,
   pub name: Ident,
+  pub args: Vec<Type>,
   pub fields: Fields<NetAgentField>,
 }
 
@@ -62,12 +136,19 @@ impl Parse for NetAgent {
       src = Some(name);
       name = input.parse()?;
     }
-    let fields = input.parse()?;
-    Ok(NetAgent { src, name, fields })
+    let args = parse_type_args(input)?;
+    let fields: Fields<NetAgentField> = input.parse()?;
+    fields.reject_attrs()?;
+    Ok(NetAgent {
+      src,
+      name,
+      args,
+      fields,
+    })
   }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum NetAgentField {
   Implicit(Token!(_)),
   Port(Ident),
@@ -86,7 +167,11 @@ impl Parse for NetAgentField {
       let fork = input.fork();
       let _: Ident = fork.parse()?;
       let lookahead = fork.lookahead1();
-      if lookahead.peek(Paren) || lookahead.peek(Brace) || lookahead.peek(Token![::]) {
+      if lookahead.peek(Paren)
+        || lookahead.peek(Brace)
+        || lookahead.peek(Token![::])
+        || lookahead.peek(Token![<])
+      {
         input.parse().map(NetAgentField::Agent)
       } else {
         input.parse().map(NetAgentField::Port)
@@ -104,7 +189,7 @@ impl TryFrom<Ident> for NetAgentField {
   }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct PayloadExpr {
   pub dollar: Token![$],
   pub expr: Expr,
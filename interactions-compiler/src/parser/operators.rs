@@ -0,0 +1,88 @@
+use crate::*;
+use syn::{
+  braced,
+  parse::{Parse, ParseStream},
+  Attribute, BinOp, Token,
+};
+
+#[derive(Debug, Clone)]
+pub struct Operators {
+  pub attrs: Vec<Attribute>,
+  pub kw: kw::operators,
+  pub entries: Vec<OperatorEntry>,
+}
+
+impl Parse for Operators {
+  fn parse(input: ParseStream) -> syn::Result<Self> {
+    let attrs = input.call(Attribute::parse_outer)?;
+    if let Some(attr) = attrs.iter().find(|attr| !attr.path().is_ident("doc")) {
+      return Err(syn::Error::new_spanned(attr, "unsupported attribute"));
+    }
+    let kw: kw::operators = input.parse()?;
+    let inner;
+    braced!(inner in input);
+    let mut entries = vec![];
+    while !inner.is_empty() {
+      entries.push(inner.parse()?);
+    }
+    Ok(Operators { attrs, kw, entries })
+  }
+}
+
+#[derive(Debug, Clone)]
+pub struct OperatorEntry {
+  pub op: Operator,
+  pub agent: OperatorAgent,
+  pub agent_x: Option<OperatorAgent>,
+}
+
+impl Parse for OperatorEntry {
+  fn parse(input: ParseStream) -> syn::Result<Self> {
+    let op: Operator = input.parse()?;
+    let _: Token![=>] = input.parse()?;
+    let agent: OperatorAgent = input.parse()?;
+    let agent_x = if input.peek(Token![,]) {
+      let _: Token![,] = input.parse()?;
+      Some(input.parse()?)
+    } else {
+      None
+    };
+    let _: Token![;] = input.parse()?;
+    Ok(OperatorEntry { op, agent, agent_x })
+  }
+}
+
+#[derive(Debug, Clone)]
+pub enum Operator {
+  Literal(Token![$]),
+  Binary(BinOp),
+}
+
+impl Parse for Operator {
+  fn parse(input: ParseStream) -> syn::Result<Self> {
+    if input.peek(Token![$]) {
+      input.parse().map(Operator::Literal)
+    } else {
+      input.parse().map(Operator::Binary)
+    }
+  }
+}
+
+#[derive(Debug, Clone)]
+pub struct OperatorAgent {
+  pub src: Option<Ident>,
+  pub name: Ident,
+}
+
+impl Parse for OperatorAgent {
+  fn parse(input: ParseStream) -> syn::Result<Self> {
+    let mut src = None;
+    let mut name: Ident = input.parse()?;
+    if input.lookahead1().peek(Token![::]) {
+      let _: Token![::] = input.parse()?;
+      src = Some(name);
+      name = input.parse()?;
+    }
+    Ok(OperatorAgent { src, name })
+  }
+}
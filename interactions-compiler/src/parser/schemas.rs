@@ -0,0 +1,97 @@
+use proc_macro2::{Delimiter, Spacing, TokenStream, TokenTree};
+use syn::{
+  braced, bracketed, parenthesized,
+  parse::{Parse, ParseStream},
+  token::Paren,
+  Attribute, Ident, Token,
+};
+
+#[derive(Debug, Clone)]
+pub struct Schema {
+  pub attrs: Vec<Attribute>,
+  pub vars: Vec<Ident>,
+  pub entries: Vec<Vec<TokenStream>>,
+  pub body: TokenStream,
+}
+
+impl Parse for Schema {
+  fn parse(input: ParseStream) -> syn::Result<Self> {
+    let attrs = input.call(Attribute::parse_outer)?;
+    let _: Token![for] = input.parse()?;
+    let vars = if input.peek(Paren) {
+      let inner;
+      parenthesized!(inner in input);
+      inner
+        .parse_terminated(Ident::parse, Token![,])?
+        .into_iter()
+        .collect()
+    } else {
+      vec![input.parse()?]
+    };
+    let _: Token![in] = input.parse()?;
+    let inner;
+    bracketed!(inner in input);
+    let mut entries = vec![];
+    for entry in split_commas(inner.parse()?) {
+      let values = if vars.len() == 1 {
+        vec![entry.clone()]
+      } else {
+        match &entry.clone().into_iter().collect::<Vec<_>>()[..] {
+          [TokenTree::Group(group)] if group.delimiter() == Delimiter::Parenthesis => {
+            split_commas(group.stream())
+          }
+          _ => return Err(syn::Error::new_spanned(entry, "expected a tuple")),
+        }
+      };
+      if values.len() != vars.len() {
+        return Err(syn::Error::new_spanned(
+          entry,
+          format!("expected {} values, found {}", vars.len(), values.len()),
+        ));
+      }
+      entries.push(values);
+    }
+    let body;
+    braced!(body in input);
+    let body: TokenStream = body.parse()?;
+    Ok(Schema {
+      attrs,
+      vars,
+      entries,
+      body,
+    })
+  }
+}
+
+// Splits on the commas outside of generic arguments, so that an entry like
+// `Pair<A, B>` stays whole. A `>` that ends `->` or `=>` closes nothing.
+fn split_commas(tokens: TokenStream) -> Vec<TokenStream> {
+  let mut parts = vec![];
+  let mut part = vec![];
+  let mut depth = 0usize;
+  let mut arrow = false;
+  for token in tokens {
+    let mut joint = false;
+    match &token {
+      TokenTree::Punct(punct) if punct.as_char() == ',' && depth == 0 => {
+        parts.push(part.drain(..).collect());
+        continue;
+      }
+      TokenTree::Punct(punct) => {
+        match punct.as_char() {
+          '<' => depth += 1,
+          '>' if !arrow => depth = depth.saturating_sub(1),
+          _ => {}
+        }
+        joint = matches!(punct.as_char(), '-' | '=') && punct.spacing() == Spacing::Joint;
+      }
+      _ => {}
+    }
+    arrow = joint;
+    part.push(token);
+  }
+  if !part.is_empty() {
+    parts.push(part.into_iter().collect());
+  }
+  parts
+}
@@ -1,10 +1,14 @@
 use crate::*;
-use syn::{parse::Parse, Ident, Token, Visibility};
+use syn::{parse::Parse, Attribute, Ident, Token, Visibility};
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Struct {
+  pub attrs: Vec<Attribute>,
+  pub derives: Derives,
   pub vis: Visibility,
+  pub external: Option<Token![extern]>,
   pub name: Ident,
+  pub generics: Vec<Ident>,
   pub fields: Fields<StructField>,
 }
 
@@ -29,18 +33,30 @@ impl Struct {
 
 impl Parse for Struct {
   fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+    let mut attrs = input.call(Attribute::parse_outer)?;
+    let derives = Derives::from_attrs(&mut attrs)?;
     let vis: Visibility = input.parse()?;
+    let external: Option<Token![extern]> = input.parse()?;
     let _: Token![struct] = input.parse()?;
     let name: Ident = input.parse()?;
+    let generics = parse_generics(input)?;
     let fields: Fields<_> = input.parse()?;
     if fields.semi() {
       let _: Token![;] = input.parse()?;
     }
-    Ok(Struct { vis, name, fields })
+    Ok(Struct {
+      attrs,
+      derives,
+      vis,
+      external,
+      name,
+      generics,
+      fields,
+    })
   }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum StructField {
   Port(PortType),
   Payload(PayloadType),
@@ -1,20 +1,23 @@
-use syn::{parse::Parse, Ident, Token, Type};
+use crate::*;
+use syn::{parse::Parse, Token, Type};
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct PortType {
   pub sign: Sign,
   pub name: Ident,
+  pub args: Vec<Type>,
 }
 
 impl Parse for PortType {
   fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
     let sign: Sign = input.parse()?;
     let name: Ident = input.parse()?;
-    Ok(PortType { sign, name })
+    let args = parse_type_args(input)?;
+    Ok(PortType { sign, name, args })
   }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct PayloadType {
   pub dollar: Token![$],
   pub ty: Type,
@@ -0,0 +1,219 @@
+use syn::{
+  parse::Parse, punctuated::Punctuated, Attribute, Ident, Path, PathSegment, Token, UseTree,
+};
+
+#[derive(Debug, Clone)]
+pub struct Use {
+  pub attrs: Vec<Attribute>,
+  pub path: Path,
+  pub module: Option<Ident>,
+  pub agents: Vec<UseAgent>,
+  pub glob: Option<Token![*]>,
+  pub implicit: bool,
+  // Set for `use a::b::X;`, which imports either the module `a::b::X` or the
+  // agent `X` of `a::b`; it is parsed as the latter until the program it is in
+  // decides which, see `Program::resolve_uses`.
+  pub unresolved: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct UseAgent {
+  pub name: Ident,
+  pub alias: Option<Ident>,
+}
+
+impl UseAgent {
+  pub fn ident(&self) -> &Ident {
+    self.alias.as_ref().unwrap_or(&self.name)
+  }
+}
+
+impl Parse for Use {
+  fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+    let attrs = input.call(Attribute::parse_outer)?;
+    if let Some(attr) = attrs.iter().find(|attr| attr.path().is_ident("cfg")) {
+      return Err(syn::Error::new_spanned(
+        attr,
+        "`#[cfg]` is not supported on `use` items",
+      ));
+    }
+    let _: Token![use] = input.parse()?;
+    let mut tree: UseTree = input.parse()?;
+    let _: Token![;] = input.parse()?;
+    let mut segments = Punctuated::<PathSegment, Token![::]>::new();
+    let mut u = loop {
+      match tree {
+        UseTree::Path(p) => {
+          segments.push(p.ident.into());
+          tree = *p.tree;
+        }
+        UseTree::Name(n) if !segments.is_empty() => {
+          let mut u = Use::new(None);
+          u.agents.push(UseAgent {
+            name: n.ident,
+            alias: None,
+          });
+          u.unresolved = true;
+          break u;
+        }
+        UseTree::Rename(r) if !segments.is_empty() => {
+          let mut u = Use::new(None);
+          u.agents.push(UseAgent {
+            name: r.ident,
+            alias: Some(r.rename),
+          });
+          u.unresolved = true;
+          break u;
+        }
+        UseTree::Name(n) => {
+          segments.push(n.ident.clone().into());
+          break Use::new(Some(n.ident));
+        }
+        UseTree::Rename(r) => {
+          segments.push(r.ident.into());
+          break Use::new(Some(r.rename));
+        }
+        UseTree::Glob(g) => {
+          let mut u = Use::new(None);
+          u.glob = Some(g.star_token);
+          break u;
+        }
+        UseTree::Group(g) => {
+          let mut u = Use::new(None);
+          for item in g.items {
+            match item {
+              UseTree::Name(n) if n.ident == "self" => {
+                u.module = segments.last().map(|s| s.ident.clone());
+              }
+              UseTree::Rename(r) if r.ident == "self" => u.module = Some(r.rename),
+              UseTree::Name(n) => u.agents.push(UseAgent {
+                name: n.ident,
+                alias: None,
+              }),
+              UseTree::Rename(r) => u.agents.push(UseAgent {
+                name: r.ident,
+                alias: Some(r.rename),
+              }),
+              UseTree::Glob(g) => u.glob = Some(g.star_token),
+              tree => return Err(syn::Error::new_spanned(tree, "expected an agent name")),
+            }
+          }
+          break u;
+        }
+      }
+    };
+    if segments.is_empty() {
+      return Err(input.error("expected a module path"));
+    }
+    u.attrs = attrs;
+    u.path = Path {
+      leading_colon: None,
+      segments,
+    };
+    Ok(u)
+  }
+}
+
+impl Use {
+  pub fn new(module: Option<Ident>) -> Self {
+    Use {
+      attrs: vec![],
+      path: Path {
+        leading_colon: None,
+        segments: Punctuated::new(),
+      },
+      module,
+      agents: vec![],
+      glob: None,
+      implicit: false,
+      unresolved: false,
+    }
+  }
+
+  // Reads an unresolved `use a::b::X;` as an import of the module `a::b::X`.
+  pub fn resolve_as_module(&mut self) {
+    let agent = self.agents.pop().unwrap();
+    self.path.segments.push(agent.name.clone().into());
+    self.module = Some(agent.alias.unwrap_or(agent.name));
+    self.unresolved = false;
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::{Item, Program};
+  use quote::ToTokens;
+
+  fn parse(source: &str) -> syn::Result<(String, Option<String>, Vec<String>, bool)> {
+    let program = syn::parse_str::<Program>(source)?;
+    let u = program.items.iter().find_map(Item::as_use).unwrap();
+    let agents = u
+      .agents
+      .iter()
+      .map(|a| match &a.alias {
+        Some(alias) => format!("{} as {}", a.name, alias),
+        None => a.name.to_string(),
+      })
+      .collect();
+    Ok((
+      u.path.to_token_stream().to_string().replace(' ', ""),
+      u.module.clone().map(|m| m.to_string()),
+      agents,
+      u.glob.is_some(),
+    ))
+  }
+
+  #[test]
+  fn parses_modules_and_agents() {
+    let s = |x: &str| x.to_string();
+    assert_eq!(
+      parse("use libs::std;").unwrap(),
+      (s("libs::std"), Some(s("std")), vec![], false)
+    );
+    assert_eq!(
+      parse("use libs::std as s;").unwrap(),
+      (s("libs::std"), Some(s("s")), vec![], false)
+    );
+    assert_eq!(
+      parse("use libs::std::*;").unwrap(),
+      (s("libs::std"), None, vec![], true)
+    );
+    assert_eq!(
+      parse("use libs::std::U64 as N; struct P(+P, -N);").unwrap(),
+      (s("libs::std"), None, vec![s("U64 as N")], false)
+    );
+    assert_eq!(
+      parse("use libs::std::{self, Add, U64 as N};").unwrap(),
+      (
+        s("libs::std"),
+        Some(s("std")),
+        vec![s("Add"), s("U64 as N")],
+        false
+      )
+    );
+  }
+
+  #[test]
+  fn resolves_single_names_by_their_uses() {
+    let s = |x: &str| x.to_string();
+    assert_eq!(
+      parse("use libs::nat; struct P(+P, -nat);").unwrap(),
+      (s("libs"), None, vec![s("nat")], false)
+    );
+    assert_eq!(
+      parse("use libs::Nat; impl P(_) for Nat::Z(_) {}").unwrap(),
+      (s("libs::Nat"), Some(s("Nat")), vec![], false)
+    );
+    assert_eq!(
+      parse("use libs::Nat;").unwrap(),
+      (s("libs::Nat"), Some(s("Nat")), vec![], false)
+    );
+  }
+
+  #[test]
+  fn rejects_malformed_uses() {
+    assert!(parse("use *;").is_err());
+    assert!(parse("use libs::std::{a::B};").is_err());
+    assert!(parse("#[cfg(test)] use libs::std;").is_err());
+  }
+}
@@ -0,0 +1,225 @@
+use crate::*;
+use proc_macro2::{Spacing, TokenTree};
+use syn::{punctuated::Punctuated, Path, PathSegment, Visibility};
+
+/// An agent as seen from the module being compiled: declared in it when
+/// `module` is `None`, otherwise declared in the module at that path.
+#[derive(Debug, Clone)]
+pub struct AgentPath {
+  pub module: Option<Path>,
+  pub name: Ident,
+}
+
+impl Program {
+  // Decides what each `use a::b::X;` imports from how the source refers to
+  // `X`: it is a module when the source writes `X::`, or never mentions `X`
+  // at all, and an agent otherwise.
+  pub fn resolve_uses(&mut self) {
+    let mut qualifiers = BTreeSet::new();
+    let mut mentions = BTreeSet::new();
+    scan_idents(self.source.clone(), &mut qualifiers, &mut mentions);
+    for item in &mut self.items {
+      let Item::Use(u) = item else {
+        continue;
+      };
+      if !u.unresolved {
+        continue;
+      }
+      let ident = u.agents[0].ident().to_string();
+      if qualifiers.contains(&ident) || !mentions.contains(&ident) {
+        u.resolve_as_module();
+      } else {
+        u.unresolved = false;
+      }
+    }
+  }
+
+  // `scope` is the module a reference is written in: `None` for the module
+  // being compiled, otherwise an index into `self.modules`.
+  pub fn resolve_agent(
+    &self,
+    scope: Option<usize>,
+    src: &Option<Ident>,
+    name: &Ident,
+  ) -> Option<AgentPath> {
+    let program = self.scope_program(scope);
+    if let Some(src) = src {
+      let u = program.uses().find(|u| u.module.as_ref() == Some(src))?;
+      return Some(AgentPath {
+        module: Some(self.scope_path(scope, &u.path)),
+        name: name.clone(),
+      });
+    }
+    if program.declares(name) {
+      return Some(AgentPath {
+        module: scope.map(|i| self.modules[i].path.clone()),
+        name: name.clone(),
+      });
+    }
+    for u in program.uses() {
+      if let Some(a) = u.agents.iter().find(|a| a.ident() == name) {
+        return Some(AgentPath {
+          module: Some(self.scope_path(scope, &u.path)),
+          name: a.name.clone(),
+        });
+      }
+    }
+    program
+      .uses()
+      .filter(|u| u.glob.is_some())
+      .map(|u| self.scope_path(scope, &u.path))
+      .find(|path| self.module(path).is_some_and(|m| m.program.exports(name)))
+      .map(|path| AgentPath {
+        module: Some(path),
+        name: name.clone(),
+      })
+  }
+
+  pub fn is_agent(&self, src: &Option<Ident>, name: &Ident) -> bool {
+    match self.resolve_agent(None, src, name) {
+      Some(AgentPath { module: None, .. }) => true,
+      Some(AgentPath {
+        module: Some(path),
+        name,
+      }) => self
+        .module(&path)
+        .is_some_and(|m| m.program.declares(&name)),
+      None => false,
+    }
+  }
+
+  pub fn uses(&self) -> impl Iterator<Item = &Use> + '_ {
+    self.items.iter().filter_map(Item::as_use)
+  }
+
+  pub fn declares(&self, name: &Ident) -> bool {
+    self
+      .items
+      .iter()
+      .filter_map(Item::as_struct)
+      .any(|s| &s.name == name)
+  }
+
+  // Whether other modules can import the agent `name` declared here.
+  pub fn exports(&self, name: &Ident) -> bool {
+    self.exported().any(|s| &s.name == name)
+  }
+
+  pub fn exported(&self) -> impl Iterator<Item = &Struct> + '_ {
+    self
+      .items
+      .iter()
+      .filter_map(Item::as_struct)
+      .filter(|s| !matches!(s.vis, Visibility::Inherited))
+  }
+
+  pub fn module(&self, path: &Path) -> Option<&Module> {
+    self.module_index(path).map(|i| &self.modules[i])
+  }
+
+  pub fn module_index(&self, path: &Path) -> Option<usize> {
+    let key = self.path_str(path);
+    self
+      .modules
+      .iter()
+      .position(|m| self.path_str(&m.path) == key)
+  }
+
+  // The alias the module at `path` goes by here, importing it under a hidden
+  // one when no `use` names it.
+  pub fn module_alias(&self, path: &Path, implicit: &mut Vec<Use>) -> Ident {
+    let key = self.path_str(path);
+    let alias = self
+      .uses()
+      .chain(implicit.iter())
+      .filter(|u| self.path_str(&u.path) == key)
+      .find_map(|u| u.module.clone());
+    if let Some(alias) = alias {
+      return alias;
+    }
+    let alias = format_ident!("__{}", self.escape(&key));
+    let mut u = Use::new(Some(alias.clone()));
+    u.path = path.clone();
+    u.implicit = true;
+    implicit.push(u);
+    alias
+  }
+
+  pub fn path_str(&self, path: &Path) -> String {
+    let segments = path.segments.iter().map(|s| s.ident.to_string());
+    let prefix = path.leading_colon.map(|_| "::").unwrap_or("");
+    prefix.to_string() + &segments.collect::<Vec<_>>().join("::")
+  }
+
+  fn scope_program(&self, scope: Option<usize>) -> &Program {
+    match scope {
+      Some(i) => &self.modules[i].program,
+      None => self,
+    }
+  }
+
+  // Paths in an imported module are relative to it; this rewrites them to be
+  // relative to the module being compiled.
+  fn scope_path(&self, scope: Option<usize>, path: &Path) -> Path {
+    let Some(i) = scope else {
+      return path.clone();
+    };
+    let base = &self.modules[i].path;
+    let mut segments = path.segments.iter().peekable();
+    let first = &segments.peek().unwrap().ident;
+    if path.leading_colon.is_some() || first == "crate" {
+      return path.clone();
+    }
+    let mut joined = base.segments.iter().cloned().collect::<Vec<_>>();
+    if first == "self" {
+      segments.next();
+    }
+    while let Some(segment) = segments.next_if(|s| s.ident == "super") {
+      match joined.last() {
+        Some(last) if last.ident != "super" => {
+          joined.pop();
+        }
+        _ => joined.push(segment.clone()),
+      }
+    }
+    joined.extend(segments.cloned());
+    Path {
+      leading_colon: base.leading_colon,
+      segments: joined.into_iter().collect::<Punctuated<PathSegment, _>>(),
+    }
+  }
+}
+
+// Collects the idents in `tokens` outside of `use` items, into `qualifiers`
+// when a `::` follows them and into `mentions` otherwise.
+fn scan_idents(
+  tokens: TokenStream,
+  qualifiers: &mut BTreeSet<String>,
+  mentions: &mut BTreeSet<String>,
+) {
+  let tokens = tokens.into_iter().collect::<Vec<_>>();
+  let mut i = 0;
+  while i < tokens.len() {
+    match &tokens[i] {
+      TokenTree::Ident(ident) if ident == "use" => {
+        while i < tokens.len() && !matches!(&tokens[i], TokenTree::Punct(p) if p.as_char() == ';') {
+          i += 1;
+        }
+      }
+      TokenTree::Ident(ident) => {
+        let qualified = matches!(
+          (tokens.get(i + 1), tokens.get(i + 2)),
+          (Some(TokenTree::Punct(a)), Some(TokenTree::Punct(b)))
+            if a.as_char() == ':' && a.spacing() == Spacing::Joint && b.as_char() == ':'
+        );
+        match qualified {
+          true => qualifiers.insert(ident.to_string()),
+          false => mentions.insert(ident.to_string()),
+        };
+      }
+      TokenTree::Group(group) => scan_idents(group.stream(), qualifiers, mentions),
+      _ => {}
+    }
+    i += 1;
+  }
+}
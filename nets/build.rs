@@ -0,0 +1,3 @@
+fn main() {
+  internets_interactions_compiler::build("src/bin/libs/nat.inet");
+}
@@ -10,6 +10,27 @@ impl Addr {
   pub fn is_null(&self) -> bool {
     self.0 as usize == 0
   }
+  #[inline(always)]
+  pub fn immediate(bits: u64) -> Addr {
+    Addr(((bits as usize) << 2 | 1) as *mut Word)
+  }
+  #[inline(always)]
+  pub fn is_immediate(&self) -> bool {
+    self.0 as usize & 1 != 0
+  }
+  #[inline(always)]
+  pub fn as_immediate(&self) -> u64 {
+    debug_assert!(self.is_immediate());
+    (self.0 as usize >> 2) as u64
+  }
+  #[inline(always)]
+  pub fn within(&self, start: Addr, len: Length) -> bool {
+    *self >= start && *self < start + len
+  }
+  #[inline(always)]
+  pub fn near(&self, other: Addr, len: Length) -> bool {
+    (self.0 as usize).abs_diff(other.0 as usize) < len.length_bytes as usize
+  }
 }
 
 impl Add<Delta> for Addr {
@@ -28,12 +49,20 @@ impl Add<Length> for Addr {
   }
 }
 
+impl Sub<Length> for Addr {
+  type Output = Addr;
+  #[inline(always)]
+  fn sub(self, len: Length) -> Self::Output {
+    Addr(((self.0 as usize) - (len.length_bytes as usize)) as *mut Word)
+  }
+}
+
 impl Sub<Addr> for Addr {
   type Output = Delta;
   #[inline(always)]
   fn sub(self, base: Addr) -> Self::Output {
     Delta {
-      offset_bytes: ((self.0 as isize) - (base.0 as isize)) as i32,
+      offset_bytes: ((self.0 as isize) - (base.0 as isize)) as IWord,
     }
   }
 }
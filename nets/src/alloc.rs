@@ -1,27 +1,74 @@
 mod bump;
+mod class;
 mod link;
 mod ring;
 
 use std::fmt::Debug;
 
 pub use bump::*;
+pub use class::*;
 pub use link::*;
 pub use ring::*;
 
 use crate::*;
 
+// `alloc_near` looks at up to `NEAR_PROBES` free blocks for one within
+// `NEAR_LEN` of the hint before falling back to `alloc`.
+pub const NEAR_PROBES: usize = 2;
+pub const NEAR_LEN: Length = Length::of(512);
+
 pub trait Alloc: BufferMut + Debug {
+  const PARTIAL_FREE: bool;
   fn alloc(&mut self, len: Length) -> Addr;
   #[inline(always)]
+  fn alloc_near(&mut self, near: Addr, len: Length) -> Addr {
+    let _ = near;
+    self.alloc(len)
+  }
+  #[inline(always)]
   fn alloc_write(&mut self, data: &[Word]) -> Addr {
-    let len = Length::of(data.len() as u32);
+    let len = Length::of(data.len() as UWord);
     let addr = self.alloc(len);
     self.slice_mut(addr, len).copy_from_slice(data);
     addr
   }
+  #[inline(always)]
+  fn alloc_write_near(&mut self, near: Addr, data: &[Word]) -> Addr {
+    let len = Length::of(data.len() as UWord);
+    let addr = self.alloc_near(near, len);
+    self.slice_mut(addr, len).copy_from_slice(data);
+    addr
+  }
+  // Whether agents of lengths `parts`, which will be freed one by one, should
+  // be allocated as a single block rather than as separate agents.
+  #[inline(always)]
+  fn prefers_block(&self, parts: &[Length]) -> bool {
+    let _ = parts;
+    Self::PARTIAL_FREE
+  }
   fn free(&mut self, addr: Addr, len: Length);
 }
 
+// Unlinks the first block near `near` from the free list after `head`, which
+// the caller has already checked, or returns `Addr::NULL`.
+#[inline(always)]
+pub(crate) fn unlink_near(buffer: &mut impl BufferMut, head: Addr, near: Addr) -> Addr {
+  let mut prev = head;
+  for _ in 0..NEAR_PROBES {
+    let addr: Addr = buffer.read_payload(prev);
+    if addr.is_null() {
+      break;
+    }
+    if addr.near(near, NEAR_LEN) {
+      let next: Addr = buffer.read_payload(addr);
+      buffer.write_payload(prev, next);
+      return addr;
+    }
+    prev = addr;
+  }
+  Addr::NULL
+}
+
 pub trait DelegateAlloc: Debug {
   type Alloc: Alloc;
   fn delegatee_alloc(&self) -> &Self::Alloc;
@@ -44,16 +91,70 @@ impl<T: DelegateAlloc> DelegateBufferMut for T {
 }
 
 impl<T: DelegateAlloc> Alloc for T {
+  const PARTIAL_FREE: bool = T::Alloc::PARTIAL_FREE;
   #[inline(always)]
   fn alloc(&mut self, len: Length) -> Addr {
     self.delegatee_alloc_mut().alloc(len)
   }
   #[inline(always)]
+  fn alloc_near(&mut self, near: Addr, len: Length) -> Addr {
+    self.delegatee_alloc_mut().alloc_near(near, len)
+  }
+  #[inline(always)]
   fn alloc_write(&mut self, data: &[Word]) -> Addr {
     self.delegatee_alloc_mut().alloc_write(data)
   }
   #[inline(always)]
+  fn alloc_write_near(&mut self, near: Addr, data: &[Word]) -> Addr {
+    self.delegatee_alloc_mut().alloc_write_near(near, data)
+  }
+  #[inline(always)]
+  fn prefers_block(&self, parts: &[Length]) -> bool {
+    self.delegatee_alloc().prefers_block(parts)
+  }
+  #[inline(always)]
   fn free(&mut self, addr: Addr, len: Length) {
     self.delegatee_alloc_mut().free(addr, len)
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const LEN: Length = Length::of(2);
+
+  // Allocates `n` blocks, then frees those at `freed`, in order.
+  fn blocks(alloc: &mut impl Alloc, n: usize, freed: &[usize]) -> Vec<Addr> {
+    let blocks = (0..n).map(|_| alloc.alloc(LEN)).collect::<Vec<_>>();
+    for &i in freed {
+      alloc.free(blocks[i], LEN);
+    }
+    blocks
+  }
+
+  fn prefers_nearby_blocks(mut alloc: impl Alloc) {
+    let blocks = blocks(&mut alloc, 1000, &[0, 900]);
+    assert!(!blocks[900].near(blocks[1], NEAR_LEN));
+    assert_eq!(alloc.alloc_near(blocks[1], LEN), blocks[0]);
+    assert_eq!(alloc.alloc(LEN), blocks[900]);
+  }
+
+  fn falls_back_past_the_probes(mut alloc: impl Alloc) {
+    let blocks = blocks(&mut alloc, 1000, &[0, 500, 700, 900]);
+    assert_eq!(alloc.alloc_near(blocks[1], LEN), blocks[900]);
+    assert_eq!(alloc.alloc_near(blocks[999], LEN), blocks[700]);
+  }
+
+  #[test]
+  fn link_alloc_allocates_near() {
+    prefers_nearby_blocks(LinkAlloc::new(ArrayBuffer::new(1 << 12)));
+    falls_back_past_the_probes(LinkAlloc::new(ArrayBuffer::new(1 << 12)));
+  }
+
+  #[test]
+  fn class_alloc_allocates_near() {
+    prefers_nearby_blocks(ClassAlloc::new(ArrayBuffer::new(1 << 12)));
+    falls_back_past_the_probes(ClassAlloc::new(ArrayBuffer::new(1 << 12)));
+  }
+}
@@ -22,6 +22,7 @@ impl<B: BufferMut> DelegateBufferMut for BumpAlloc<B> {
 }
 
 impl<B: BufferMut> Alloc for BumpAlloc<B> {
+  const PARTIAL_FREE: bool = true;
   #[inline(always)]
   fn alloc(&mut self, len: Length) -> Addr {
     let addr = self.alloc;
@@ -0,0 +1,505 @@
+use crate::*;
+
+// Blocks of up to `SMALL_WORDS` words are kept in exact-size free lists, like
+// `LinkAlloc`, and bumped upwards from the start of the buffer. Larger blocks
+// are carved downwards from the end of the buffer, carry a length tag in
+// their first and last word, and are binned by power of two so that they can
+// be split on allocation and coalesced with their neighbours on free.
+//
+// When the bump region is exhausted, small blocks are split off larger small
+// blocks, then carved in runs out of the large region. As a last resort
+// before running out of memory, the small free lists are coalesced.
+const SMALL_WORDS: usize = 64;
+const LARGE_BINS: usize = usize::BITS as usize;
+const TAGS_LEN: Length = Length::of(2);
+const LINK_LEN: Length = Length::of_payload::<Addr>();
+const MIN_SMALL_LEN: Length = LINK_LEN;
+const MIN_LARGE_LEN: Length = TAGS_LEN.add(LINK_LEN).add(LINK_LEN);
+
+#[derive(Debug)]
+pub struct ClassAlloc<B: BufferMut> {
+  pub buffer: B,
+  small: [Addr; SMALL_WORDS + 1],
+  large: [Addr; LARGE_BINS],
+  pub end: Addr,
+  pub top: Addr,
+}
+
+impl<B: BufferMut> DelegateBuffer for ClassAlloc<B> {
+  type Buffer = B;
+  #[inline(always)]
+  fn delegatee_buffer(&self) -> &Self::Buffer {
+    &self.buffer
+  }
+}
+
+impl<B: BufferMut> DelegateBufferMut for ClassAlloc<B> {
+  #[inline(always)]
+  fn delegatee_buffer_mut(&mut self) -> &mut Self::Buffer {
+    &mut self.buffer
+  }
+}
+
+impl<B: BufferMut> Alloc for ClassAlloc<B> {
+  const PARTIAL_FREE: bool = false;
+  #[inline(always)]
+  fn alloc(&mut self, len: Length) -> Addr {
+    if len.length_words() <= SMALL_WORDS {
+      self.alloc_small(len)
+    } else {
+      self.alloc_large(len)
+    }
+  }
+
+  #[inline(always)]
+  fn alloc_near(&mut self, near: Addr, len: Length) -> Addr {
+    if len.length_words() > SMALL_WORDS {
+      return self.alloc_large(len);
+    }
+    let head = self.small[len.length_words()];
+    if head.is_null() || head.near(near, NEAR_LEN) {
+      return self.alloc_small(len);
+    }
+    let addr = unlink_near(&mut self.buffer, head, near);
+    if addr.is_null() {
+      return self.alloc_small(len);
+    }
+    addr
+  }
+
+  #[inline(always)]
+  fn free(&mut self, addr: Addr, len: Length) {
+    debug_assert!(len >= MIN_SMALL_LEN);
+    self.assert_valid(addr, len);
+    if cfg!(debug_assertions) {
+      self.slice_mut(addr, len).fill(Word::NULL);
+    }
+    if len.length_words() <= SMALL_WORDS {
+      self.push_small(addr, len);
+    } else {
+      self.free_large(addr - Length::of(1));
+    }
+  }
+}
+
+impl<B: BufferMut> ClassAlloc<B> {
+  pub fn new(buffer: B) -> Self {
+    safe! { assert!(buffer.len() > Length::of(0)) };
+    let bounds = buffer.buffer_bounds();
+    ClassAlloc {
+      buffer,
+      small: [Addr::NULL; SMALL_WORDS + 1],
+      large: [Addr::NULL; LARGE_BINS],
+      end: bounds.start,
+      top: bounds.end,
+    }
+  }
+
+  #[inline(always)]
+  fn alloc_small(&mut self, len: Length) -> Addr {
+    let addr = self.small[len.length_words()];
+    if !addr.is_null() {
+      self.small[len.length_words()] = self.read_payload(addr);
+      return addr;
+    }
+    let addr = self.end;
+    if addr + len <= self.top {
+      self.end = addr + len;
+      return addr;
+    }
+    self.refill_small(len)
+  }
+
+  #[cold]
+  fn refill_small(&mut self, len: Length) -> Addr {
+    let addr = self.split_small(len);
+    if !addr.is_null() {
+      return addr;
+    }
+    let addr = self.carve_small(len);
+    if !addr.is_null() {
+      return addr;
+    }
+    if self.reclaim_small() {
+      return self.alloc_small(len);
+    }
+    oom!();
+  }
+
+  fn split_small(&mut self, len: Length) -> Addr {
+    for words in len.length_words() + 1..=SMALL_WORDS {
+      let addr = self.small[words];
+      let rest = Length::of(words as UWord) - len;
+      if addr.is_null() || rest < MIN_SMALL_LEN {
+        continue;
+      }
+      self.small[words] = self.read_payload(addr);
+      self.push_small(addr + len, rest);
+      return addr;
+    }
+    Addr::NULL
+  }
+
+  // The run stays tagged as used, so the large region never coalesces into
+  // it while its blocks live on in the small free lists; `reclaim_small`
+  // hands it back once they are all free again.
+  fn carve_small(&mut self, len: Length) -> Addr {
+    let count = SMALL_WORDS / len.length_words();
+    let mut run = self.try_alloc_large(Length::of((count * len.length_words()) as UWord));
+    if run.is_null() {
+      run = self.try_alloc_large(len);
+    }
+    if run.is_null() {
+      return Addr::NULL;
+    }
+    let end = run + self.tag(run - Length::of(1)).0 - TAGS_LEN;
+    let mut rest = run + len;
+    while rest + len <= end {
+      self.push_small(rest, len);
+      rest = rest + len;
+    }
+    if rest < end {
+      let tail = Length {
+        length_bytes: (end - rest).offset_bytes as UWord,
+      };
+      self.push_small(rest, tail);
+    }
+    run
+  }
+
+  // Merges adjacent small free blocks, handing runs that end at the bump
+  // pointer back to the bump region and fully free carved runs back to the
+  // large region. Returns whether anything changed.
+  #[cold]
+  fn reclaim_small(&mut self) -> bool {
+    let mut blocks = vec![];
+    for words in 1..=SMALL_WORDS {
+      let mut addr = std::mem::replace(&mut self.small[words], Addr::NULL);
+      while !addr.is_null() {
+        blocks.push((addr, Length::of(words as UWord)));
+        addr = self.read_payload(addr);
+      }
+    }
+    blocks.sort_by_key(|(addr, _)| *addr);
+    let mut runs: Vec<(Addr, Length)> = vec![];
+    for (addr, len) in blocks.iter().copied() {
+      match runs.last_mut() {
+        Some((run, run_len)) if *run + *run_len == addr => *run_len = *run_len + len,
+        _ => runs.push((addr, len)),
+      }
+    }
+    let mut carved = vec![];
+    let mut block = self.top;
+    while block < self.buffer_bounds().end {
+      let (size, used) = self.tag(block);
+      if used {
+        carved.push((block, size));
+      }
+      block = block + size;
+    }
+    let mut freed = vec![];
+    runs.retain(|&(run, run_len)| {
+      let block = (run - Length::of(1), run_len + TAGS_LEN);
+      let found = carved.binary_search(&block).is_ok();
+      if found {
+        freed.push(block.0);
+      }
+      !found
+    });
+    for &block in &freed {
+      self.free_large(block);
+    }
+    let old_end = self.end;
+    if let Some(&(run, run_len)) = runs.last() {
+      if run + run_len == self.end {
+        self.end = run;
+        runs.pop();
+      }
+    }
+    let mut pieces = 0;
+    for (mut run, mut run_len) in runs {
+      while run_len.non_zero() {
+        let words = run_len.length_words();
+        let piece = if words <= SMALL_WORDS {
+          run_len
+        } else if Length::of((words - SMALL_WORDS) as UWord) >= MIN_SMALL_LEN {
+          Length::of(SMALL_WORDS as UWord)
+        } else {
+          Length::of(SMALL_WORDS as UWord) - MIN_SMALL_LEN
+        };
+        self.push_small(run, piece);
+        run = run + piece;
+        run_len = run_len - piece;
+        pieces += 1;
+      }
+    }
+    !freed.is_empty() || self.end != old_end || pieces < blocks.len()
+  }
+
+  #[inline(always)]
+  fn push_small(&mut self, addr: Addr, len: Length) {
+    let next = self.small[len.length_words()];
+    self.write_payload(addr, next);
+    self.small[len.length_words()] = addr;
+  }
+
+  fn alloc_large(&mut self, len: Length) -> Addr {
+    let addr = self.try_alloc_large(len);
+    if !addr.is_null() {
+      return addr;
+    }
+    if self.reclaim_small() {
+      let addr = self.try_alloc_large(len);
+      if !addr.is_null() {
+        return addr;
+      }
+    }
+    oom!();
+  }
+
+  fn try_alloc_large(&mut self, len: Length) -> Addr {
+    let size = len + TAGS_LEN;
+    let bin = Self::bin(size);
+    let mut block = self.large[bin];
+    while !block.is_null() && self.tag(block).0 < size {
+      block = self.read_payload(block + Length::of(1) + LINK_LEN);
+    }
+    if block.is_null() {
+      block = self.large[bin + 1..]
+        .iter()
+        .copied()
+        .find(|block| !block.is_null())
+        .unwrap_or(Addr::NULL);
+    }
+    if block.is_null() {
+      if self.top < self.end + size {
+        return Addr::NULL;
+      }
+      block = self.top - size;
+      self.top = block;
+      self.set_tags(block, size, true);
+      return block + Length::of(1);
+    }
+    let (block_size, _) = self.tag(block);
+    self.unlink(block, block_size);
+    let rest = block_size - size;
+    if rest >= MIN_LARGE_LEN {
+      self.set_tags(block, size, true);
+      self.set_tags(block + size, rest, false);
+      self.insert(block + size, rest);
+    } else {
+      self.set_tags(block, block_size, true);
+    }
+    block + Length::of(1)
+  }
+
+  fn free_large(&mut self, mut block: Addr) {
+    let (mut size, used) = self.tag(block);
+    debug_assert!(used);
+    let next = block + size;
+    if next < self.buffer_bounds().end {
+      let (next_size, next_used) = self.tag(next);
+      if !next_used {
+        self.unlink(next, next_size);
+        size = size + next_size;
+      }
+    }
+    if block > self.top {
+      let (prev_size, prev_used) = self.tag(block - Length::of(1));
+      if !prev_used {
+        block = block - prev_size;
+        self.unlink(block, prev_size);
+        size = size + prev_size;
+      }
+    }
+    if block == self.top {
+      self.top = block + size;
+      return;
+    }
+    self.set_tags(block, size, false);
+    self.insert(block, size);
+  }
+
+  #[inline(always)]
+  fn bin(size: Length) -> usize {
+    (usize::BITS - 1 - size.length_words().leading_zeros()) as usize
+  }
+
+  #[inline(always)]
+  fn tag(&self, tag: Addr) -> (Length, bool) {
+    let word = self.word(tag);
+    (
+      Length {
+        length_bytes: word.0 & !1,
+      },
+      word.0 & 1 != 0,
+    )
+  }
+
+  #[inline(always)]
+  fn set_tags(&mut self, block: Addr, size: Length, used: bool) {
+    let tag = Word(size.length_bytes | used as UWord);
+    *self.word_mut(block) = tag;
+    *self.word_mut(block + size - Length::of(1)) = tag;
+  }
+
+  fn insert(&mut self, block: Addr, size: Length) {
+    let bin = Self::bin(size);
+    let next = self.large[bin];
+    self.write_payload(block + Length::of(1), Addr::NULL);
+    self.write_payload(block + Length::of(1) + LINK_LEN, next);
+    if !next.is_null() {
+      self.write_payload(next + Length::of(1), block);
+    }
+    self.large[bin] = block;
+  }
+
+  fn unlink(&mut self, block: Addr, size: Length) {
+    let prev: Addr = self.read_payload(block + Length::of(1));
+    let next: Addr = self.read_payload(block + Length::of(1) + LINK_LEN);
+    if prev.is_null() {
+      self.large[Self::bin(size)] = next;
+    } else {
+      self.write_payload(prev + Length::of(1) + LINK_LEN, next);
+    }
+    if !next.is_null() {
+      self.write_payload(next + Length::of(1), prev);
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn rng(mut seed: u64) -> impl FnMut() -> usize {
+    move || {
+      seed = seed
+        .wrapping_mul(6364136223846793005)
+        .wrapping_add(1442695040888963407);
+      (seed >> 33) as usize
+    }
+  }
+
+  fn fill(alloc: &mut impl Alloc, addr: Addr, len: Length, tag: usize) {
+    alloc.slice_mut(addr, len).fill(Word((tag as UWord) << 2));
+  }
+
+  fn check(alloc: &impl Alloc, addr: Addr, len: Length, tag: usize) {
+    for i in 0..len.length_words() {
+      assert_eq!(
+        alloc.word(addr + Delta::of(i as IWord)).0,
+        (tag as UWord) << 2
+      );
+    }
+  }
+
+  // Runs random mixed-size traffic, including 256+ word agents, keeping
+  // `occupancy` percent of the buffer live.
+  fn churn(alloc: &mut impl Alloc, words: usize, occupancy: usize, steps: usize) {
+    let mut rnd = rng(7);
+    let mut live = vec![];
+    let mut live_words = 0;
+    for step in 0..steps {
+      if rnd() & 1 == 0 && !live.is_empty() {
+        let (addr, len, tag) = live.swap_remove(rnd() % live.len());
+        check(alloc, addr, len, tag);
+        alloc.free(addr, len);
+        live_words -= len.length_words();
+        continue;
+      }
+      let len = Length::of(match rnd() % 20 {
+        0 => 256 + rnd() % 512,
+        1..=3 => 5 + rnd() % 60,
+        _ => 2 + rnd() % 3,
+      } as UWord);
+      if (live_words + len.length_words()) * 100 > words * occupancy {
+        continue;
+      }
+      let addr = alloc.alloc(len);
+      fill(alloc, addr, len, step);
+      live.push((addr, len, step));
+      live_words += len.length_words();
+    }
+    for (addr, len, tag) in live {
+      check(alloc, addr, len, tag);
+      alloc.free(addr, len);
+    }
+  }
+
+  #[test]
+  fn mixed_sizes_at_high_occupancy() {
+    let words = 1 << 16;
+    let mut alloc = ClassAlloc::new(ArrayBuffer::new(words));
+    churn(&mut alloc, words, 75, 200_000);
+  }
+
+  #[test]
+  fn reclaims_everything_once_freed() {
+    let words = 1 << 16;
+    let mut alloc = ClassAlloc::new(ArrayBuffer::new(words));
+    churn(&mut alloc, words, 75, 50_000);
+    let len = Length::of((words - 2) as UWord);
+    let addr = alloc.alloc(len);
+    fill(&mut alloc, addr, len, 1);
+    alloc.free(addr, len);
+    assert!(alloc.top == alloc.buffer_bounds().end);
+  }
+
+  #[test]
+  fn splits_larger_small_blocks() {
+    let mut alloc = ClassAlloc::new(ArrayBuffer::new(1 << 10));
+    let fours = (0..256)
+      .map(|_| alloc.alloc(Length::of(4)))
+      .collect::<Vec<_>>();
+    for &addr in &fours {
+      alloc.free(addr, Length::of(4));
+    }
+    let mut twos = (0..512)
+      .map(|_| alloc.alloc(Length::of(2)))
+      .collect::<Vec<_>>();
+    twos.sort();
+    twos.dedup();
+    assert_eq!(twos.len(), 512);
+  }
+
+  #[test]
+  fn serves_small_blocks_from_the_large_region() {
+    let mut alloc = ClassAlloc::new(ArrayBuffer::new(1 << 10));
+    let big = alloc.alloc(Length::of((1 << 10) - 2));
+    alloc.free(big, Length::of((1 << 10) - 2));
+    let small = (0..(1 << 8))
+      .map(|_| alloc.alloc(Length::of(3)))
+      .collect::<Vec<_>>();
+    for (i, &addr) in small.iter().enumerate() {
+      fill(&mut alloc, addr, Length::of(3), i);
+    }
+    for (i, &addr) in small.iter().enumerate() {
+      check(&alloc, addr, Length::of(3), i);
+    }
+  }
+
+  #[test]
+  fn coalesces_large_blocks() {
+    let mut alloc = ClassAlloc::new(ArrayBuffer::new(1 << 12));
+    let blocks = (0..3)
+      .map(|_| alloc.alloc(Length::of(300)))
+      .collect::<Vec<_>>();
+    alloc.free(blocks[1], Length::of(300));
+    alloc.free(blocks[0], Length::of(300));
+    let merged = alloc.alloc(Length::of(600));
+    assert!(merged == blocks[1]);
+    alloc.free(merged, Length::of(600));
+    alloc.free(blocks[2], Length::of(300));
+    assert!(alloc.top == alloc.buffer_bounds().end);
+  }
+
+  #[test]
+  fn link_alloc_handles_large_agents() {
+    let words = 1 << 16;
+    let mut alloc = LinkAlloc::new(ArrayBuffer::new(words));
+    let a = alloc.alloc(Length::of(300));
+    alloc.free(a, Length::of(300));
+    assert!(alloc.alloc(Length::of(300)) == a);
+  }
+}
@@ -23,6 +23,7 @@ impl<B: BufferMut> DelegateBufferMut for LinkAlloc<B> {
 }
 
 impl<B: BufferMut> Alloc for LinkAlloc<B> {
+  const PARTIAL_FREE: bool = true;
   #[inline(always)]
   fn alloc(&mut self, len: Length) -> Addr {
     let alloc = self.get_alloc(len);
@@ -40,6 +41,26 @@ impl<B: BufferMut> Alloc for LinkAlloc<B> {
     }
   }
 
+  #[inline(always)]
+  fn alloc_near(&mut self, near: Addr, len: Length) -> Addr {
+    let head = self.get_alloc(len);
+    if head.is_null() || head.near(near, NEAR_LEN) {
+      return self.alloc(len);
+    }
+    let addr = unlink_near(&mut self.buffer, head, near);
+    if addr.is_null() {
+      return self.alloc(len);
+    }
+    addr
+  }
+
+  // The parts of a block are only ever recycled separately, so a block is
+  // only worth carving while none of them has a freed agent to reuse.
+  #[inline(always)]
+  fn prefers_block(&self, parts: &[Length]) -> bool {
+    parts.iter().all(|&len| self.get_alloc(len).is_null())
+  }
+
   #[inline(always)]
   fn free(&mut self, addr: Addr, len: Length) {
     debug_assert!(len >= Length::of(2));
@@ -64,21 +85,22 @@ impl<B: BufferMut> LinkAlloc<B> {
     }
   }
 
+  // Agents of 256+ words grow the table on their first free; until then
+  // their list is empty.
   #[inline(always)]
   fn get_alloc(&self, len: Length) -> Addr {
-    if cfg!(feature = "unsafe") {
-      *unsafe { self.allocs.get_unchecked(len.length_words()) }
-    } else {
-      self.allocs[len.length_words()]
+    match self.allocs.get(len.length_words()) {
+      Some(alloc) => *alloc,
+      None => Addr::NULL,
     }
   }
 
   #[inline(always)]
   fn get_alloc_mut(&mut self, len: Length) -> &mut Addr {
-    if cfg!(feature = "unsafe") {
-      unsafe { self.allocs.get_unchecked_mut(len.length_words()) }
-    } else {
-      &mut self.allocs[len.length_words()]
+    let i = len.length_words();
+    if i >= self.allocs.len() {
+      self.allocs.resize(i + 1, Addr::NULL);
     }
+    &mut self.allocs[i]
   }
 }
@@ -24,6 +24,7 @@ impl<B: BufferMut> DelegateBufferMut for RingAlloc<B> {
 }
 
 impl<B: BufferMut> Alloc for RingAlloc<B> {
+  const PARTIAL_FREE: bool = true;
   fn alloc(&mut self, len: Length) -> Addr {
     let initial = self.alloc;
     loop {
@@ -67,6 +68,18 @@ impl<B: BufferMut> Alloc for RingAlloc<B> {
     }
   }
 
+  fn alloc_near(&mut self, near: Addr, len: Length) -> Addr {
+    let mut addr = self.alloc;
+    for _ in 0..NEAR_PROBES {
+      if addr.near(near, NEAR_LEN) && self.word(addr).as_null_len() >= len {
+        self.alloc = addr;
+        break;
+      }
+      addr = self.dll_read_prev_next(addr).1;
+    }
+    self.alloc(len)
+  }
+
   fn free(&mut self, addr: Addr, len: Length) {
     debug_assert!(len.non_zero());
     self.assert_valid(addr, len);
@@ -0,0 +1,62 @@
+use internets_nets::*;
+use std::time::Instant;
+
+const WORDS: usize = 1 << 22;
+const LIVE_WORDS: usize = 1 << 21;
+
+// Random mixed-size traffic: mostly 2-4 word agents, some medium ones and a
+// few of 256+ words.
+fn churn(alloc: &mut impl Alloc, steps: usize) -> usize {
+  let mut seed = 7u64;
+  let mut rnd = move || {
+    seed = seed
+      .wrapping_mul(6364136223846793005)
+      .wrapping_add(1442695040888963407);
+    (seed >> 33) as usize
+  };
+  let mut live = vec![];
+  let mut live_words = 0;
+  let mut checksum = 0;
+  for step in 0..steps {
+    if rnd() & 1 == 0 && !live.is_empty() {
+      let (addr, len): (Addr, Length) = live.swap_remove(rnd() % live.len());
+      checksum += alloc.word(addr).0 as usize;
+      alloc.free(addr, len);
+      live_words -= len.length_words();
+      continue;
+    }
+    let len = Length::of(match rnd() % 64 {
+      0 => 256 + rnd() % 512,
+      1..=8 => 5 + rnd() % 60,
+      _ => 2 + rnd() % 3,
+    } as UWord);
+    if live_words + len.length_words() > LIVE_WORDS {
+      continue;
+    }
+    let addr = alloc.alloc(len);
+    alloc.slice_mut(addr, len).fill(Word((step as UWord) << 2));
+    live.push((addr, len));
+    live_words += len.length_words();
+  }
+  checksum
+}
+
+fn bench(name: &str, alloc: &mut impl Alloc, steps: usize) {
+  let start = Instant::now();
+  let checksum = churn(alloc, steps);
+  println!("{name}: {:?} ({checksum})", start.elapsed());
+}
+
+fn main() {
+  let args: Vec<_> = std::env::args().collect();
+  let steps = args
+    .get(1)
+    .map(|x| x.parse().unwrap())
+    .unwrap_or(10_000_000);
+  bench("link", &mut LinkAlloc::new(ArrayBuffer::new(WORDS)), steps);
+  bench(
+    "class",
+    &mut ClassAlloc::new(ArrayBuffer::new(WORDS)),
+    steps,
+  );
+}
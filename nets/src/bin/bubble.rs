@@ -62,6 +62,7 @@ impl Bubble {
   pub const SUM: Kind = Kind::of(23);
 }
 
+// The chunks below lay out `u64` payloads by hand as two words.
 impl<N: Net> Interactions<N> for Bubble {
   #[inline(always)]
   fn reduce(
@@ -3,67 +3,41 @@ use internets_nets::*;
 mod libs;
 
 interactions! {
-  use libs::std;
+  use libs::list::{Cons, Nil};
+  use libs::print::Print;
+  use libs::std::*;
 
-  struct Nil(+List);
-  struct Cons(+List, -U64, -List);
-
-  struct Sort(-List, +List);
-  struct Insert(-List, -U64, +List);
-  struct SwapIf(-Bool, -U64, -U64, -List, +List);
-
-  struct Rnd(-U64, -U64, +List);
-  struct Sum(-List, +U64);
-
-  impl Sort(_, o) for Nil(_) {
-    Nil(o)
-  }
-  impl Sort(_, o) for Cons(_, x, xs) {
-    Sort(xs, s)
-    Insert(s, x, o)
+  def Sort(List<U64>) -> List<U64> {
+    Sort(Nil<U64>) = Nil<U64>;
+    Sort(Cons<U64>(x, xs)) = Insert(x, Sort(xs));
   }
 
-  impl Insert(_, v, o) for Nil(_) {
-    Cons(o, v, n)
-    Nil(n)
-  }
-  impl Insert(_, v, o) for Cons(_, x, xs) {
-    Clone(v, v0, v1)
-    Clone(x, x0, x1)
-    SwapIf(c, v0, x0, xs, o)
-    Gt(x1, v1, c)
+  def Insert(U64, List<U64>) -> List<U64> {
+    Insert(v, Nil<U64>) = Cons<U64>(v, Nil<U64>);
+    Insert(v, Cons<U64>(x, xs)) = SwapIf(Gt(x, v), v, x, xs);
   }
 
-  impl SwapIf(_, v, x, xs, o) for False(_) {
-    Cons(o, v, a)
-    Cons(a, x, xs)
-  }
-  impl SwapIf(_, v, x, xs, o) for True(_) {
-    Cons(o, x, a)
-    Insert(xs, v, a)
+  def SwapIf(Bool, U64, U64, List<U64>) -> List<U64> {
+    SwapIf(False, v, x, xs) = Cons<U64>(v, Cons<U64>(x, xs));
+    SwapIf(True, v, x, xs) = Cons<U64>(x, Insert(v, xs));
   }
 
+  struct Rnd(-U64, -U64, +List<U64>);
+
   impl Rnd(_, s, o) for U64(_, $0) {
     Erase(s)
-    Nil(o)
+    Nil<U64>(o)
   }
   impl Rnd(_, s, o) for U64(_, $n) {
-    Clone(s, s0, s1)
-    MulX(s1, s2, $1664525)
-    AddX(s2, s3, $1013904223)
-    ModX(s3, s4, $4294967296)
-    Cons(o, s0, o2)
-    Rnd(n1, s4, o2)
-    U64(n, $n)
-    AddX(n, n1, $u64::MAX)
+    Cons<U64>(o, s, l)
+    Rnd(m, t, l)
+    m = $(n - 1)
+    t = (s * $1664525 + $1013904223) % $4294967296
   }
 
-  impl Sum(_, o) for Nil(_) {
-    U64(o, $0)
-  }
-  impl Sum(_, o) for Cons(_, x, l) {
-    Add(x, y, o)
-    Sum(l, y)
+  def Sum(List<U64>) -> U64 {
+    Sum(Nil<U64>) = U64(_, $0);
+    Sum(Cons<U64>(x, xs)) = Add(x, Sum(xs));
   }
 
   fn _main(n: $u64) {
@@ -3,9 +3,10 @@ use internets_nets::*;
 mod libs;
 
 interactions! {
-  use libs::nat;
-  use libs::std;
-  use libs::u64_nat;
+  use libs::nat::{self, Succ, Zero};
+  use libs::print::Print;
+  use libs::std::U64;
+  use libs::u64_nat::{NatToU64, U64ToNat};
 
   struct Fib(-Nat, +Nat);
   struct FibS(-Nat, +Nat);
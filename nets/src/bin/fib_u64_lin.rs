@@ -3,7 +3,8 @@ use internets_nets::*;
 mod libs;
 
 interactions! {
-  use libs::std;
+  use libs::print::Print;
+  use libs::std::{Add, Clone, Erase, U64};
 
   struct Fib {
     n: -U64,
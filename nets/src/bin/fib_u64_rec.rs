@@ -3,7 +3,8 @@ use internets_nets::*;
 mod libs;
 
 interactions! {
-  use libs::std;
+  use libs::print::Print;
+  use libs::std::{Add, U64};
 
   struct Fib(-U64, +U64);
 
@@ -29,7 +30,7 @@ fn main() {
   let args: Vec<_> = std::env::args().collect();
   let n = args.get(1).map(|x| x.parse().unwrap()).unwrap_or(32);
   let mut stats = Stats::default();
-  let mut net = BasicNet::new(LinkAlloc::new(ArrayBuffer::new(1 << 28)));
+  let mut net = BasicNet::new(LinkAlloc::new(ArrayBuffer::new(1 << 28))).with_immediate_depth(64);
   _main(n).construct(&mut net, &Interactions);
   reduce_with_stats(&mut net, &Interactions, &mut stats);
   println!("{stats}");
@@ -0,0 +1,49 @@
+use internets_nets::*;
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+mod libs;
+
+interactions! {
+  use libs::print::Print;
+  use libs::std::U64;
+
+  extern struct HashU64(-U64, +U64);
+  extern struct Square(-U64, +U64);
+
+  fn _main(n: $u64) {
+    U64(x, $n)
+    Square(x, y)
+    HashU64(y, h)
+    Print(h)
+  }
+}
+
+#[derive(Debug)]
+struct Env(HostFns);
+
+impl Host for Env {
+  fn host_fns(&mut self) -> &mut HostFns {
+    &mut self.0
+  }
+}
+
+impl libs::print::Output for Env {
+  fn print(&mut self, value: u64) {
+    println!("{}", value);
+  }
+}
+
+fn main() {
+  let args: Vec<_> = std::env::args().collect();
+  let n = args.get(1).map(|x| x.parse().unwrap()).unwrap_or(12);
+  let fns = HostFns::new()
+    .bind::<Square<_>>(|x| x.wrapping_mul(x))
+    .bind::<HashU64<_>>(|x| {
+      let mut hasher = DefaultHasher::new();
+      x.hash(&mut hasher);
+      hasher.finish()
+    });
+  let mut net = BasicNet::with_effects(LinkAlloc::new(ArrayBuffer::new(1 << 16)), Env(fns));
+  _main(n).construct(&mut net, &Interactions);
+  reduce_with_stats(&mut net, &Interactions, &mut Stats::default());
+}
@@ -0,0 +1,125 @@
+use internets_nets::interactions;
+use std::io::{BufRead, Write};
+
+interactions! {
+  use super::std::U64;
+
+  pub effect Io {
+    fn read_line(&mut self) -> String;
+    fn write(&mut self, text: &str);
+    fn read_file(&mut self, path: &str) -> String;
+    fn write_file(&mut self, path: &str, contents: &str);
+  }
+
+  #[derive(Erase, Clone)]
+  #[erase(Str = EraseStr)]
+  #[clone(Str = CloneStr)]
+  pub struct Str(+Str, $String);
+  pub struct EraseStr(-Str);
+  pub struct CloneStr(-Str, +Str, +Str);
+
+  pub struct World(+World);
+  pub struct Exit(-World);
+
+  pub struct ReadLine(-World, +World, +Str);
+
+  impl ReadLine(_, w, s) for World(_) {
+    let line = effects.read_line();
+    World(w)
+    Str(s, $line)
+  }
+
+  // What `ReadU64` read: the number, or the line when it isn't one.
+  pub enum Number {
+    Parsed(-U64),
+    NotANumber(-Str),
+  }
+
+  pub struct ReadU64(-World, +World, +Number);
+  struct ReadU64X(+Number, $Option<u64>, $String);
+
+  impl ReadU64(_, w, n) for World(_) {
+    let line = effects.read_line();
+    let value = line.trim().parse::<u64>().ok();
+    World(w)
+    ReadU64X(n, $value, $line)
+  }
+  impl ReadU64X(o, $Some(x), $_) for _ {
+    Parsed(o, U64(_, $x))
+  }
+  impl ReadU64X(o, $None, $line) for _ {
+    NotANumber(o, Str(_, $line))
+  }
+
+  pub struct Print(-World, -U64, +World);
+  struct PrintX(-U64, +World);
+
+  impl Print(_, x, w) for World(_) { PrintX(x, w) }
+  impl PrintX(_, w) for U64(_, $n) {
+    {
+      effects.write(&format!("{}\n", n));
+    }
+    World(w)
+  }
+
+  pub struct PrintStr(-World, -Str, +World);
+  struct PrintStrX(-Str, +World);
+
+  impl PrintStr(_, s, w) for World(_) { PrintStrX(s, w) }
+  impl PrintStrX(_, w) for Str(_, $s) {
+    {
+      effects.write(&s);
+    }
+    World(w)
+  }
+
+  pub struct ReadFile(-World, -Str, +World, +Str);
+  struct ReadFileX(-Str, +World, +Str);
+
+  impl ReadFile(_, p, w, s) for World(_) { ReadFileX(p, w, s) }
+  impl ReadFileX(_, w, s) for Str(_, $path) {
+    let contents = effects.read_file(&path);
+    World(w)
+    Str(s, $contents)
+  }
+
+  pub struct WriteFile(-World, -Str, -Str, +World);
+  struct WriteFileX(-Str, -Str, +World);
+  struct WriteFileXX(-Str, +World, $String);
+
+  impl WriteFile(_, p, c, w) for World(_) { WriteFileX(p, c, w) }
+  impl WriteFileX(_, c, w) for Str(_, $path) { WriteFileXX(c, w, $path) }
+  impl WriteFileXX(_, w, $path) for Str(_, $contents) {
+    {
+      effects.write_file(&path, &contents);
+    }
+    World(w)
+  }
+}
+
+#[derive(Debug)]
+#[allow(dead_code)]
+pub struct StdIo;
+
+impl Io for StdIo {
+  fn read_line(&mut self) -> String {
+    let mut line = String::new();
+    std::io::stdin().lock().read_line(&mut line).unwrap();
+    line.truncate(line.trim_end_matches(['\n', '\r']).len());
+    line
+  }
+
+  fn write(&mut self, text: &str) {
+    let mut stdout = std::io::stdout().lock();
+    stdout.write_all(text.as_bytes()).unwrap();
+    stdout.flush().unwrap();
+  }
+
+  fn read_file(&mut self, path: &str) -> String {
+    std::fs::read_to_string(path).unwrap_or_else(|err| panic!("{}: {}", path, err))
+  }
+
+  fn write_file(&mut self, path: &str, contents: &str) {
+    std::fs::write(path, contents).unwrap_or_else(|err| panic!("{}: {}", path, err))
+  }
+}
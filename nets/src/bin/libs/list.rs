@@ -0,0 +1,11 @@
+use internets_nets::interactions;
+
+interactions! {
+  use super::std::{Clone, Erase};
+
+  #[derive(Erase, Clone)]
+  pub enum List<T> {
+    Nil,
+    Cons(-T, -List<T>),
+  }
+}
@@ -1,3 +1,8 @@
+pub mod io;
+pub mod list;
 pub mod nat;
+pub mod print;
 pub mod std;
+pub mod stream;
+pub mod time;
 pub mod u64_nat;
@@ -0,0 +1,29 @@
+use internets_nets::interactions;
+
+interactions! {
+  use super::std::U64;
+
+  pub effect Output {
+    fn print(&mut self, value: u64);
+  }
+
+  pub struct Print(-U64);
+
+  impl Print(_) for U64(_, $n) {
+    {
+      effects.print(n);
+    }
+  }
+}
+
+impl Output for () {
+  fn print(&mut self, value: u64) {
+    println!("{}", value);
+  }
+}
+
+impl Output for Vec<u64> {
+  fn print(&mut self, value: u64) {
+    self.push(value);
+  }
+}
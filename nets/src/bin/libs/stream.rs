@@ -0,0 +1,104 @@
+use internets_nets::interactions;
+use std::{collections::HashMap, fmt::Debug, sync::mpsc::Sender};
+
+interactions! {
+  use super::std::U64;
+
+  pub effect Streams {
+    fn pull(&mut self, source: &str) -> Option<u64>;
+    fn push(&mut self, sink: &str, value: u64);
+    fn close(&mut self, sink: &str);
+  }
+
+  pub enum Stream {
+    End,
+    Next(-U64, -Stream),
+  }
+
+  pub struct Source(+Stream, $&'static str);
+  struct Pulled(+Stream, $Option<u64>, $&'static str);
+
+  impl Source(o, $name) for _ {
+    let item = effects.pull(name);
+    Pulled(o, $item, $name)
+  }
+  impl Pulled(o, $Some(x), $name) for _ {
+    Next(o, U64(_, $x), Source(_, $name))
+  }
+  impl Pulled(o, $None, $_) for _ {
+    End(o)
+  }
+
+  pub struct Sink(-Stream, $&'static str);
+  struct SinkNext(-U64, -Stream, $&'static str);
+
+  impl Sink(_, $name) for Next(_, x, xs) { SinkNext(x, xs, $name) }
+  impl Sink(_, $name) for End(_) {
+    {
+      effects.close(name);
+    }
+  }
+  impl SinkNext(_, xs, $name) for U64(_, $value) {
+    {
+      effects.push(name, value);
+    }
+    Sink(xs, $name)
+  }
+}
+
+#[derive(Default)]
+#[allow(dead_code)]
+pub struct Channels {
+  sources: HashMap<&'static str, Box<dyn Iterator<Item = u64>>>,
+  sinks: HashMap<&'static str, Sender<u64>>,
+}
+
+#[allow(dead_code)]
+impl Channels {
+  pub fn new() -> Self {
+    Channels::default()
+  }
+
+  pub fn source(
+    mut self,
+    name: &'static str,
+    iter: impl IntoIterator<Item = u64> + 'static,
+  ) -> Self {
+    self.sources.insert(name, Box::new(iter.into_iter()));
+    self
+  }
+
+  pub fn sink(mut self, name: &'static str, sender: Sender<u64>) -> Self {
+    self.sinks.insert(name, sender);
+    self
+  }
+}
+
+impl Streams for Channels {
+  fn pull(&mut self, source: &str) -> Option<u64> {
+    let Some(iter) = self.sources.get_mut(source) else {
+      panic!("no source bound to `{}`", source);
+    };
+    iter.next()
+  }
+
+  fn push(&mut self, sink: &str, value: u64) {
+    let Some(sender) = self.sinks.get(sink) else {
+      panic!("no sink bound to `{}`", sink);
+    };
+    let _ = sender.send(value);
+  }
+
+  fn close(&mut self, sink: &str) {
+    self.sinks.remove(sink);
+  }
+}
+
+impl Debug for Channels {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("Channels")
+      .field("sources", &self.sources.keys())
+      .field("sinks", &self.sinks.keys())
+      .finish()
+  }
+}
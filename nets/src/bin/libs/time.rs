@@ -0,0 +1,82 @@
+use internets_nets::interactions;
+use std::{
+  future::Future,
+  pin::Pin,
+  sync::{Arc, Mutex},
+  task::{Context, Poll, Waker},
+  time::Duration,
+};
+
+interactions! {
+  use super::std::U64;
+
+  pub effect Timer {
+    fn sleep(&mut self, ms: u64) -> Pin<Box<dyn Future<Output = ()>>>;
+  }
+
+  pub struct Sleep(-U64, +U64);
+
+  impl Sleep(_, o) for U64(_, $ms) {
+    let () = effects.sleep(ms).await;
+    U64(o, $ms)
+  }
+}
+
+#[derive(Debug)]
+#[allow(dead_code)]
+pub struct Clock;
+
+impl Timer for Clock {
+  fn sleep(&mut self, ms: u64) -> Pin<Box<dyn Future<Output = ()>>> {
+    Box::pin(Timeout::new(Duration::from_millis(ms)))
+  }
+}
+
+impl super::print::Output for Clock {
+  fn print(&mut self, value: u64) {
+    println!("{}", value);
+  }
+}
+
+#[derive(Default)]
+#[allow(dead_code)]
+struct TimeoutState {
+  done: bool,
+  waker: Option<Waker>,
+}
+
+#[allow(dead_code)]
+pub struct Timeout {
+  state: Arc<Mutex<TimeoutState>>,
+}
+
+#[allow(dead_code)]
+impl Timeout {
+  pub fn new(duration: Duration) -> Self {
+    let state = Arc::new(Mutex::new(TimeoutState::default()));
+    let shared = state.clone();
+    std::thread::spawn(move || {
+      std::thread::sleep(duration);
+      let mut state = shared.lock().unwrap();
+      state.done = true;
+      if let Some(waker) = state.waker.take() {
+        waker.wake();
+      }
+    });
+    Timeout { state }
+  }
+}
+
+impl Future for Timeout {
+  type Output = ();
+
+  fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<()> {
+    let mut state = self.state.lock().unwrap();
+    if state.done {
+      Poll::Ready(())
+    } else {
+      state.waker = Some(cx.waker().clone());
+      Poll::Pending
+    }
+  }
+}
@@ -1,8 +1,8 @@
 use internets_nets::interactions;
 
 interactions! {
-  use super::std;
-  use super::nat;
+  use super::std::U64;
+  use super::nat::{Succ, Zero};
 
   pub struct NatToU64(-Nat, +U64);
   struct _NatToU64(-Nat, +U64, $u64);
@@ -138,8 +138,7 @@ impl<N: Net> Interactions<N> for Nat {
   }
 }
 
-fn main() {
-  let mut stats = Stats::default();
+pub fn run(stats: &mut Stats) {
   let mut buffer = ArrayBuffer::new(1 << 19);
   for _ in 0..1000 {
     let mut net = BasicNet::new(RingAlloc::new(buffer.as_mut()));
@@ -178,7 +177,13 @@ fn main() {
       LinkHalf::Port(base + Delta::of(22), PortMode::Principal),
       LinkHalf::Port(base + Delta::of(25), PortMode::Principal),
     );
-    reduce_with_stats(&mut net, &Nat, &mut stats);
+    reduce_with_stats(&mut net, &Nat, stats);
   }
+}
+
+#[allow(dead_code)]
+fn main() {
+  let mut stats = Stats::default();
+  run(&mut stats);
   println!("{stats}");
 }
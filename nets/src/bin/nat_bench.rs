@@ -0,0 +1,27 @@
+use internets_nets::*;
+use std::time::Duration;
+
+#[path = "nat_gen.rs"]
+mod generated;
+#[path = "nat.rs"]
+mod hand;
+
+// Alternates the hand-written and generated nets so both see the same machine
+// state, and keeps the fastest of each.
+fn main() {
+  let args: Vec<_> = std::env::args().collect();
+  let rounds = args.get(1).map(|x| x.parse().unwrap()).unwrap_or(5);
+  let mut best = [Duration::MAX; 2];
+  let mut ops = [0; 2];
+  for _ in 0..rounds {
+    for (i, run) in [hand::run, generated::run].into_iter().enumerate() {
+      let mut stats = Stats::default();
+      run(&mut stats);
+      best[i] = best[i].min(stats.elapsed);
+      ops[i] = stats.ops;
+    }
+  }
+  for (name, (ops, elapsed)) in ["nat", "nat_gen"].iter().zip(ops.into_iter().zip(best)) {
+    println!("{name}: {}", Stats { ops, elapsed });
+  }
+}
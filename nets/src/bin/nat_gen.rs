@@ -3,7 +3,7 @@ use internets_nets::interactions;
 mod libs;
 
 interactions! {
-  use libs::nat;
+  use libs::nat::*;
 
   fn square(i: -U64, o: +U64) {
     Clone(i, i0, i1)
@@ -21,17 +21,22 @@ interactions! {
   }
 }
 
-fn main() {
+pub fn run(stats: &mut internets_nets::Stats) {
   use internets_nets::*;
-  let mut stats = Stats::default();
-  let mut buffer = ArrayBuffer::new(1 << 18);
+  let mut buffer = ArrayBuffer::new(1 << 19);
   for _ in 0..1000 {
-    let mut net = BasicNet::new(LinkAlloc::new(buffer.as_mut()));
+    let mut net = BasicNet::new(RingAlloc::new(buffer.as_mut())).with_immediate_depth(64);
     let free = net.alloc_write(&[Word::NULL]);
     let mut free_0 = LinkHalf::Null;
     _main(&mut free_0).construct(&mut net, &Interactions);
     net.link(free_0, LinkHalf::Port(free, PortMode::Auxiliary));
-    reduce_with_stats(&mut net, &Interactions, &mut stats);
+    reduce_with_stats(&mut net, &Interactions, stats);
   }
+}
+
+#[allow(dead_code)]
+fn main() {
+  let mut stats = internets_nets::Stats::default();
+  run(&mut stats);
   eprintln!("{stats}");
 }
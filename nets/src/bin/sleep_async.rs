@@ -0,0 +1,56 @@
+use internets_nets::*;
+use std::{
+  future::Future,
+  pin::pin,
+  sync::Arc,
+  task::{Context, Poll, Wake, Waker},
+  thread::{self, Thread},
+  time::Instant,
+};
+
+mod libs;
+use libs::time::Clock;
+
+interactions! {
+  use libs::print::Print;
+  use libs::std::{Add, U64};
+  use libs::time::Sleep;
+
+  fn _main() {
+    Sleep(U64(_, $300), a)
+    Sleep(U64(_, $200), b)
+    Sleep(U64(_, $100), c)
+    Add(a, b, ab)
+    Add(ab, c, abc)
+    Print(abc)
+  }
+}
+
+struct ThreadWaker(Thread);
+
+impl Wake for ThreadWaker {
+  fn wake(self: Arc<Self>) {
+    self.0.unpark();
+  }
+}
+
+fn block_on<F: Future>(future: F) -> F::Output {
+  let mut future = pin!(future);
+  let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+  let mut cx = Context::from_waker(&waker);
+  loop {
+    if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+      return output;
+    }
+    thread::park();
+  }
+}
+
+fn main() {
+  let start = Instant::now();
+  let mut stats = Stats::default();
+  let mut net = BasicNet::with_effects(LinkAlloc::new(ArrayBuffer::new(1 << 16)), Clock);
+  _main().construct(&mut net, &Interactions);
+  block_on(reduce_async(&mut net, &Interactions, &mut stats));
+  eprintln!("{stats} in {:?} total", start.elapsed());
+}
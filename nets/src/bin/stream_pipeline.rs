@@ -0,0 +1,45 @@
+use internets_nets::*;
+use std::{sync::mpsc, thread};
+
+mod libs;
+use libs::stream::Channels;
+
+interactions! {
+  use libs::std::{Mul, U64};
+  use libs::stream::{End, Next, Sink, Source};
+
+  struct Triple(-Stream, +Stream);
+
+  impl Triple(_, o) for End(_) { End(o) }
+  impl Triple(_, o) for Next(_, x, xs) {
+    Triple(xs, ys)
+    Mul(x, U64(_, $3), y)
+    Next(o, y, ys)
+  }
+
+  fn _main() {
+    Source(s, $"numbers")
+    Triple(s, t)
+    Sink(t, $"tripled")
+  }
+}
+
+fn main() {
+  let args: Vec<_> = std::env::args().collect();
+  let n = args.get(1).map(|x| x.parse().unwrap()).unwrap_or(10);
+  let (sender, receiver) = mpsc::channel();
+  let worker = thread::spawn(move || {
+    let channels = Channels::new()
+      .source("numbers", 1..=n)
+      .sink("tripled", sender);
+    let mut stats = Stats::default();
+    let mut net = BasicNet::with_effects(LinkAlloc::new(ArrayBuffer::new(1 << 16)), channels);
+    _main().construct(&mut net, &Interactions);
+    reduce_with_stats(&mut net, &Interactions, &mut stats);
+    stats
+  });
+  for value in receiver {
+    println!("{value}");
+  }
+  eprintln!("{}", worker.join().unwrap());
+}
@@ -0,0 +1,35 @@
+use internets_nets::*;
+
+mod libs;
+use libs::io::StdIo;
+
+interactions! {
+  use libs::std::{Add, U64};
+  use libs::io::{EraseStr, Exit, NotANumber, Parsed, Print, PrintStr, ReadU64, Str, World};
+
+  // Lines that aren't numbers count as zero.
+  struct OrZero(-Number, +U64);
+
+  impl OrZero(_, o) for Parsed(_, o) {}
+  impl OrZero(_, o) for NotANumber(_, s) {
+    EraseStr(s)
+    U64(o, $0)
+  }
+
+  fn _main() {
+    World(w0)
+    PrintStr(w0, Str(_, $"first: ".to_string()), w1)
+    ReadU64(w1, w2, OrZero(_, x))
+    PrintStr(w2, Str(_, $"second: ".to_string()), w3)
+    ReadU64(w3, w4, OrZero(_, y))
+    Add(x, y, z)
+    Print(w4, z, w5)
+    Exit(w5)
+  }
+}
+
+fn main() {
+  let mut net = BasicNet::with_effects(LinkAlloc::new(ArrayBuffer::new(1 << 16)), StdIo);
+  _main().construct(&mut net, &Interactions);
+  reduce_with_stats(&mut net, &Interactions, &mut Stats::default());
+}
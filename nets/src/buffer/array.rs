@@ -33,7 +33,7 @@ impl<T: Deref<Target = [Word]>> Buffer for ArrayBuffer<T> {
       let Range { start, end } = self.buffer_bounds();
       assert!(addr.0 as usize >= start.0 as usize);
       assert!(addr.0 as usize + length.length_bytes as usize <= end.0 as usize);
-      assert!(addr.0 as usize & 0b11 == 0);
+      assert!(addr.0 as usize & (WORD_SIZE - 1) == 0);
     }
   }
 
@@ -45,7 +45,7 @@ impl<T: Deref<Target = [Word]>> Buffer for ArrayBuffer<T> {
 
   #[inline(always)]
   fn read_payload<P>(&self, addr: Addr) -> P {
-    self.assert_valid(addr, Length::of_payload::<P>());
+    self.assert_valid_payload::<P>(addr);
     unsafe { read_unaligned(addr.0 as *mut P) }
   }
 
@@ -56,7 +56,7 @@ impl<T: Deref<Target = [Word]>> Buffer for ArrayBuffer<T> {
 
   #[inline(always)]
   fn len(&self) -> Length {
-    Length::of(self.array.len() as u32)
+    Length::of(self.array.len() as UWord)
   }
 }
 
@@ -69,7 +69,7 @@ impl<T: DerefMut<Target = [Word]>> BufferMut for ArrayBuffer<T> {
 
   #[inline(always)]
   fn write_payload<P>(&mut self, addr: Addr, value: P) {
-    self.assert_valid(addr, Length::of_payload::<P>());
+    self.assert_valid_payload::<P>(addr);
     unsafe { write_unaligned(addr.0 as *mut P, value) }
   }
 
@@ -88,6 +88,15 @@ impl ArrayBuffer<Box<[Word]>> {
 }
 
 impl<T: Deref<Target = [Word]>> ArrayBuffer<T> {
+  #[inline(always)]
+  fn assert_valid_payload<P>(&self, addr: Addr) {
+    safe! {
+      let Range { start, end } = self.buffer_bounds();
+      assert!(addr.0 as usize >= start.0 as usize);
+      assert!(addr.0 as usize + std::mem::size_of::<P>() <= end.0 as usize);
+    }
+  }
+
   pub fn as_ref(&self) -> ArrayBuffer<&[Word]> {
     ArrayBuffer {
       array: &*self.array,
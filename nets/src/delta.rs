@@ -1,20 +1,27 @@
+use crate::*;
 use std::fmt::Debug;
 
 #[derive(Clone, Copy)]
 pub struct Delta {
-  pub(super) offset_bytes: i32,
+  pub(super) offset_bytes: IWord,
 }
 
 impl Delta {
   #[inline(always)]
-  pub const fn of(delta: i32) -> Delta {
+  pub const fn of(words: IWord) -> Delta {
     Delta {
-      offset_bytes: delta << 2,
+      offset_bytes: words << WORD_SHIFT,
     }
   }
   #[inline(always)]
-  pub const fn offset_words(&self) -> i32 {
-    self.offset_bytes >> 2
+  pub const fn of_bytes(bytes: IWord) -> Delta {
+    Delta {
+      offset_bytes: bytes,
+    }
+  }
+  #[inline(always)]
+  pub const fn offset_words(&self) -> IWord {
+    self.offset_bytes >> WORD_SHIFT
   }
 }
 
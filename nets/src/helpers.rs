@@ -27,8 +27,30 @@ pub trait GetKind<I> {
   const KIND: Kind;
 }
 
-pub trait Construct<I> {
-  fn construct<N: Net>(self, net: &mut N, interactions: &I);
+pub trait Construct<I, N: Net> {
+  fn construct(self, net: &mut N, interactions: &I);
+  #[inline(always)]
+  fn construct_near(self, net: &mut N, interactions: &I, near: Addr)
+  where
+    Self: Sized,
+  {
+    let _ = near;
+    self.construct(net, interactions)
+  }
+}
+
+pub trait Layout {
+  const LEN: Length;
+  const IMMEDIATE: bool = false;
+}
+
+// Implemented by agents with one port and one payload.
+pub trait Payload {
+  type Payload: 'static;
+}
+
+pub trait Place<N: Net> {
+  fn place(self, net: &mut N, addr: Addr);
 }
 
 pub trait Destruct {
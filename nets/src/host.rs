@@ -0,0 +1,73 @@
+use std::{
+  any::Any,
+  fmt::Debug,
+  sync::atomic::{AtomicUsize, Ordering},
+};
+
+// Implemented for each `extern struct` agent `F(-T, +R)`; `index` is unique
+// per agent across all modules and dense, so `HostFns` can be a plain table.
+pub trait HostFn: 'static {
+  const NAME: &'static str;
+  type Input: 'static;
+  type Output: 'static;
+  fn index() -> usize;
+}
+
+pub fn next_host_fn_index() -> usize {
+  static NEXT: AtomicUsize = AtomicUsize::new(0);
+  NEXT.fetch_add(1, Ordering::Relaxed)
+}
+
+#[derive(Default)]
+pub struct HostFns {
+  fns: Vec<Option<(&'static str, Box<dyn Any>)>>,
+}
+
+pub trait Host {
+  fn host_fns(&mut self) -> &mut HostFns;
+}
+
+impl Host for HostFns {
+  #[inline(always)]
+  fn host_fns(&mut self) -> &mut HostFns {
+    self
+  }
+}
+
+impl HostFns {
+  pub fn new() -> Self {
+    HostFns::default()
+  }
+
+  pub fn bind<F: HostFn>(mut self, f: impl FnMut(F::Input) -> F::Output + 'static) -> Self {
+    let i = F::index();
+    if i >= self.fns.len() {
+      self.fns.resize_with(i + 1, || None);
+    }
+    let f: Box<dyn FnMut(F::Input) -> F::Output> = Box::new(f);
+    self.fns[i] = Some((F::NAME, Box::new(f)));
+    self
+  }
+
+  #[inline(always)]
+  pub fn call<F: HostFn>(&mut self, a: F::Input) -> F::Output {
+    let Some(Some((_, f))) = self.fns.get_mut(F::index()) else {
+      panic!("no host function bound to `{}`", F::NAME);
+    };
+    // Only `bind::<F>` writes slot `F::index()`, so the type always matches.
+    let f: &mut Box<dyn FnMut(F::Input) -> F::Output> = if cfg!(feature = "unsafe") {
+      unsafe { &mut *(f.as_mut() as *mut dyn Any as *mut _) }
+    } else {
+      f.downcast_mut().unwrap()
+    };
+    f(a)
+  }
+}
+
+impl Debug for HostFns {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_set()
+      .entries(self.fns.iter().flatten().map(|(name, _)| name))
+      .finish()
+  }
+}
@@ -0,0 +1,91 @@
+use crate::*;
+
+pub const BOXED_IMMEDIATE_LEN: Length = Length::of(1).add(Length::of_payload::<u64>());
+
+// Payloads stored as immediate bits. Only integer-like types implement this:
+// every value maps to an integer and back, so no padding, niches or pointers
+// are ever reinterpreted. Bits too large for a word are boxed by `settle`.
+pub trait Immediate: Copy {
+  fn to_bits(self) -> u64;
+  fn from_bits(bits: u64) -> Self;
+}
+
+macro_rules! impl_immediate {
+  ($($ty:ty: $uty:ty),* $(,)?) => {$(
+    impl Immediate for $ty {
+      #[inline(always)]
+      fn to_bits(self) -> u64 {
+        self as $uty as u64
+      }
+      #[inline(always)]
+      fn from_bits(bits: u64) -> Self {
+        bits as $uty as $ty
+      }
+    }
+  )*};
+}
+
+impl_immediate!(
+  u8: u8,
+  u16: u16,
+  u32: u32,
+  u64: u64,
+  usize: usize,
+  i8: u8,
+  i16: u16,
+  i32: u32,
+  i64: u64,
+  isize: usize,
+);
+
+impl Immediate for bool {
+  #[inline(always)]
+  fn to_bits(self) -> u64 {
+    self as u64
+  }
+  #[inline(always)]
+  fn from_bits(bits: u64) -> Self {
+    bits != 0
+  }
+}
+
+impl Immediate for char {
+  #[inline(always)]
+  fn to_bits(self) -> u64 {
+    self as u64
+  }
+  #[inline(always)]
+  fn from_bits(bits: u64) -> Self {
+    char::from_u32(bits as u32).unwrap_or_default()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn round_trip<P: Immediate + PartialEq + std::fmt::Debug>(values: &[P]) {
+    for &value in values {
+      assert_eq!(P::from_bits(value.to_bits()), value);
+    }
+  }
+
+  #[test]
+  fn integer_like_payloads_round_trip() {
+    round_trip(&[0u8, 1, u8::MAX]);
+    round_trip(&[0u32, u32::MAX]);
+    round_trip(&[0u64, u64::MAX]);
+    round_trip(&[i8::MIN, -1, 0, i8::MAX]);
+    round_trip(&[i32::MIN, -1, 0, i32::MAX]);
+    round_trip(&[i64::MIN, -1, 0, i64::MAX]);
+    round_trip(&[false, true]);
+    round_trip(&['\0', 'a', char::MAX]);
+  }
+
+  #[test]
+  fn small_values_stay_small() {
+    assert_eq!((-1i8).to_bits(), 0xff);
+    assert_eq!(true.to_bits(), 1);
+    assert_eq!('a'.to_bits(), 97);
+  }
+}
@@ -1,3 +1,4 @@
+use crate::*;
 use std::{
   fmt::Debug,
   ops::{Add, Sub},
@@ -5,19 +6,19 @@ use std::{
 
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Length {
-  pub(super) length_bytes: u32,
+  pub(super) length_bytes: UWord,
 }
 
 impl Length {
   #[inline(always)]
-  pub const fn of(delta: u32) -> Self {
+  pub const fn of(words: UWord) -> Self {
     Length {
-      length_bytes: delta << 2,
+      length_bytes: words << WORD_SHIFT,
     }
   }
   #[inline(always)]
   pub const fn length_words(&self) -> usize {
-    (self.length_bytes >> 2) as usize
+    (self.length_bytes >> WORD_SHIFT) as usize
   }
   #[inline(always)]
   pub const fn non_zero(&self) -> bool {
@@ -25,7 +26,11 @@ impl Length {
   }
   #[inline(always)]
   pub const fn of_payload<P>() -> Length {
-    Length::of(((std::mem::size_of::<P>() + 1) >> 2) as u32)
+    Length::of_payload_bytes(std::mem::size_of::<P>())
+  }
+  #[inline(always)]
+  pub const fn of_payload_bytes(bytes: usize) -> Length {
+    Length::of(bytes.div_ceil(WORD_SIZE) as UWord)
   }
   #[inline(always)]
   pub const fn add(self, other: Length) -> Length {
@@ -3,10 +3,14 @@ mod alloc;
 mod buffer;
 mod delta;
 mod helpers;
+mod host;
+mod immediate;
 mod kind;
 mod length;
 mod macros;
 mod net;
+mod rules;
+mod suspend;
 mod word;
 
 pub use addr::*;
@@ -14,8 +18,12 @@ pub use alloc::*;
 pub use buffer::*;
 pub use delta::*;
 pub use helpers::*;
+pub use host::*;
+pub use immediate::*;
 pub use kind::*;
 pub use length::*;
 pub use macros::*;
 pub use net::*;
+pub use rules::*;
+pub use suspend::*;
 pub use word::*;
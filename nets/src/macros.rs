@@ -29,6 +29,7 @@ macro_rules! oom {
   };
 }
 
+#[cfg(not(feature = "word64"))]
 #[macro_export]
 macro_rules! u64_0 {
   ($x:literal) => {
@@ -40,6 +41,7 @@ macro_rules! u64_0 {
   };
 }
 
+#[cfg(not(feature = "word64"))]
 #[macro_export]
 macro_rules! u64_1 {
   ($x:literal) => {
@@ -51,6 +53,24 @@ macro_rules! u64_1 {
   };
 }
 
+// With 64-bit words a `u64` fits in `u64_0`; `u64_1` is a padding word, so
+// hand-written layouts keep the same offsets under both word sizes.
+#[cfg(feature = "word64")]
+#[macro_export]
+macro_rules! u64_0 {
+  ($x:literal) => {
+    Word($x as u64)
+  };
+}
+
+#[cfg(feature = "word64")]
+#[macro_export]
+macro_rules! u64_1 {
+  ($x:literal) => {
+    Word::NULL
+  };
+}
+
 #[macro_export]
 macro_rules! const_concat_array {
   ($c:ident = $a:ident + $b:ident) => {
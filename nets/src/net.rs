@@ -1,18 +1,33 @@
 use crate::*;
 use std::{
   fmt::{Debug, Display},
+  task::{Context, Poll},
   time::{Duration, Instant},
 };
 
 pub trait Net: Alloc {
+  type Effects: ?Sized;
   fn link(&mut self, a: LinkHalf, b: LinkHalf);
-  fn reduce(&mut self, interactions: &impl Interactions<Self>) -> bool;
+  fn resolve(&self, half: LinkHalf) -> LinkHalf;
+  fn reduce(&mut self, interactions: &impl Interactions<Self>) -> u64;
+  fn effects(&mut self) -> &mut Self::Effects;
+  fn park(&mut self, redex: Suspended<Self>)
+  where
+    Self: Sized;
+  fn poll_parked(&mut self, cx: &mut Context) -> Poll<Option<Resume<Self>>>
+  where
+    Self: Sized;
 }
 
 #[derive(Debug)]
-pub struct BasicNet<M: Alloc> {
+pub struct BasicNet<M: Alloc, E = ()> {
   pub mem: M,
   pub active: Vec<ActivePair>,
+  pub effects: E,
+  pub parked: Parked<Self>,
+  pub immediate_depth: u32,
+  depth: u32,
+  next: Option<((Kind, Addr), (Kind, Addr))>,
 }
 
 #[derive(Default)]
@@ -21,10 +36,22 @@ pub enum LinkHalf {
   Null,
   From(Addr),
   Kind(Kind),
+  Immediate(Kind, u64),
   Port(Addr, PortMode),
 }
 
-impl<M: Alloc> DelegateAlloc for BasicNet<M> {
+impl LinkHalf {
+  #[inline(always)]
+  fn nil_word(self) -> Word {
+    match self {
+      LinkHalf::Kind(kind) => Word::kind(kind),
+      LinkHalf::Immediate(kind, bits) => Word::immediate(kind, bits),
+      _ => fail!(unreachable!()),
+    }
+  }
+}
+
+impl<M: Alloc, E: Debug> DelegateAlloc for BasicNet<M, E> {
   type Alloc = M;
   #[inline(always)]
   fn delegatee_alloc(&self) -> &Self::Alloc {
@@ -36,46 +63,118 @@ impl<M: Alloc> DelegateAlloc for BasicNet<M> {
   }
 }
 
-impl<M: Alloc> Net for BasicNet<M> {
+impl<M: Alloc, E: Debug> Net for BasicNet<M, E> {
+  type Effects = E;
+
   #[inline(always)]
   fn link(&mut self, a: LinkHalf, b: LinkHalf) {
     let a = self.get_link_half(a);
     let b = self.get_link_half(b);
+    let a = self.settle(a);
+    let b = self.settle(b);
     use LinkHalf::*;
     use PortMode::*;
     match (a, b) {
       (Port(a, Auxiliary), Port(b, Auxiliary)) => self.link_aux_aux(a, b),
       (Port(a, Auxiliary), Port(b, Principal)) => self.link_aux_prn(a, b),
-      (Port(a, Auxiliary), Kind(b)) => self.link_aux_nil(a, b),
       (Port(a, Principal), Port(b, Auxiliary)) => self.link_aux_prn(b, a),
       (Port(a, Principal), Port(b, Principal)) => self.link_prn_prn(a, b),
-      (Port(a, Principal), Kind(b)) => self.link_prn_nil(a, b),
-      (Kind(a), Port(b, Auxiliary)) => self.link_aux_nil(b, a),
-      (Kind(a), Port(b, Principal)) => self.link_prn_nil(b, a),
+      (Port(a, Auxiliary), b) | (b, Port(a, Auxiliary)) => self.link_aux_nil(a, b.nil_word()),
+      (Port(a, Principal), b) | (b, Port(a, Principal)) => self.link_prn_nil(a, b.nil_word()),
       (Kind(a), Kind(b)) => self.link_nil_nil(a, b),
-      _ => fail!(unreachable!()),
+      (a, b) => self.link_imm(a.nil_word(), b.nil_word()),
     }
   }
 
   #[inline(always)]
-  fn reduce(&mut self, interactions: &impl Interactions<Self>) -> bool {
+  fn resolve(&self, half: LinkHalf) -> LinkHalf {
+    self.get_link_half(half)
+  }
+
+  #[inline(always)]
+  fn reduce(&mut self, interactions: &impl Interactions<Self>) -> u64 {
     if let Some(pair) = self.active.pop() {
       let (a, b) = self.resolve_active_pair(pair);
-      let did_reduce = interactions.reduce(self, a, b);
-      debug_assert!(did_reduce);
-      true
+      self.interact(interactions, a, b)
     } else {
-      false
+      0
     }
   }
+
+  #[inline(always)]
+  fn effects(&mut self) -> &mut E {
+    &mut self.effects
+  }
+
+  #[inline(always)]
+  fn park(&mut self, redex: Suspended<Self>) {
+    self.parked.push(redex);
+  }
+
+  #[inline(always)]
+  fn poll_parked(&mut self, cx: &mut Context) -> Poll<Option<Resume<Self>>> {
+    self.parked.poll(cx)
+  }
 }
 
 impl<M: Alloc> BasicNet<M> {
   pub fn new(mem: M) -> Self {
+    BasicNet::with_effects(mem, ())
+  }
+}
+
+impl<M: Alloc, E: Debug> BasicNet<M, E> {
+  pub fn with_effects(mem: M, effects: E) -> Self {
     BasicNet {
       mem,
       active: vec![],
+      effects,
+      parked: Parked::default(),
+      immediate_depth: 0,
+      depth: 0,
+      next: None,
+    }
+  }
+
+  pub fn with_immediate_depth(mut self, depth: u32) -> Self {
+    self.immediate_depth = depth;
+    self
+  }
+
+  // The first pair a rule creates is kept out of `active` and run as soon as
+  // the rule returns, since its remaining links may still write to the ports
+  // of either agent. Up to `immediate_depth` such pairs are chained per pop.
+  #[inline(always)]
+  fn interact(
+    &mut self,
+    interactions: &impl Interactions<Self>,
+    mut a: (Kind, Addr),
+    mut b: (Kind, Addr),
+  ) -> u64 {
+    let mut ops = 0;
+    self.depth = self.immediate_depth;
+    loop {
+      let did_reduce = interactions.reduce(self, a, b);
+      debug_assert!(did_reduce);
+      ops += 1;
+      match self.next.take() {
+        Some(next) => (a, b) = next,
+        None => break,
+      }
     }
+    self.depth = 0;
+    ops
+  }
+
+  #[inline(always)]
+  fn can_chain(&self) -> bool {
+    self.depth != 0 && self.next.is_none()
+  }
+
+  #[inline(always)]
+  fn chain(&mut self, a: (Kind, Addr), b: (Kind, Addr)) {
+    self.depth -= 1;
+    self.next = Some(if a.0 > b.0 { (b, a) } else { (a, b) });
   }
 
   #[inline(always)]
@@ -85,6 +184,10 @@ impl<M: Alloc> BasicNet<M> {
         let word = self.word(addr);
         match word.mode() {
           WordMode::Kind => LinkHalf::Kind(word.as_kind()),
+          WordMode::Immediate => {
+            let (kind, bits) = word.as_immediate();
+            LinkHalf::Immediate(kind, bits)
+          }
           WordMode::Port(mode) => LinkHalf::Port(addr + word.as_port(), mode),
           _ => fail!(unreachable!()),
         }
@@ -93,6 +196,22 @@ impl<M: Alloc> BasicNet<M> {
     }
   }
 
+  // Immediates that don't fit a word (a kind id of 256 or more, or a payload
+  // of `IMMEDIATE_PAYLOAD_BITS` or more bits) are boxed into a nullary agent
+  // whose payload holds the same bits, so `Destruct` can read either form.
+  #[inline(always)]
+  fn settle(&mut self, half: LinkHalf) -> LinkHalf {
+    match half {
+      LinkHalf::Immediate(kind, bits) if !Word::fits_immediate(kind, bits) => {
+        let addr = self.alloc(BOXED_IMMEDIATE_LEN);
+        *self.word_mut(addr) = Word::kind(kind);
+        self.write_payload(addr + Length::of(1), bits);
+        LinkHalf::Port(addr, PortMode::Principal)
+      }
+      half => half,
+    }
+  }
+
   fn link_aux_aux(&mut self, a: Addr, b: Addr) {
     *self.word_mut(a) = Word::port(b - a, PortMode::Auxiliary);
     *self.word_mut(b) = Word::port(a - b, PortMode::Auxiliary);
@@ -102,21 +221,27 @@ impl<M: Alloc> BasicNet<M> {
     *self.word_mut(a) = Word::port(b - a, PortMode::Principal);
   }
 
-  fn link_aux_nil(&mut self, a: Addr, b: Kind) {
-    *self.word_mut(a) = Word::kind(b)
+  fn link_aux_nil(&mut self, a: Addr, b: Word) {
+    *self.word_mut(a) = b
   }
 
   fn link_prn_prn(&mut self, a: Addr, b: Addr) {
+    if self.can_chain() {
+      return self.chain((self.word(a).as_kind(), a), (self.word(b).as_kind(), b));
+    }
     self.active.push(ActivePair(
       Word::port(a - self.origin(), PortMode::Principal),
       Word::port(b - self.origin(), PortMode::Principal),
     ));
   }
 
-  fn link_prn_nil(&mut self, a: Addr, b: Kind) {
+  fn link_prn_nil(&mut self, a: Addr, b: Word) {
+    if self.can_chain() {
+      return self.chain((self.word(a).as_kind(), a), self.resolve_active_half(b));
+    }
     self.active.push(ActivePair(
       Word::port(a - self.origin(), PortMode::Principal),
-      Word::kind(b),
+      b,
     ));
   }
 
@@ -124,10 +249,21 @@ impl<M: Alloc> BasicNet<M> {
     // they just annihilate
   }
 
+  fn link_imm(&mut self, a: Word, b: Word) {
+    if self.can_chain() {
+      return self.chain(self.resolve_active_half(a), self.resolve_active_half(b));
+    }
+    self.active.push(ActivePair(a, b));
+  }
+
   #[inline(always)]
   fn resolve_active_half(&self, word: Word) -> (Kind, Addr) {
     match word.mode() {
       WordMode::Kind => (word.as_kind(), Addr::NULL),
+      WordMode::Immediate => {
+        let (kind, bits) = word.as_immediate();
+        (kind, Addr::immediate(bits))
+      }
       WordMode::Port(PortMode::Principal) => {
         let addr = self.origin() + word.as_port();
         (self.word(addr).as_kind(), addr)
@@ -183,9 +319,106 @@ pub fn reduce_with_stats<N: Net, I: Interactions<N>>(
 ) {
   let start = Instant::now();
   let mut ops = 0;
-  while net.reduce(interactions) {
-    ops += 1;
+  loop {
+    match net.reduce(interactions) {
+      0 => break,
+      n => ops += n,
+    }
   }
   stats.elapsed += Instant::now() - start;
   stats.ops += ops;
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::cell::RefCell;
+
+  const MAX_BITS: u64 = (1 << IMMEDIATE_PAYLOAD_BITS) - 1;
+  const MAX_KIND: u32 = (1 << IMMEDIATE_KIND_BITS) - 1;
+
+  fn cases() -> Vec<(Kind, u64)> {
+    let mut cases = vec![];
+    for kind in [0, 1, MAX_KIND, MAX_KIND + 1, 1000] {
+      for bits in [0, 1, MAX_BITS - 1, MAX_BITS, MAX_BITS + 1, u64::MAX] {
+        cases.push((Kind::of(kind), bits));
+      }
+    }
+    cases
+  }
+
+  fn net() -> BasicNet<impl Alloc> {
+    BasicNet::new(LinkAlloc::new(ArrayBuffer::new(1 << 12)))
+  }
+
+  // Reads an immediate back the way `Destruct` does.
+  fn read(net: &impl Net, (kind, addr): (Kind, Addr)) -> (Kind, u64) {
+    if addr.is_immediate() {
+      (kind, addr.as_immediate())
+    } else {
+      assert_eq!(net.word(addr).as_kind().id, kind.id);
+      (kind, net.read_payload::<u64>(addr + Length::of(1)))
+    }
+  }
+
+  #[test]
+  fn immediate_words_round_trip_up_to_the_limits() {
+    for (kind, bits) in cases() {
+      let fits = kind.id <= MAX_KIND && bits <= MAX_BITS;
+      assert_eq!(Word::fits_immediate(kind, bits), fits, "{kind:?} {bits}");
+      if fits {
+        let (k, b) = Word::immediate(kind, bits).as_immediate();
+        assert_eq!((k.id, b), (kind.id, bits));
+      }
+    }
+  }
+
+  #[test]
+  fn linked_immediates_round_trip() {
+    let mut net = net();
+    for (kind, bits) in cases() {
+      let port = net.alloc(Length::of(1));
+      net.link(
+        LinkHalf::Port(port, PortMode::Auxiliary),
+        LinkHalf::Immediate(kind, bits),
+      );
+      let half = match net.resolve(LinkHalf::From(port)) {
+        LinkHalf::Immediate(k, b) => {
+          assert!(Word::fits_immediate(kind, bits));
+          (k, Addr::immediate(b))
+        }
+        LinkHalf::Port(addr, PortMode::Principal) => {
+          assert!(!Word::fits_immediate(kind, bits));
+          (kind, addr)
+        }
+        _ => panic!("{kind:?} {bits} did not resolve to an agent"),
+      };
+      let (k, b) = read(&net, half);
+      assert_eq!((k.id, b), (kind.id, bits));
+    }
+  }
+
+  #[test]
+  fn active_immediates_round_trip() {
+    struct Record(RefCell<Vec<(Kind, u64)>>);
+    impl<N: Net> Interactions<N> for Record {
+      fn reduce(&self, net: &mut N, a: (Kind, Addr), b: (Kind, Addr)) -> bool {
+        let imm = if a.0.id == 2000 { b } else { a };
+        self.0.borrow_mut().push(read(net, imm));
+        true
+      }
+    }
+    let record = Record(RefCell::new(vec![]));
+    let mut net = net();
+    for (kind, bits) in cases() {
+      let agent = net.alloc_write(&[Word::kind(Kind::of(2000))]);
+      net.link(
+        LinkHalf::Port(agent, PortMode::Principal),
+        LinkHalf::Immediate(kind, bits),
+      );
+      while net.reduce(&record) != 0 {}
+      let (k, b) = record.0.borrow_mut().pop().unwrap();
+      assert_eq!((k.id, b), (kind.id, bits));
+    }
+  }
+}
@@ -0,0 +1,143 @@
+use crate::*;
+
+pub type Rule<I, N> = fn(&I, &mut N, (Kind, Addr), (Kind, Addr));
+
+pub type RuleEntry<I, N> = Option<(RuleKinds, Rule<I, N>)>;
+
+pub enum RuleKinds {
+  Pair(Kind, Kind),
+  Wildcard(Kind),
+}
+
+pub struct RuleTable<I, N, const LEN: usize> {
+  rules: [[Option<Rule<I, N>>; LEN]; LEN],
+}
+
+impl<I, N, const LEN: usize> Default for RuleTable<I, N, LEN> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl<I, N, const LEN: usize> RuleTable<I, N, LEN> {
+  pub const fn new() -> Self {
+    RuleTable {
+      rules: [[None; LEN]; LEN],
+    }
+  }
+
+  pub const fn with(self, rules: &[RuleEntry<I, N>]) -> Self {
+    self.with_pairs(rules).with_wildcards(rules)
+  }
+
+  // Pair rules take precedence over wildcards, so all pairs, including those
+  // of used modules, should be added before any wildcard.
+  pub const fn with_pairs(mut self, rules: &[RuleEntry<I, N>]) -> Self {
+    let mut i = 0;
+    while i < rules.len() {
+      if let Some((RuleKinds::Pair(a, b), rule)) = &rules[i] {
+        if a.id <= b.id {
+          self.insert(a.id as usize, b.id as usize, *rule);
+        }
+      }
+      i += 1;
+    }
+    self
+  }
+
+  // Wildcards only fill the cells of their row and column that no earlier
+  // rule claimed.
+  pub const fn with_wildcards(mut self, rules: &[RuleEntry<I, N>]) -> Self {
+    let mut i = 0;
+    while i < rules.len() {
+      if let Some((RuleKinds::Wildcard(a), rule)) = &rules[i] {
+        let a = a.id as usize;
+        let mut b = 0;
+        while b < LEN {
+          self.insert(a, b, *rule);
+          self.insert(b, a, *rule);
+          b += 1;
+        }
+      }
+      i += 1;
+    }
+    self
+  }
+
+  const fn insert(&mut self, a: usize, b: usize, rule: Rule<I, N>) {
+    if self.rules[a][b].is_none() {
+      self.rules[a][b] = Some(rule);
+    }
+  }
+
+  // Kinds outside of the table, such as those of another program, have no
+  // rules.
+  #[inline(always)]
+  pub fn get(&self, a: Kind, b: Kind) -> Option<Rule<I, N>> {
+    *self.rules.get(a.id as usize)?.get(b.id as usize)?
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  type Log = Vec<&'static str>;
+
+  fn pair(_: &(), log: &mut Log, _: (Kind, Addr), _: (Kind, Addr)) {
+    log.push("pair");
+  }
+
+  fn wildcard(_: &(), log: &mut Log, _: (Kind, Addr), _: (Kind, Addr)) {
+    log.push("wildcard");
+  }
+
+  fn fired(table: &RuleTable<(), Log, 4>, a: u32, b: u32) -> Option<&'static str> {
+    let mut log = vec![];
+    table.get(Kind::of(a), Kind::of(b))?(
+      &(),
+      &mut log,
+      (Kind::of(a), Addr::NULL),
+      (Kind::of(b), Addr::NULL),
+    );
+    log.pop()
+  }
+
+  #[test]
+  fn pairs_take_precedence_over_wildcards() {
+    let table = RuleTable::<(), Log, 4>::new().with(&[
+      Some((RuleKinds::Wildcard(Kind::of(1)), wildcard)),
+      Some((RuleKinds::Pair(Kind::of(1), Kind::of(2)), pair)),
+      Some((RuleKinds::Pair(Kind::of(2), Kind::of(1)), pair)),
+    ]);
+    assert_eq!(fired(&table, 1, 2), Some("pair"));
+    assert_eq!(fired(&table, 0, 1), Some("wildcard"));
+    assert_eq!(fired(&table, 1, 3), Some("wildcard"));
+    assert_eq!(fired(&table, 2, 3), None);
+  }
+
+  #[test]
+  fn kinds_outside_of_the_table_have_no_rules() {
+    let table =
+      RuleTable::<(), Log, 4>::new().with(&[Some((RuleKinds::Wildcard(Kind::of(1)), wildcard))]);
+    assert_eq!(fired(&table, 1, 3), Some("wildcard"));
+    assert_eq!(fired(&table, 1, 4), None);
+    assert_eq!(fired(&table, 4, 1), None);
+  }
+
+  #[test]
+  fn used_pairs_take_precedence_over_local_wildcards() {
+    let local: [RuleEntry<(), Log>; 1] = [Some((RuleKinds::Wildcard(Kind::of(0)), wildcard))];
+    let used: [RuleEntry<(), Log>; 2] = [
+      Some((RuleKinds::Pair(Kind::of(0), Kind::of(3)), pair)),
+      Some((RuleKinds::Pair(Kind::of(3), Kind::of(0)), pair)),
+    ];
+    let table = RuleTable::<(), Log, 4>::new()
+      .with_pairs(&local)
+      .with_pairs(&used)
+      .with_wildcards(&local)
+      .with_wildcards(&used);
+    assert_eq!(fired(&table, 0, 3), Some("pair"));
+    assert_eq!(fired(&table, 0, 2), Some("wildcard"));
+  }
+}
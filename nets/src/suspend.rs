@@ -0,0 +1,77 @@
+use crate::*;
+use std::{
+  fmt::Debug,
+  future::{poll_fn, Future},
+  pin::Pin,
+  task::{Context, Poll},
+};
+
+// Suspended redexes are not `Send`: they hold whatever future the effect
+// returned, and effects are free to return `!Send` ones (`Rc`, borrowed
+// state, ...). A net with parked redexes, and `reduce_async` on it, must stay
+// on the thread that created it, so use a local executor (e.g. `block_on` or a
+// `LocalSet`) and move blocking or `Send` work into a task the effect spawns
+// and wakes from, the way `Timeout` in `bin/libs/time.rs` does.
+pub type Resume<N> = Box<dyn FnOnce(&mut N)>;
+pub type Suspended<N> = Pin<Box<dyn Future<Output = Resume<N>>>>;
+
+pub struct Parked<N: ?Sized> {
+  redexes: Vec<Suspended<N>>,
+}
+
+impl<N: ?Sized> Default for Parked<N> {
+  fn default() -> Self {
+    Parked { redexes: vec![] }
+  }
+}
+
+impl<N: ?Sized> Parked<N> {
+  #[inline(always)]
+  pub fn push(&mut self, redex: Suspended<N>) {
+    self.redexes.push(redex);
+  }
+
+  #[inline(always)]
+  pub fn len(&self) -> usize {
+    self.redexes.len()
+  }
+
+  #[inline(always)]
+  pub fn is_empty(&self) -> bool {
+    self.redexes.is_empty()
+  }
+
+  pub fn poll(&mut self, cx: &mut Context) -> Poll<Option<Resume<N>>> {
+    if self.redexes.is_empty() {
+      return Poll::Ready(None);
+    }
+    for i in 0..self.redexes.len() {
+      if let Poll::Ready(resume) = self.redexes[i].as_mut().poll(cx) {
+        drop(self.redexes.swap_remove(i));
+        return Poll::Ready(Some(resume));
+      }
+    }
+    Poll::Pending
+  }
+}
+
+impl<N: ?Sized> Debug for Parked<N> {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "Parked({})", self.redexes.len())
+  }
+}
+
+// Not `Send`; see `Suspended`.
+pub async fn reduce_async<N: Net, I: Interactions<N>>(
+  net: &mut N,
+  interactions: &I,
+  stats: &mut Stats,
+) {
+  loop {
+    reduce_with_stats(net, interactions, stats);
+    match poll_fn(|cx| net.poll_parked(cx)).await {
+      Some(resume) => resume(net),
+      None => break,
+    }
+  }
+}
@@ -2,24 +2,43 @@ use crate::*;
 use std::{fmt::Debug, mem::size_of};
 
 #[derive(Clone, Copy)]
-pub struct Word(pub u32);
+pub struct Word(pub UWord);
 
 impl Debug for Word {
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
     match self.mode() {
       WordMode::Null => write!(
         f,
-        "{:08x} = Null({:?})",
+        "{:0w$x} = Null({:?})",
         self.0,
-        self.as_null_delta().offset_words()
+        self.as_null_delta().offset_words(),
+        w = WORD_SIZE * 2,
       ),
-      WordMode::Kind => write!(f, "{:08x} = Kind({:?})", self.0, self.as_kind().id),
+      WordMode::Kind => write!(
+        f,
+        "{:0w$x} = Kind({:?})",
+        self.0,
+        self.as_kind().id,
+        w = WORD_SIZE * 2,
+      ),
+      WordMode::Immediate => {
+        let (kind, bits) = self.as_immediate();
+        write!(
+          f,
+          "{:0w$x} = Immediate({:?}, {:?})",
+          self.0,
+          kind.id,
+          bits,
+          w = WORD_SIZE * 2,
+        )
+      }
       WordMode::Port(mode) => write!(
         f,
-        "{:08x} = Port({:?}, {:?})",
+        "{:0w$x} = Port({:?}, {:?})",
         self.0,
         self.as_port().offset_words(),
-        mode
+        mode,
+        w = WORD_SIZE * 2,
       ),
     }
   }
@@ -35,18 +54,41 @@ pub enum PortMode {
 pub(super) enum WordMode {
   Null,
   Kind,
+  Immediate,
   Port(PortMode),
 }
 
-pub const WORD_SIZE: usize = 4;
-const _WORD_SIZE_IS_FOUR: [u8; WORD_SIZE] = [0; size_of::<Word>()];
+// With the `word64` feature, words, deltas and lengths are 64 bits wide,
+// which lifts the few-GiB limit on a single net at the cost of memory.
+#[cfg(not(feature = "word64"))]
+pub type UWord = u32;
+#[cfg(not(feature = "word64"))]
+pub type IWord = i32;
+#[cfg(feature = "word64")]
+pub type UWord = u64;
+#[cfg(feature = "word64")]
+pub type IWord = i64;
+
+pub const WORD_SIZE: usize = size_of::<UWord>();
+pub(super) const WORD_SHIFT: u32 = WORD_SIZE.trailing_zeros();
+const _WORD_SIZE_MATCHES: [u8; WORD_SIZE] = [0; size_of::<Word>()];
+
+// An immediate word packs the mode tag (3 bits), the kind id and the payload
+// bits, so only kinds below 256 with payloads below 2^21 (2^53 with `word64`)
+// fit; `BasicNet` boxes anything larger. Active immediates are handed to rules
+// as `Addr::immediate(bits)`, which needs 2 spare pointer bits.
+pub const IMMEDIATE_KIND_BITS: u32 = 8;
+pub const IMMEDIATE_PAYLOAD_BITS: u32 = UWord::BITS - 3 - IMMEDIATE_KIND_BITS;
 
 impl Word {
   #[inline(always)]
   pub(super) const fn mode(self) -> WordMode {
     match self.0 & 0b11 {
       0 => WordMode::Null,
-      1 => WordMode::Kind,
+      1 => match self.0 & 0b100 {
+        0 => WordMode::Kind,
+        _ => WordMode::Immediate,
+      },
       2 | 3 => WordMode::Port(match self.0 & 0b1 {
         0 => PortMode::Auxiliary,
         1 => PortMode::Principal,
@@ -68,7 +110,7 @@ impl Word {
   pub(super) const fn as_null_delta(self) -> Delta {
     debug_assert!(matches!(self.mode(), WordMode::Null));
     Delta {
-      offset_bytes: self.0 as i32,
+      offset_bytes: self.0 as IWord,
     }
   }
 
@@ -76,16 +118,25 @@ impl Word {
   pub const fn as_port(self) -> Delta {
     debug_assert!(matches!(self.mode(), WordMode::Port(_)));
     Delta {
-      offset_bytes: (self.0 & !0b11) as i32,
+      offset_bytes: (self.0 & !0b11) as IWord,
     }
   }
 
   #[inline(always)]
   pub const fn as_kind(self) -> Kind {
     debug_assert!(matches!(self.mode(), WordMode::Kind));
-    Kind {
-      id: (self.0 >> 2) as u32,
-    }
+    let id: UWord = self.0 >> 3;
+    Kind { id: id as u32 }
+  }
+
+  #[inline(always)]
+  pub const fn as_immediate(self) -> (Kind, u64) {
+    debug_assert!(matches!(self.mode(), WordMode::Immediate));
+    // Typed as `UWord` so that the casts below narrow or widen depending on
+    // the word size.
+    let id: UWord = (self.0 >> 3) & ((1 << IMMEDIATE_KIND_BITS) - 1);
+    let bits: UWord = self.0 >> (3 + IMMEDIATE_KIND_BITS);
+    (Kind { id: id as u32 }, bits as u64)
   }
 }
 
@@ -99,16 +150,27 @@ impl Word {
 
   #[inline(always)]
   pub(super) const fn null_delta(delta: Delta) -> Self {
-    Word(delta.offset_bytes as u32)
+    Word(delta.offset_bytes as UWord)
   }
 
   #[inline(always)]
   pub const fn kind(kind: Kind) -> Self {
-    Word((kind.id as u32) << 2 | 1)
+    Word((kind.id as UWord) << 3 | 0b001)
+  }
+
+  #[inline(always)]
+  pub const fn fits_immediate(kind: Kind, bits: u64) -> bool {
+    kind.id < 1 << IMMEDIATE_KIND_BITS && bits < 1 << IMMEDIATE_PAYLOAD_BITS
+  }
+
+  #[inline(always)]
+  pub const fn immediate(kind: Kind, bits: u64) -> Self {
+    debug_assert!(Word::fits_immediate(kind, bits));
+    Word((bits as UWord) << (3 + IMMEDIATE_KIND_BITS) | (kind.id as UWord) << 3 | 0b101)
   }
 
   #[inline(always)]
   pub const fn port(delta: Delta, mode: PortMode) -> Self {
-    Word((delta.offset_bytes as u32) | 2 | mode as u32)
+    Word((delta.offset_bytes as UWord) | 2 | mode as UWord)
   }
 }
@@ -0,0 +1,56 @@
+use internets_nets::*;
+
+mod common;
+
+interactions! {
+  use common::{FromU64, Print, Succ, Zero};
+
+  pub struct Inc(-Nat, +Nat);
+  #[cfg(any())]
+  pub struct Gone(-Nat, +Nat);
+  #[cfg(all())]
+  pub struct Dec(-Nat, +Nat);
+
+  impl Inc(_, Succ(_, Zero(_))) for Zero(_) {}
+  impl Inc(_, Succ(_, Succ(_, x))) for Succ(_, x) {}
+
+  // Rules between gated agents are gated with them.
+  impl Gone(_, o) for Zero(_) { Zero(o) }
+  impl Gone(_, o) for Succ(_, x) { Succ(o, Gone(x, _)) }
+
+  impl Dec(_, Zero(_)) for Zero(_) {}
+  impl Dec(_, x) for Succ(_, x) {}
+
+  #[cfg(any())]
+  fn _gone() {
+    Gone(Zero(_), o)
+    Print(o, $0)
+  }
+
+  fn _main(n: $u64) {
+    Print(Inc(Dec(FromU64(_, $n), _), _), $0)
+  }
+}
+
+fn kind<T: GetKind<Interactions>>() -> u32 {
+  T::KIND.id
+}
+
+#[test]
+fn gated_agents_take_no_kinds() {
+  assert_eq!(<Interactions as Use>::KIND_COUNT, 2);
+  let inc = kind::<Inc<'static, GetKindMarker>>();
+  let dec = kind::<Dec<'static, GetKindMarker>>();
+  assert_eq!(inc, <Interactions as Use>::KIND_START);
+  assert_eq!(dec, inc + 1);
+}
+
+#[test]
+fn runs_with_gated_agents() {
+  for n in [0, 1, 5] {
+    let mut net = common::net();
+    _main(n).construct(&mut net, &Interactions);
+    reduce_with_stats(&mut net, &Interactions, &mut Stats::default());
+    assert_eq!(net.effects, [n.saturating_sub(1) + 1]);
+  }
+}
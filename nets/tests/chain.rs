@@ -0,0 +1,76 @@
+use internets_nets::*;
+
+mod common;
+
+interactions! {
+  use common::{Clone, Erase, FromU64, Print, Succ, Zero};
+
+  def Add(Nat, Nat) -> Nat {
+    Add(Zero, y) = y;
+    Add(Succ(x), y) = Succ(Add(x, y));
+  }
+
+  // Cloning and erasing `y` makes rules that leave more than one new pair.
+  def Square(Nat, Nat) -> Nat {
+    Square(Zero, y) = Zero;
+    Square(Succ(x), y) = Add(y, Square(x, y));
+  }
+
+  fn _count(n: $u64) {
+    Print(FromU64(_, $n), $0)
+  }
+
+  fn _square(n: $u64) {
+    Print(Square(FromU64(_, $n), FromU64(_, $n), _), $0)
+  }
+}
+
+// Reduces `_count(n)`, returning what was printed and the ops of every pop.
+fn run(n: u64, depth: u32) -> (Vec<u64>, Vec<u64>) {
+  let mut net = common::net().with_immediate_depth(depth);
+  _count(n).construct(&mut net, &Interactions);
+  let mut pops = vec![];
+  loop {
+    match net.reduce(&Interactions) {
+      0 => break,
+      ops => pops.push(ops),
+    }
+  }
+  (net.effects, pops)
+}
+
+#[test]
+fn chains_up_to_the_immediate_depth() {
+  let (unchained, pops) = run(100, 0);
+  assert!(pops.iter().all(|&ops| ops == 1), "{pops:?}");
+  let total: u64 = pops.iter().sum();
+  for depth in [1, 2, 7, 64, 1000] {
+    let (printed, pops) = run(100, depth);
+    assert_eq!(printed, unchained, "depth = {depth}");
+    assert_eq!(pops.iter().sum::<u64>(), total, "depth = {depth}");
+    // Every rule here makes exactly one new pair, so each pop runs a full
+    // chain until the net is done.
+    let (last, full) = pops.split_last().unwrap();
+    assert!(full.iter().all(|&ops| ops == depth as u64 + 1), "{pops:?}");
+    assert!(*last <= depth as u64 + 1, "{pops:?}");
+  }
+}
+
+#[test]
+fn chains_to_the_same_results_at_any_depth() {
+  for depth in [0, 1, 3, 64] {
+    for n in 0..8 {
+      let mut net = common::net().with_immediate_depth(depth);
+      _square(n).construct(&mut net, &Interactions);
+      reduce_with_stats(&mut net, &Interactions, &mut Stats::default());
+      assert_eq!(net.effects, [n * n], "depth = {depth}");
+    }
+  }
+}
+
+#[test]
+fn runs_long_chains_in_a_single_pop() {
+  let (printed, pops) = run(100_000, u32::MAX);
+  assert_eq!(printed, [100_000]);
+  assert_eq!(pops.len(), 1);
+}
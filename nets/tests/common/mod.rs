@@ -0,0 +1,46 @@
+#![allow(dead_code)]
+
+use internets_nets::*;
+
+interactions! {
+  pub effect Output {
+    fn print(&mut self, value: u64);
+  }
+
+  pub struct Erase(-Nat);
+  pub struct Clone(-Nat, +Nat, +Nat);
+
+  #[derive(Erase, Clone)]
+  pub enum Nat {
+    Zero,
+    Succ(-Nat),
+  }
+
+  pub struct FromU64(+Nat, $u64);
+
+  impl FromU64(o, $0) for _ { Zero(o) }
+  impl FromU64(o, $n) for _ { Succ(o, FromU64(_, $n - 1)) }
+
+  // `Print(x, $n)` prints `x + n`. The payload keeps `Print(Zero(_), $0)` an
+  // active pair, where two nullary agents would just annihilate.
+  pub struct Print(-Nat, $u64);
+
+  impl Print(_, $n) for Zero(_) {
+    {
+      effects.print(n);
+    }
+  }
+  impl Print(_, $n) for Succ(_, x) { Print(x, $n + 1) }
+}
+
+impl Output for Vec<u64> {
+  fn print(&mut self, value: u64) {
+    self.push(value);
+  }
+}
+
+pub type TestNet = BasicNet<LinkAlloc<ArrayBuffer<Box<[Word]>>>, Vec<u64>>;
+
+pub fn net() -> TestNet {
+  BasicNet::with_effects(LinkAlloc::new(ArrayBuffer::new(1 << 16)), vec![])
+}
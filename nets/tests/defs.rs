@@ -0,0 +1,55 @@
+use internets_nets::*;
+
+mod common;
+
+interactions! {
+  use common::{Clone, Erase, FromU64, Print, Succ, Zero};
+
+  def Add(Nat, Nat) -> Nat {
+    Add(Zero, y) = y;
+    Add(Succ(x), y) = Succ(Add(x, y));
+  }
+
+  // `x` is used twice, so it is cloned.
+  def Double(Nat) -> Nat {
+    Double(Zero) = Zero;
+    Double(Succ(x)) = Succ(Succ(Add(x, x)));
+  }
+
+  // The nested pattern needs a helper agent.
+  def Half(Nat) -> Nat {
+    Half(Zero) = Zero;
+    Half(Succ(Zero)) = Zero;
+    Half(Succ(Succ(x))) = Succ(Half(x));
+  }
+
+  // `y` is never used, so it is erased.
+  def First(Nat, Nat) -> Nat {
+    First(Zero, y) = Zero;
+    First(Succ(x), y) = Succ(x);
+  }
+
+  fn _main(a: $u64, b: $u64) {
+    Print(Double(FromU64(_, $a), _), $0)
+    Print(Half(FromU64(_, $a), _), $0)
+    Print(First(FromU64(_, $a), FromU64(_, $b), _), $0)
+  }
+}
+
+fn run(a: u64, b: u64) -> Vec<u64> {
+  let mut net = common::net();
+  _main(a, b).construct(&mut net, &Interactions);
+  reduce_with_stats(&mut net, &Interactions, &mut Stats::default());
+  let mut printed = net.effects;
+  printed.sort();
+  printed
+}
+
+#[test]
+fn evaluates_defs() {
+  for a in 0..6 {
+    let mut expected = vec![a * 2, a / 2, a];
+    expected.sort();
+    assert_eq!(run(a, 3), expected, "a = {a}");
+  }
+}
@@ -0,0 +1,70 @@
+use internets_nets::*;
+
+interactions! {
+  pub effect Trace {
+    fn next(&mut self) -> u64;
+    fn log(&mut self, value: u64);
+  }
+
+  pub struct U64(+U64, $u64);
+  pub struct Log(-U64);
+
+  impl Log(_) for U64(_, $n) {
+    {
+      effects.log(n);
+    }
+  }
+
+  // Logs `x`, then makes two copies of a fresh id, logging the id in between.
+  pub struct Fresh(-U64, +U64, +U64);
+
+  impl Fresh(_, a, b) for U64(_, $x) {
+    {
+      effects.log(x);
+    }
+    let id = effects.next();
+    U64(a, $id)
+    {
+      effects.log(id + 100);
+    }
+    U64(b, $id)
+  }
+
+  fn _main(x: $u64) {
+    let y = x * 2;
+    Fresh(U64(_, $y), a, b)
+    Log(a)
+    Log(b)
+  }
+}
+
+#[derive(Debug, Default)]
+struct Recorder {
+  ids: u64,
+  log: Vec<u64>,
+}
+
+impl Trace for Recorder {
+  fn next(&mut self) -> u64 {
+    self.ids += 1;
+    self.ids
+  }
+
+  fn log(&mut self, value: u64) {
+    self.log.push(value);
+  }
+}
+
+// `id` is computed once and shared by both copies, and the statements run in
+// the order they are written, before the agents built after them interact.
+#[test]
+fn runs_statements_in_order_and_shares_lets() {
+  let mut net = BasicNet::with_effects(
+    LinkAlloc::new(ArrayBuffer::new(1 << 12)),
+    Recorder::default(),
+  );
+  _main(3).construct(&mut net, &Interactions);
+  reduce_with_stats(&mut net, &Interactions, &mut Stats::default());
+  assert_eq!(net.effects.ids, 1);
+  assert_eq!(net.effects.log, [6, 101, 1, 1]);
+}
@@ -0,0 +1,50 @@
+use internets_nets::*;
+
+interactions! {
+  pub struct Packed(+Packed, $u8, $u64, $u16, $u8, $u32);
+}
+
+fn construct(net: &mut impl Net, seed: u8) -> Addr {
+  let mut p = LinkHalf::Null;
+  Packed(
+    &mut p,
+    seed,
+    u64::from(seed) << 56 | 0x0001_0203_0405_0607,
+    u16::from(seed) << 8 | 0x09,
+    !seed,
+    u32::from(seed) << 24 | 0x000a_0b0c,
+  )
+  .construct(net, &Interactions);
+  let LinkHalf::Port(addr, PortMode::Principal) = p else {
+    panic!("expected a principal port");
+  };
+  addr
+}
+
+fn destruct(net: &mut impl Net, addr: Addr) -> (u8, u64, u16, u8, u32) {
+  let Packed((), a, b, c, d, e) = <Packed<_> as Destruct>::destruct(net, addr);
+  (a, b, c, d, e)
+}
+
+// Payloads are packed without padding, so each one after the first `u8` sits
+// at an unaligned offset; reading one at the wrong offset, or writing past the
+// agent into its neighbour, changes the values read back.
+#[test]
+fn packed_payloads_round_trip() {
+  let mut net = BasicNet::new(LinkAlloc::new(ArrayBuffer::new(1 << 12)));
+  let addrs = (1..=4)
+    .map(|seed| construct(&mut net, seed))
+    .collect::<Vec<_>>();
+  for (seed, addr) in (1..=4).zip(addrs) {
+    assert_eq!(
+      destruct(&mut net, addr),
+      (
+        seed,
+        u64::from(seed) << 56 | 0x0001_0203_0405_0607,
+        u16::from(seed) << 8 | 0x09,
+        !seed,
+        u32::from(seed) << 24 | 0x000a_0b0c,
+      )
+    );
+  }
+}
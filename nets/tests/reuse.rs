@@ -0,0 +1,87 @@
+use internets_nets::*;
+
+mod common;
+
+interactions! {
+  use common::{FromU64, Print, Succ, Zero};
+
+  pub struct Add(-Nat, -Nat, +Nat);
+
+  // `Add` and `Succ` have the same sizes on both sides, so this rule rewires
+  // the redex in place.
+  impl Add(_, y, o) for Succ(_, x) {
+    Add(x, y, o2)
+    Succ(o, o2)
+  }
+  impl Add(_, y, y) for Zero(_) {}
+
+  fn _sum(a: $u64, b: $u64) {
+    Print(Add(FromU64(_, $a), FromU64(_, $b), _), $0)
+  }
+
+  fn _both(a: $u64, b: $u64) {
+    Print(FromU64(_, $a), $0)
+    Print(FromU64(_, $b), $0)
+  }
+}
+
+// Counts the agents allocated by a `LinkAlloc`.
+#[derive(Debug)]
+struct Counting {
+  alloc: LinkAlloc<ArrayBuffer<Box<[Word]>>>,
+  allocs: usize,
+}
+
+impl DelegateBuffer for Counting {
+  type Buffer = LinkAlloc<ArrayBuffer<Box<[Word]>>>;
+  fn delegatee_buffer(&self) -> &Self::Buffer {
+    &self.alloc
+  }
+}
+
+impl DelegateBufferMut for Counting {
+  fn delegatee_buffer_mut(&mut self) -> &mut Self::Buffer {
+    &mut self.alloc
+  }
+}
+
+impl Alloc for Counting {
+  const PARTIAL_FREE: bool = true;
+  fn alloc(&mut self, len: Length) -> Addr {
+    self.allocs += 1;
+    self.alloc.alloc(len)
+  }
+  fn alloc_near(&mut self, near: Addr, len: Length) -> Addr {
+    self.allocs += 1;
+    self.alloc.alloc_near(near, len)
+  }
+  fn prefers_block(&self, parts: &[Length]) -> bool {
+    self.alloc.prefers_block(parts)
+  }
+  fn free(&mut self, addr: Addr, len: Length) {
+    self.alloc.free(addr, len)
+  }
+}
+
+// Returns the printed values and the number of allocations.
+fn run<C: Construct<Interactions, BasicNet<Counting, Vec<u64>>>>(net: C) -> (Vec<u64>, usize) {
+  let alloc = LinkAlloc::new(ArrayBuffer::new(1 << 16));
+  let mut net_ = BasicNet::with_effects(Counting { alloc, allocs: 0 }, vec![]);
+  net.construct(&mut net_, &Interactions);
+  reduce_with_stats(&mut net_, &Interactions, &mut Stats::default());
+  (net_.effects, net_.mem.allocs)
+}
+
+#[test]
+fn adds_without_allocating() {
+  let (_, base) = run(_sum(0, 3));
+  let (_, both_base) = run(_both(0, 3));
+  for a in 0..8 {
+    let (sum, allocs) = run(_sum(a, 3));
+    assert_eq!(sum, [a + 3]);
+    // Each `Add` step rebuilds an `Add` and a `Succ`, but only building the
+    // inputs costs allocations.
+    let (_, both) = run(_both(a, 3));
+    assert_eq!(allocs - base, both - both_base, "a = {a}");
+  }
+}
@@ -0,0 +1,112 @@
+use internets_nets::*;
+use std::sync::mpsc;
+
+#[path = "../src/bin/libs/mod.rs"]
+#[allow(dead_code)]
+mod libs;
+use libs::stream::Channels;
+
+interactions! {
+  pub effect Log {
+    fn log(&mut self, value: u64);
+  }
+
+  pub struct U64(+U64, $u64);
+  pub struct Unit(+U64);
+  pub struct Show(-U64, $u64);
+
+  impl Show(_, $k) for U64(_, $n) {
+    {
+      effects.log(k + n);
+    }
+  }
+  impl Show(_, $k) for Unit(_) {
+    {
+      effects.log(k);
+    }
+  }
+
+  // `Probe(x, $tag)` has a rule for `U64` and a wildcard rule for anything
+  // else, which hands its partner on to `Show`.
+  pub struct Probe(-U64, $u64);
+
+  impl Probe(_, $tag) for U64(_, $n) {
+    {
+      effects.log(tag * 1000 + n);
+    }
+  }
+  impl Probe(p, $tag) for _ {
+    {
+      effects.log(tag);
+    }
+    Show(p, $10)
+  }
+
+  fn _probes() {
+    Probe(U64(_, $7), $1)
+    Probe(Unit(_), $2)
+  }
+}
+
+mod pipeline {
+  use super::libs;
+  use internets_nets::*;
+
+  interactions! {
+    use libs::std::{Mul, U64};
+    use libs::stream::{End, Next, Sink, Source};
+
+    struct Triple(-Stream, +Stream);
+
+    impl Triple(_, o) for End(_) { End(o) }
+    impl Triple(_, o) for Next(_, x, xs) {
+      Triple(xs, ys)
+      Mul(x, U64(_, $3), y)
+      Next(o, y, ys)
+    }
+
+    pub fn _main() {
+      Source(s, $"numbers")
+      Triple(s, t)
+      Sink(t, $"tripled")
+    }
+  }
+}
+
+#[derive(Debug, Default)]
+struct Logged(Vec<u64>);
+
+impl Log for Logged {
+  fn log(&mut self, value: u64) {
+    self.0.push(value);
+  }
+}
+
+#[test]
+fn prefers_pair_rules_over_wildcard_rules() {
+  let mut net =
+    BasicNet::with_effects(LinkAlloc::new(ArrayBuffer::new(1 << 12)), Logged::default());
+  _probes().construct(&mut net, &Interactions);
+  reduce_with_stats(&mut net, &Interactions, &mut Stats::default());
+  let mut logged = net.effects.0;
+  logged.sort();
+  assert_eq!(logged, [2, 10, 1007]);
+}
+
+fn run_pipeline(numbers: Vec<u64>) -> Vec<u64> {
+  let (sender, receiver) = mpsc::channel();
+  let channels = Channels::new()
+    .source("numbers", numbers)
+    .sink("tripled", sender);
+  let mut net = BasicNet::with_effects(LinkAlloc::new(ArrayBuffer::new(1 << 12)), channels);
+  pipeline::_main().construct(&mut net, &pipeline::Interactions);
+  reduce_with_stats(&mut net, &pipeline::Interactions, &mut Stats::default());
+  drop(net);
+  receiver.into_iter().collect()
+}
+
+#[test]
+fn streams_from_sources_to_sinks_in_order() {
+  assert_eq!(run_pipeline(vec![1, 2, 3, 10]), [3, 6, 9, 30]);
+  assert_eq!(run_pipeline(vec![]), Vec::<u64>::new());
+}